@@ -0,0 +1,163 @@
+//! Embeds the frontend into the binary.
+//!
+//! Every file under `../frontend` (or `MEMORY_MEMO_FRONTEND_DIR`) is
+//! fingerprinted for its ETag and, when it is a compressible type and
+//! compression actually helps, precompressed with gzip and brotli.
+//! Stylesheets and scripts are also embedded under a name carrying their
+//! content hash (`app.3f9c2a1b4d5e.js`), and HTML pages are rewritten to load
+//! those, so browsers can cache them for good. The result is written to
+//! `$OUT_DIR/assets.rs` and included by `src/assets.rs`.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=MEMORY_MEMO_FRONTEND_DIR");
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let frontend_dir = env::var_os("MEMORY_MEMO_FRONTEND_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| manifest_dir.join("../frontend"));
+    println!("cargo:rerun-if-changed={}", frontend_dir.display());
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let compressed_dir = out_dir.join("assets");
+    fs::create_dir_all(&compressed_dir).unwrap();
+
+    let mut files = Vec::new();
+    if frontend_dir.is_dir() {
+        collect_files(&frontend_dir, &frontend_dir, &mut files);
+    } else {
+        println!("cargo:warning=Frontend directory {} not found; no assets embedded", frontend_dir.display());
+    }
+    files.sort();
+
+    let url_paths: Vec<String> = files.iter().map(|relative| relative.to_string_lossy().replace('\\', "/")).collect();
+    let hashed_names: Vec<(String, String)> = files
+        .iter()
+        .zip(&url_paths)
+        .filter(|(_, url_path)| is_fingerprinted(url_path))
+        .map(|(relative, url_path)| {
+            let contents = fs::read(frontend_dir.join(relative)).unwrap();
+            (url_path.clone(), hashed_name(url_path, &contents))
+        })
+        .collect();
+
+    let mut generated = String::from("static ASSETS: &[Asset] = &[\n");
+    for (index, (relative, url_path)) in files.iter().zip(&url_paths).enumerate() {
+        let mut source = frontend_dir.join(relative).canonicalize().unwrap();
+        let mut contents = fs::read(&source).unwrap();
+        if url_path.ends_with(".html") {
+            contents = link_hashed_names(&contents, &hashed_names);
+            source = compressed_dir.join(format!("{}.html", index));
+            fs::write(&source, &contents).unwrap();
+        }
+        let etag = &hex(&Sha256::digest(&contents))[..20];
+
+        let (gzip, brotli) = if is_compressible(url_path) {
+            (
+                write_if_smaller(&compressed_dir, index, "gz", &contents, gzip(&contents)),
+                write_if_smaller(&compressed_dir, index, "br", &contents, brotli(&contents)),
+            )
+        } else {
+            (None, None)
+        };
+
+        let hashed = hashed_names.iter().find(|(original, _)| original == url_path).map(|(_, hashed)| hashed);
+        for path in std::iter::once(url_path).chain(hashed) {
+            generated.push_str(&format!(
+                "    Asset {{ path: {:?}, etag: {:?}, body: include_bytes!({:?}), gzip: {}, brotli: {} }},\n",
+                path,
+                etag,
+                source,
+                include_option(gzip.as_deref()),
+                include_option(brotli.as_deref()),
+            ));
+        }
+    }
+    generated.push_str("];\n");
+
+    fs::write(out_dir.join("assets.rs"), generated).unwrap();
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else {
+            files.push(path.strip_prefix(root).unwrap().to_path_buf());
+        }
+    }
+}
+
+/// Files also served under a content-hashed name
+fn is_fingerprinted(path: &str) -> bool {
+    let extension = path.rsplit('.').next().unwrap_or_default();
+    matches!(extension, "css" | "js" | "mjs")
+}
+
+/// `app.js` becomes `app.3f9c2a1b4d5e.js`
+fn hashed_name(path: &str, contents: &[u8]) -> String {
+    let hash = &hex(&Sha256::digest(contents))[..12];
+    match path.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}.{}.{}", stem, hash, extension),
+        None => format!("{}.{}", path, hash),
+    }
+}
+
+/// Point quoted references to fingerprinted files at their hashed names
+fn link_hashed_names(html: &[u8], hashed_names: &[(String, String)]) -> Vec<u8> {
+    let mut html = String::from_utf8(html.to_vec()).unwrap();
+    for (original, hashed) in hashed_names {
+        html = html.replace(&format!("\"{}\"", original), &format!("\"{}\"", hashed));
+    }
+    html.into_bytes()
+}
+
+fn is_compressible(path: &str) -> bool {
+    let extension = path.rsplit('.').next().unwrap_or_default();
+    matches!(extension, "html" | "css" | "js" | "mjs" | "json" | "svg" | "txt" | "xml" | "webmanifest" | "map")
+}
+
+fn gzip(contents: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(contents).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn brotli(contents: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, 11, 22);
+        encoder.write_all(contents).unwrap();
+    }
+    output
+}
+
+/// Write a compressed variant to `OUT_DIR` unless it is no smaller than the original
+fn write_if_smaller(dir: &Path, index: usize, extension: &str, original: &[u8], compressed: Vec<u8>) -> Option<PathBuf> {
+    if compressed.len() >= original.len() {
+        return None;
+    }
+    let path = dir.join(format!("{}.{}", index, extension));
+    fs::write(&path, compressed).unwrap();
+    Some(path)
+}
+
+fn include_option(path: Option<&Path>) -> String {
+    match path {
+        Some(path) => format!("Some(include_bytes!({:?}))", path),
+        None => "None".to_string(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
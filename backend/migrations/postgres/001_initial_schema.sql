@@ -0,0 +1,69 @@
+-- Memory Memo PostgreSQL schema, equivalent to migrations/sqlite/001-006
+
+CREATE TABLE users (
+    id TEXT PRIMARY KEY,
+    username TEXT UNIQUE NOT NULL CHECK (char_length(username) BETWEEN 3 AND 50),
+    password_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    is_admin BOOLEAN NOT NULL DEFAULT FALSE,
+    disabled_at TIMESTAMPTZ
+);
+
+-- Forever memos table (persistent until manually deleted)
+CREATE TABLE forever_memos (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    content TEXT NOT NULL CHECK (length(content) > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Flush memos table (expire after the configured TTL)
+CREATE TABLE flush_memos (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    content TEXT NOT NULL CHECK (length(content) > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_forever_memos_user_created ON forever_memos (user_id, created_at DESC);
+CREATE INDEX idx_flush_memos_user_created ON flush_memos (user_id, created_at DESC);
+CREATE INDEX idx_flush_memos_expires ON flush_memos (expires_at);
+
+-- Sessions live in memory; on shutdown they are written here and read back on
+-- startup so that a restart does not log everyone out
+CREATE TABLE sessions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    csrf_token TEXT NOT NULL DEFAULT '',
+    remember_me BOOLEAN NOT NULL DEFAULT FALSE,
+    last_seen_at TIMESTAMPTZ,
+    absolute_expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_sessions_expires ON sessions (expires_at);
+
+-- Security-relevant and data-changing events. No foreign keys: the trail must
+-- outlive deleted accounts.
+CREATE TABLE audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    user_id TEXT,
+    actor_id TEXT,
+    ip TEXT,
+    user_agent TEXT,
+    request_id TEXT,
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_audit_events_user_id ON audit_events (user_id, created_at);
+CREATE INDEX idx_audit_events_created_at ON audit_events (created_at);
+
+CREATE TABLE cleanup_logs (
+    id BIGSERIAL PRIMARY KEY,
+    cleaned_count BIGINT NOT NULL,
+    run_time TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Per-user quota overrides set by admins; NULL uses the configured limit, 0 is unlimited
+CREATE TABLE user_quotas (
+    user_id TEXT PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    max_memos BIGINT CHECK (max_memos >= 0),
+    max_flush_memos BIGINT CHECK (max_flush_memos >= 0),
+    max_bytes BIGINT CHECK (max_bytes >= 0)
+);
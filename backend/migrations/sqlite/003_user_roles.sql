@@ -0,0 +1,3 @@
+-- User roles and account status
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN disabled_at DATETIME;
@@ -0,0 +1,6 @@
+-- Sessions live in memory; on shutdown they are written here and read back on
+-- startup so that a restart does not log everyone out
+ALTER TABLE sessions ADD COLUMN csrf_token TEXT NOT NULL DEFAULT '';
+ALTER TABLE sessions ADD COLUMN remember_me BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE sessions ADD COLUMN last_seen_at DATETIME;
+ALTER TABLE sessions ADD COLUMN absolute_expires_at DATETIME;
@@ -0,0 +1,17 @@
+-- Security-relevant and data-changing events. user_id is the account the event
+-- concerns, actor_id whoever caused it when that is someone else (an admin).
+-- No foreign keys: the trail must outlive deleted accounts.
+CREATE TABLE audit_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_type TEXT NOT NULL,
+    user_id TEXT,
+    actor_id TEXT,
+    ip TEXT,
+    user_agent TEXT,
+    request_id TEXT,
+    details TEXT,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_audit_events_user_id ON audit_events(user_id, created_at);
+CREATE INDEX idx_audit_events_created_at ON audit_events(created_at);
@@ -0,0 +1,6 @@
+-- Cleanup runs, previously created on first use by the cleanup job
+CREATE TABLE IF NOT EXISTS cleanup_logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cleaned_count INTEGER NOT NULL,
+    run_time DATETIME NOT NULL
+);
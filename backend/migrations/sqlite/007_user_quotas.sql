@@ -0,0 +1,7 @@
+-- Per-user quota overrides set by admins; NULL uses the configured limit, 0 is unlimited
+CREATE TABLE IF NOT EXISTS user_quotas (
+    user_id TEXT PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    max_memos INTEGER,
+    max_flush_memos INTEGER,
+    max_bytes INTEGER
+);
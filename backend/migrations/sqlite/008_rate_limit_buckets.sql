@@ -0,0 +1,7 @@
+-- Rate limit buckets live in memory; with rate_limit.persist they are written
+-- here on shutdown and read back on startup
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+    key TEXT PRIMARY KEY,
+    tokens REAL NOT NULL,
+    updated_at DATETIME NOT NULL
+);
@@ -24,16 +24,19 @@ echo ""
 
 # Test login
 echo "Testing login..."
-curl -s -X POST http://127.0.0.1:3002/api/auth/login \
+LOGIN_BODY=$(curl -s -X POST http://127.0.0.1:3002/api/auth/login \
   -H "Content-Type: application/json" \
   -b cookies.txt -c cookies.txt \
-  -d '{"username":"testuser","password":"testpass123"}' | jq
+  -d '{"username":"testuser","password":"testpass123"}')
+echo "$LOGIN_BODY" | jq
+CSRF_TOKEN=$(echo "$LOGIN_BODY" | jq -r .csrf_token)
 echo ""
 
 # Test memo creation
 echo "Testing memo creation..."
 curl -s -X POST http://127.0.0.1:3002/api/memos/forever \
   -H "Content-Type: application/json" \
+  -H "X-CSRF-Token: $CSRF_TOKEN" \
   -b cookies.txt \
   -d '{"content":"Test memo"}' | jq
 echo ""
@@ -0,0 +1,932 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    response::Json,
+    routing::{get, post},
+    Extension, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use anyhow::Result;
+use chrono::Duration;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use crate::{
+    api::{
+        client_info::ClientInfo,
+        middleware::{require_admin, require_session, CurrentSession},
+        openapi::{AuditEventsResponse, MessageResponse},
+        validation::{Valid, Validate},
+    },
+    app::AppState,
+    clock::{Clock, OffsetClock},
+    config::{Config, MAX_DURATION_DAYS},
+    models::{
+        audit_event::{AuditEventType, AuditQuery},
+        quota::{QuotaOverride, QuotaStatus},
+        user::UserSummary,
+    },
+    services::{
+        admin::AdminService,
+        backup::{verify_snapshot, BackupInfo, BackupService, SnapshotCheck},
+        cleanup::CleanupStatistics,
+    },
+    validation::{Validation, ValidationErrors},
+};
+
+#[derive(Deserialize, ToSchema)]
+struct ResetPasswordRequest {
+    /// At least 8 characters and at most 1024 bytes
+    #[schema(min_length = 8)]
+    password: String,
+}
+
+impl Validate for ResetPasswordRequest {
+    fn validate(&self, _config: &Config) -> Result<(), ValidationErrors> {
+        Validation::new().new_password("password", &self.password).finish()
+    }
+}
+
+/// Limits are stored as signed 64-bit integers
+impl Validate for QuotaOverride {
+    fn validate(&self, _config: &Config) -> Result<(), ValidationErrors> {
+        let mut validation = Validation::new();
+        for (field, value) in [
+            ("max_memos", self.max_memos),
+            ("max_flush_memos", self.max_flush_memos),
+            ("max_bytes", self.max_bytes),
+        ] {
+            if value.is_some_and(|max| i64::try_from(max).is_err()) {
+                validation = validation.error(field, "out_of_range", format!("{} is out of range", field));
+            }
+        }
+        validation.finish()
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    /// Number of runs, 1 to 500 (default 20)
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct TimeOffsetRequest {
+    /// Seconds to shift the memo clock by, at most ten years either way;
+    /// 0 returns to real time
+    offset_seconds: i64,
+}
+
+impl TimeOffsetRequest {
+    fn offset(&self) -> Option<Duration> {
+        Duration::try_seconds(self.offset_seconds).filter(|offset| offset.num_days().abs() <= MAX_DURATION_DAYS)
+    }
+}
+
+impl Validate for TimeOffsetRequest {
+    fn validate(&self, _config: &Config) -> Result<(), ValidationErrors> {
+        let mut validation = Validation::new();
+        if self.offset().is_none() {
+            validation = validation.error(
+                "offset_seconds",
+                "out_of_range",
+                format!("offset_seconds must be within {} days either way", MAX_DURATION_DAYS),
+            );
+        }
+        validation.finish()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+struct UserListResponse {
+    users: Vec<UserSummary>,
+}
+
+/// A user's quota override and the limits that result from it
+#[derive(Serialize, ToSchema)]
+struct UserQuotaResponse {
+    /// What the admins have set for this user; unset fields use the configured limit
+    #[serde(rename = "override")]
+    quota_override: QuotaOverride,
+    #[serde(flatten)]
+    status: QuotaStatus,
+}
+
+/// An account change that ended the user's sessions
+#[derive(Serialize, ToSchema)]
+struct SessionsRevokedResponse {
+    message: String,
+    revoked_sessions: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct CleanupRunResponse {
+    cleaned_count: u64,
+    #[schema(format = DateTime)]
+    run_time: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct CleanupHistoryResponse {
+    runs: Vec<CleanupRunResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct CleanedCountResponse {
+    cleaned_count: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BackupListResponse {
+    backups: Vec<BackupInfo>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct RestoreResponse {
+    message: String,
+    /// Schema version of the snapshot before it was migrated to the current one
+    schema_version: i64,
+    /// Snapshot of the data as it was just before the restore
+    safety_backup: BackupInfo,
+    revoked_sessions: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ClockResponse {
+    /// Current time on the memo expiry clock
+    #[schema(format = DateTime)]
+    now: String,
+    offset_seconds: i64,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_users, disable_user, enable_user, reset_password, get_user_quota, set_user_quota,
+        cleanup_stats, cleanup_history, run_cleanup, force_cleanup,
+        query_audit_events,
+        list_backups, create_backup, verify_backup, restore_backup,
+        get_clock, set_time_offset,
+    ),
+    components(schemas(
+        ResetPasswordRequest, TimeOffsetRequest, UserListResponse, UserSummary, SessionsRevokedResponse,
+        QuotaOverride, UserQuotaResponse,
+        CleanupStatistics, CleanupRunResponse, CleanupHistoryResponse, CleanedCountResponse,
+        BackupListResponse, BackupInfo, SnapshotCheck, RestoreResponse, ClockResponse,
+    ))
+)]
+pub(crate) struct AdminApi;
+
+pub fn admin_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/users", get(list_users))
+        .route("/users/:user_id/disable", post(disable_user))
+        .route("/users/:user_id/enable", post(enable_user))
+        .route("/users/:user_id/reset-password", post(reset_password))
+        .route("/users/:user_id/quota", get(get_user_quota).put(set_user_quota))
+        .route("/cleanup/stats", get(cleanup_stats))
+        .route("/cleanup/history", get(cleanup_history))
+        .route("/cleanup/run", post(run_cleanup))
+        .route("/cleanup/force", post(force_cleanup))
+        .route("/audit", get(query_audit_events))
+        .route("/backups", get(list_backups).post(create_backup))
+        .route("/backups/:name/verify", post(verify_backup))
+        .route("/backups/:name/restore", post(restore_backup))
+        .route("/debug/clock", get(get_clock).put(set_time_offset))
+        // Layers run bottom-up: authenticate first, then check the role
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_admin))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_session))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/users",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every account with its memo counts", body = UserListResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn list_users(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let admin_service = AdminService::new(state.db);
+
+    match admin_service.list_users().await {
+        Ok(users) => Ok(Json(json!(UserListResponse { users }))),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to fetch users"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{user_id}/disable",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "User disabled and signed out", body = SessionsRevokedResponse),
+        (status = 400, description = "Admins cannot disable themselves", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn disable_user(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path(user_id): Path<String>,
+    Extension(current): Extension<CurrentSession>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    if user_id == current.user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "You cannot disable your own account"})),
+        ));
+    }
+
+    let admin_service = AdminService::new(state.db.clone());
+
+    match admin_service.set_disabled(&user_id, true).await {
+        Ok(true) => {
+            let revoked = state.session_store.remove_user_sessions(&user_id);
+            tracing::info!("Admin {} disabled user {} ({} sessions revoked)", current.user_id, user_id, revoked);
+            let audit = state.audit_service();
+            audit
+                .record(client.event(AuditEventType::AdminUserDisabled).user(&user_id).actor(&current.user_id))
+                .await;
+            audit
+                .record(
+                    client
+                        .event(AuditEventType::SessionsRevoked)
+                        .user(&user_id)
+                        .actor(&current.user_id)
+                        .details(json!({"count": revoked, "reason": "account_disabled"})),
+                )
+                .await;
+            Ok(Json(json!(SessionsRevokedResponse {
+                message: "User disabled".to_string(),
+                revoked_sessions: revoked,
+            })))
+        }
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "User not found"})),
+        )),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to disable user"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{user_id}/enable",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "User enabled", body = MessageResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn enable_user(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path(user_id): Path<String>,
+    Extension(current): Extension<CurrentSession>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let admin_service = AdminService::new(state.db.clone());
+
+    match admin_service.set_disabled(&user_id, false).await {
+        Ok(true) => {
+            tracing::info!("Admin {} enabled user {}", current.user_id, user_id);
+            state
+                .audit_service()
+                .record(client.event(AuditEventType::AdminUserEnabled).user(&user_id).actor(&current.user_id))
+                .await;
+            Ok(Json(json!(MessageResponse { message: "User enabled".to_string() })))
+        }
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "User not found"})),
+        )),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to enable user"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{user_id}/reset-password",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password replaced and the user signed out", body = SessionsRevokedResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 422, description = "Password too short or too long", body = ValidationErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn reset_password(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path(user_id): Path<String>,
+    Extension(current): Extension<CurrentSession>,
+    Valid(payload): Valid<ResetPasswordRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let admin_service = AdminService::with_hasher(state.db.clone(), state.password_hasher.clone());
+
+    match admin_service.reset_password(&user_id, &payload.password).await {
+        Ok(true) => {
+            // Force the user to log in again with the new password
+            let revoked = state.session_store.remove_user_sessions(&user_id);
+            tracing::info!("Admin {} reset the password of user {}", current.user_id, user_id);
+            let audit = state.audit_service();
+            audit
+                .record(
+                    client
+                        .event(AuditEventType::PasswordChanged)
+                        .user(&user_id)
+                        .actor(&current.user_id)
+                        .details(json!({"method": "admin_reset"})),
+                )
+                .await;
+            audit
+                .record(
+                    client
+                        .event(AuditEventType::SessionsRevoked)
+                        .user(&user_id)
+                        .actor(&current.user_id)
+                        .details(json!({"count": revoked, "reason": "password_reset"})),
+                )
+                .await;
+            Ok(Json(json!(SessionsRevokedResponse {
+                message: "Password reset".to_string(),
+                revoked_sessions: revoked,
+            })))
+        }
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "User not found"})),
+        )),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to reset password"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/users/{user_id}/quota",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user's quota override, limits and usage", body = UserQuotaResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn get_user_quota(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    user_quota(&state, &user_id).await
+}
+
+/// Replace the user's quota override. Unset fields fall back to the
+/// configured limits, 0 lifts a limit, and an empty object removes the override.
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/users/{user_id}/quota",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    request_body = QuotaOverride,
+    responses(
+        (status = 200, description = "Override saved", body = UserQuotaResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 422, description = "A limit is out of range", body = ValidationErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn set_user_quota(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path(user_id): Path<String>,
+    Extension(current): Extension<CurrentSession>,
+    Valid(payload): Valid<QuotaOverride>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let admin_service = AdminService::new(state.db.clone());
+
+    match admin_service.set_quota_override(&user_id, &payload).await {
+        Ok(true) => {
+            tracing::info!("Admin {} changed the quota of user {}", current.user_id, user_id);
+            state
+                .audit_service()
+                .record(
+                    client
+                        .event(AuditEventType::AdminQuotaChanged)
+                        .user(&user_id)
+                        .actor(&current.user_id)
+                        .details(json!(payload)),
+                )
+                .await;
+            user_quota(&state, &user_id).await
+        }
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "User not found"})),
+        )),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to update quota"})),
+        )),
+    }
+}
+
+async fn user_quota(state: &AppState, user_id: &str) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let admin_service = AdminService::new(state.db.clone());
+    let quota_override = match admin_service.quota_override(user_id).await {
+        Ok(Some(quota_override)) => quota_override,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, Json(json!({"error": "User not found"})))),
+        Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to fetch quota"})))),
+    };
+
+    match state.memo_service().quota_status(user_id).await {
+        Ok(status) => Ok(Json(json!(UserQuotaResponse { quota_override, status }))),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to fetch quota"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/cleanup/stats",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Flush memo counts and the last cleanup", body = CleanupStatistics),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn cleanup_stats(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let cleanup_service = state.cleanup_service();
+
+    match cleanup_service.get_cleanup_statistics().await {
+        Ok(stats) => Ok(Json(json!(stats))),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to fetch cleanup statistics"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/cleanup/history",
+    tag = "admin",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Recent cleanup runs, newest first", body = CleanupHistoryResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn cleanup_history(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 500);
+    let cleanup_service = state.cleanup_service();
+
+    match cleanup_service.get_cleanup_history(limit).await {
+        Ok(history) => {
+            let runs: Vec<CleanupRunResponse> = history
+                .into_iter()
+                .map(|(cleaned_count, run_time)| CleanupRunResponse {
+                    cleaned_count,
+                    run_time: run_time.to_rfc3339(),
+                })
+                .collect();
+            Ok(Json(json!(CleanupHistoryResponse { runs })))
+        }
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to fetch cleanup history"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/cleanup/run",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Expired flush memos deleted", body = CleanedCountResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn run_cleanup(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(current): Extension<CurrentSession>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let cleanup_service = state.cleanup_service();
+
+    match cleanup_service.cleanup_expired_flush_memos().await {
+        Ok(cleaned_count) => {
+            tracing::info!("Admin {} ran flush memo cleanup", current.user_id);
+            state
+                .audit_service()
+                .record(
+                    client
+                        .event(AuditEventType::AdminCleanupRun)
+                        .actor(&current.user_id)
+                        .details(json!({"cleaned_count": cleaned_count})),
+                )
+                .await;
+            Ok(Json(json!(CleanedCountResponse { cleaned_count })))
+        }
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Cleanup failed"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/cleanup/force",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every flush memo deleted, expired or not", body = CleanedCountResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn force_cleanup(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(current): Extension<CurrentSession>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let cleanup_service = state.cleanup_service();
+
+    match cleanup_service.force_cleanup_all_flush_memos().await {
+        Ok(cleaned_count) => {
+            tracing::warn!("Admin {} force-deleted all flush memos", current.user_id);
+            state
+                .audit_service()
+                .record(
+                    client
+                        .event(AuditEventType::AdminCleanupForced)
+                        .actor(&current.user_id)
+                        .details(json!({"cleaned_count": cleaned_count})),
+                )
+                .await;
+            Ok(Json(json!(CleanedCountResponse { cleaned_count })))
+        }
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Cleanup failed"})),
+        )),
+    }
+}
+
+/// Search the audit log by user, actor, event type and time range
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/audit",
+    tag = "admin",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Matching events, newest first", body = AuditEventsResponse),
+        (status = 400, description = "Unknown event type", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn query_audit_events(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    if let Some(event_type) = &query.event_type {
+        if event_type.parse::<AuditEventType>().is_err() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Unknown event type {}", event_type)})),
+            ));
+        }
+    }
+
+    match state.audit_service().query(&query).await {
+        Ok(events) => Ok(Json(json!(AuditEventsResponse { events }))),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to fetch audit events"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/backups",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Snapshots in the backup directory, newest first", body = BackupListResponse),
+        (status = 501, description = "The database is not SQLite", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn list_backups(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match backup_service(&state)?.list_backups().await {
+        Ok(backups) => Ok(Json(json!(BackupListResponse { backups }))),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to list backups"})),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backups",
+    tag = "admin",
+    responses(
+        (status = 201, description = "Snapshot taken", body = BackupInfo),
+        (status = 501, description = "The database is not SQLite", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn create_backup(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(current): Extension<CurrentSession>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    match backup_service(&state)?.create_backup().await {
+        Ok(backup) => {
+            tracing::info!("Admin {} created backup {}", current.user_id, backup.name);
+            state
+                .audit_service()
+                .record(
+                    client
+                        .event(AuditEventType::AdminBackupCreated)
+                        .actor(&current.user_id)
+                        .details(json!({"name": backup.name})),
+                )
+                .await;
+            Ok((StatusCode::CREATED, Json(json!(backup))))
+        }
+        Err(e) => {
+            tracing::error!("Backup failed: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "Backup failed"})),
+            ))
+        }
+    }
+}
+
+/// Integrity and schema version of a snapshot; 422 when it cannot be restored
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backups/{name}/verify",
+    tag = "admin",
+    params(("name" = String, Path, description = "Backup file name")),
+    responses(
+        (status = 200, description = "The snapshot can be restored", body = SnapshotCheck),
+        (status = 404, description = "No such backup", body = ErrorResponse),
+        (status = 422, description = "The snapshot is damaged or from a newer schema", body = ErrorResponse),
+        (status = 501, description = "The database is not SQLite", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn verify_backup(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let path = backup_service(&state)?.backup_path(&name).map_err(backup_not_found)?;
+
+    match verify_snapshot(&path).await {
+        Ok(check) => match check.ensure_usable() {
+            Ok(()) => Ok(Json(json!(check))),
+            Err(e) => Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"error": e.to_string(), "check": check})),
+            )),
+        },
+        Err(e) => Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+/// Replace the live data with a snapshot. Every session is revoked because
+/// the accounts they belong to may not exist in the restored data.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backups/{name}/restore",
+    tag = "admin",
+    params(("name" = String, Path, description = "Backup file name")),
+    responses(
+        (status = 200, description = "Data replaced and every session revoked", body = RestoreResponse),
+        (status = 404, description = "No such backup", body = ErrorResponse),
+        (status = 422, description = "The snapshot is damaged or from a newer schema", body = ErrorResponse),
+        (status = 501, description = "The database is not SQLite", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn restore_backup(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path(name): Path<String>,
+    Extension(current): Extension<CurrentSession>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let backup_service = backup_service(&state)?;
+    let path = backup_service.backup_path(&name).map_err(backup_not_found)?;
+
+    // A snapshot that fails the checks is the caller's problem, not a server error
+    if let Err(e) = verify_snapshot(&path).await.and_then(|check| check.ensure_usable()) {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": e.to_string()})),
+        ));
+    }
+
+    match backup_service.restore(&path).await {
+        Ok(report) => {
+            let revoked = state.session_store.clear();
+            tracing::warn!("Admin {} restored backup {} ({} sessions revoked)", current.user_id, name, revoked);
+            // Recorded after the restore so the event is not overwritten by it
+            state
+                .audit_service()
+                .record(
+                    client
+                        .event(AuditEventType::AdminBackupRestored)
+                        .actor(&current.user_id)
+                        .details(json!({
+                            "name": name,
+                            "schema_version": report.schema_version,
+                            "safety_backup": report.safety_backup.name,
+                            "revoked_sessions": revoked,
+                        })),
+                )
+                .await;
+            Ok(Json(json!(RestoreResponse {
+                message: "Backup restored".to_string(),
+                schema_version: report.schema_version,
+                safety_backup: report.safety_backup,
+                revoked_sessions: revoked,
+            })))
+        }
+        Err(e) => {
+            tracing::error!("Restore of {} failed: {}", name, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "Restore failed"})),
+            ))
+        }
+    }
+}
+
+/// Snapshots are taken with SQLite's `VACUUM INTO`; PostgreSQL has its own tools
+fn backup_service(state: &AppState) -> Result<BackupService, (StatusCode, Json<Value>)> {
+    state.backup_service().ok_or_else(|| {
+        (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(json!({"error": "Backups are only available with SQLite; use pg_dump for PostgreSQL"})),
+        )
+    })
+}
+
+fn backup_not_found(err: anyhow::Error) -> (StatusCode, Json<Value>) {
+    (StatusCode::NOT_FOUND, Json(json!({"error": err.to_string()})))
+}
+
+/// Current time on the memo expiry clock and its debug offset
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/debug/clock",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The memo clock and its offset", body = ClockResponse),
+        (status = 404, description = "debug.time_offset is disabled", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn get_clock(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let offset = time_offset(&state)?;
+    Ok(Json(clock_response(&offset)))
+}
+
+/// Shift the memo expiry clock, e.g. a day ahead to watch flush memos expire.
+/// An offset of zero returns to real time.
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/debug/clock",
+    tag = "admin",
+    request_body = TimeOffsetRequest,
+    responses(
+        (status = 200, description = "Offset applied", body = ClockResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 422, description = "offset_seconds is out of range", body = ValidationErrorResponse),
+        (status = 404, description = "debug.time_offset is disabled", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Not an admin, or missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
+async fn set_time_offset(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(current): Extension<CurrentSession>,
+    Valid(payload): Valid<TimeOffsetRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let offset = time_offset(&state)?;
+    let new_offset = payload.offset().expect("validated by Valid");
+
+    let previous = offset.offset();
+    offset.set_offset(new_offset);
+    tracing::warn!(
+        "Admin {} shifted the memo clock by {}s (was {}s)",
+        current.user_id,
+        new_offset.num_seconds(),
+        previous.num_seconds()
+    );
+    state
+        .audit_service()
+        .record(
+            client
+                .event(AuditEventType::AdminTimeOffsetChanged)
+                .actor(&current.user_id)
+                .details(json!({
+                    "offset_seconds": new_offset.num_seconds(),
+                    "previous_offset_seconds": previous.num_seconds(),
+                })),
+        )
+        .await;
+    Ok(Json(clock_response(&offset)))
+}
+
+/// The offset clock is only installed when `debug.time_offset` is enabled
+fn time_offset(state: &AppState) -> Result<std::sync::Arc<OffsetClock>, (StatusCode, Json<Value>)> {
+    state.time_offset.clone().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Time offset is disabled; set debug.time_offset to enable it"})),
+        )
+    })
+}
+
+fn clock_response(offset: &OffsetClock) -> Value {
+    json!(ClockResponse {
+        now: offset.now().to_rfc3339(),
+        offset_seconds: offset.offset().num_seconds(),
+    })
+}
@@ -1,76 +1,143 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::StatusCode,
+    middleware,
     response::Json,
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::Cookies;
 use anyhow::Result;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use crate::{
-    database::DatabasePool,
-    services::{auth::AuthService, session::SessionStore},
+    api::{
+        client_info::ClientInfo,
+        middleware::{require_session, CurrentSession},
+        openapi::{AuditEventsResponse, MessageResponse},
+        validation::{Valid, Validate},
+    },
+    app::AppState,
+    models::{audit_event::AuditEventType, quota::QuotaStatus, user::PublicUser, User},
+    config::Config,
+    services::auth::AuthService,
+    storage,
+    validation::{Validation, ValidationErrors},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct RegisterRequest {
+    #[schema(min_length = 3, max_length = 50)]
     username: String,
+    /// At least 8 characters and at most 1024 bytes
+    #[schema(min_length = 8)]
     password: String,
 }
 
-#[derive(Deserialize)]
+impl Validate for RegisterRequest {
+    fn validate(&self, _config: &Config) -> Result<(), ValidationErrors> {
+        Validation::new()
+            .username("username", &self.username)
+            .new_password("password", &self.password)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 struct LoginRequest {
     username: String,
     password: String,
+    /// Keep the session for the remember-me lifetime instead of the browser session
+    #[serde(default)]
+    remember_me: bool,
+}
+
+/// Nothing to check: a login that breaks the registration rules simply fails
+impl Validate for LoginRequest {
+    fn validate(&self, _config: &Config) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ActivityQuery {
+    /// Number of events, 1 to 200 (default 50)
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
 struct UserResponse {
     id: String,
     username: String,
+    #[schema(format = DateTime)]
     created_at: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct LoginResponse {
     user_id: String,
     username: String,
+    /// Send back in the `X-CSRF-Token` header on POST, PUT and DELETE
+    csrf_token: String,
 }
 
-pub fn auth_routes(pool: DatabasePool, session_store: SessionStore) -> Router {
+/// The signed-in user with their quota
+#[derive(Serialize, ToSchema)]
+struct MeResponse {
+    #[serde(flatten)]
+    user: PublicUser,
+    quota: QuotaStatus,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(register, login, logout, me, activity),
+    components(schemas(RegisterRequest, LoginRequest, UserResponse, LoginResponse, PublicUser, MeResponse))
+)]
+pub(crate) struct AuthApi;
+
+pub fn auth_routes(state: AppState) -> Router {
+    let authenticated = Router::new()
+        .route("/logout", post(logout))
+        .route("/me", get(me))
+        .route("/activity", get(activity))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_session));
+
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
-        .route("/logout", post(logout))
-        .route("/me", get(me))
-        .with_state((pool, session_store))
+        .merge(authenticated)
+        .with_state(state)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "Account created", body = UserResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 422, description = "Username or password too short or too long", body = ValidationErrorResponse),
+        (status = 409, description = "Username already exists", body = ErrorResponse),
+    )
+)]
 async fn register(
-    State((pool, _)): State<(DatabasePool, SessionStore)>,
-    Json(payload): Json<RegisterRequest>,
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Valid(payload): Valid<RegisterRequest>,
 ) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
-    // Validation
-    if payload.username.len() < 3 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Username must be at least 3 characters"})),
-        ));
-    }
-    
-    if payload.password.len() < 8 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Password must be at least 8 characters"})),
-        ));
-    }
-
-    let auth_service = AuthService::new(pool);
+    let auth_service = AuthService::with_hasher(state.db.clone(), state.password_hasher.clone());
 
     match auth_service.register(&payload.username, &payload.password).await {
         Ok(user) => {
+            state
+                .audit_service()
+                .record(client.event(AuditEventType::Register).user(&user.id))
+                .await;
+
             let response = UserResponse {
                 id: user.id,
                 username: user.username,
@@ -79,7 +146,7 @@ async fn register(
             Ok((StatusCode::CREATED, Json(json!(response))))
         }
         Err(err) => {
-            if err.to_string().contains("UNIQUE constraint failed") {
+            if err.to_string() == storage::USERNAME_TAKEN {
                 Err((
                     StatusCode::CONFLICT,
                     Json(json!({"error": "Username already exists"})),
@@ -94,25 +161,48 @@ async fn register(
     }
 }
 
+/// Start a session: sets the session and CSRF cookies
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 401, description = "Invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Account is disabled", body = ErrorResponse),
+    )
+)]
 async fn login(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
+    State(state): State<AppState>,
+    client: ClientInfo,
     cookies: Cookies,
-    Json(payload): Json<LoginRequest>,
+    Valid(payload): Valid<LoginRequest>,
 ) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
-    let auth_service = AuthService::new(pool);
+    let auth_service = AuthService::with_hasher(state.db.clone(), state.password_hasher.clone());
 
-    match auth_service.login(&payload.username, &payload.password).await {
+    let result = auth_service.login(&payload.username, &payload.password).await;
+    state.metrics.record_login(result.is_ok());
+    audit_login(&state, &client, &payload, &result).await;
+
+    match result {
         Ok(user) => {
+            tracing::Span::current().record("user_id", user.id.as_str());
+
+            // Rotate: never carry a pre-login session over into the authenticated one
+            if let Some(previous_id) = state.cookie_settings.session_id(&cookies) {
+                state.session_store.remove_session(&previous_id);
+            }
+
             // Create session in store
-            let session_id = session_store.create_session(user.id.clone());
-            let mut cookie = Cookie::new("session_id", session_id);
-            cookie.set_http_only(true);
-            cookie.set_path("/"); // Allow cookie for all paths
-            cookies.add(cookie);
+            let (session_id, session) = state.session_store.create_session(user.id.clone(), payload.remember_me);
+            state.cookie_settings.set_session(&cookies, &session_id, &session);
 
             let response = LoginResponse {
                 user_id: user.id,
                 username: user.username,
+                csrf_token: session.csrf_token,
             };
             Ok((StatusCode::OK, Json(json!(response))))
         }
@@ -122,6 +212,11 @@ async fn login(
                     StatusCode::UNAUTHORIZED,
                     Json(json!({"error": "Invalid credentials"})),
                 ))
+            } else if err.to_string().contains("Account is disabled") {
+                Err((
+                    StatusCode::FORBIDDEN,
+                    Json(json!({"error": "Account is disabled"})),
+                ))
             } else {
                 Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -132,42 +227,121 @@ async fn login(
     }
 }
 
+/// Record a login attempt. Failures are filed under the account when the
+/// username exists, so its owner can see attempts on it.
+async fn audit_login(
+    state: &AppState,
+    client: &ClientInfo,
+    payload: &LoginRequest,
+    result: &Result<PublicUser>,
+) {
+    let event = match result {
+        Ok(user) => client
+            .event(AuditEventType::LoginSucceeded)
+            .user(&user.id)
+            .details(json!({"remember_me": payload.remember_me})),
+        Err(err) => {
+            let reason = if err.to_string().contains("Account is disabled") {
+                "account_disabled"
+            } else if err.to_string().contains("Invalid username or password") {
+                "invalid_credentials"
+            } else {
+                "error"
+            };
+            let mut event = client
+                .event(AuditEventType::LoginFailed)
+                .details(json!({"username": payload.username, "reason": reason}));
+            if let Ok(Some(user)) = User::find_by_username(&state.db, &payload.username).await {
+                event = event.user(user.id);
+            }
+            event
+        }
+    };
+    state.audit_service().record(event).await;
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Session ended and cookies cleared", body = MessageResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
 async fn logout(
-    State((_, session_store)): State<(DatabasePool, SessionStore)>,
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(current): Extension<CurrentSession>,
     cookies: Cookies,
 ) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
-    // Check if session_id cookie exists
-    if let Some(session_cookie) = cookies.get("session_id") {
-        // Remove session from store
-        session_store.remove_session(session_cookie.value());
-        // Remove the session cookie
-        cookies.remove(Cookie::named("session_id"));
-        Ok((StatusCode::OK, Json(json!({"message": "Logged out"}))))
-    } else {
-        Err((
-            StatusCode::UNAUTHORIZED,
-            Json(json!({"error": "No active session"})),
-        ))
-    }
+    // Remove session from store and expire the cookies
+    state.session_store.remove_session(&current.session_id);
+    state.cookie_settings.clear_session(&cookies);
+    state
+        .audit_service()
+        .record(client.event(AuditEventType::Logout).user(&current.user_id))
+        .await;
+    let response = MessageResponse { message: "Logged out".to_string() };
+    Ok((StatusCode::OK, Json(json!(response))))
 }
 
+/// The signed-in user, with the limits on their memos and how much of them is used
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The signed-in user", body = MeResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
 async fn me(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
-    cookies: Cookies,
+    State(state): State<AppState>,
+    Extension(current): Extension<CurrentSession>,
 ) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
-    // Get session from cookie
-    let session_cookie = cookies.get("session_id")
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "No session"}))))?;
-
-    // Get user ID from session
-    let user_id = session_store.get_user_id(session_cookie.value())
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "Invalid session"}))))?;
-
     // Get user info
-    let auth_service = AuthService::new(pool);
-    match auth_service.validate_user(&user_id).await {
-        Ok(Some(user)) => Ok((StatusCode::OK, Json(json!(user)))),
-        Ok(None) => Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "User not found"})))),
+    let auth_service = AuthService::new(state.db.clone());
+    let user = match auth_service.validate_user(&current.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "User not found"})))),
+        Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Internal error"})))),
+    };
+
+    match state.memo_service().quota_status(&user.id).await {
+        Ok(quota) => Ok((StatusCode::OK, Json(json!(MeResponse { user, quota })))),
         Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Internal error"})))),
     }
-}
\ No newline at end of file
+}
+
+/// The caller's own account activity: logins, failed attempts, memo changes
+/// and admin actions on the account, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/activity",
+    tag = "auth",
+    params(ActivityQuery),
+    responses(
+        (status = 200, description = "The caller's audit events", body = AuditEventsResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+async fn activity(
+    State(state): State<AppState>,
+    Extension(current): Extension<CurrentSession>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    match state.audit_service().activity(&current.user_id, limit).await {
+        Ok(events) => Ok(Json(json!(AuditEventsResponse { events }))),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to fetch activity"})),
+        )),
+    }
+}
@@ -0,0 +1,71 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts},
+};
+use crate::{
+    api::middleware::RequestId,
+    app::AppState,
+    models::audit_event::{AuditEventType, NewAuditEvent},
+};
+
+/// Longest user agent kept in the audit log
+const MAX_USER_AGENT_CHARS: usize = 512;
+
+/// Who is on the other end of a request, for the audit log
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl ClientInfo {
+    /// Start an audit event stamped with this client
+    pub fn event(&self, event_type: AuditEventType) -> NewAuditEvent {
+        NewAuditEvent {
+            ip: self.ip.clone(),
+            user_agent: self.user_agent.clone(),
+            request_id: self.request_id.clone(),
+            ..NewAuditEvent::new(event_type)
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let forwarded_for = state
+            .config
+            .server
+            .trust_forwarded_for
+            .then(|| parts.headers.get("x-forwarded-for"))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            // Proxies append the address they saw, so only the last entry was
+            // written by ours; anything before it is whatever the client sent
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+            .map(|ip| ip.to_string());
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|agent| agent.chars().take(MAX_USER_AGENT_CHARS).collect());
+
+        Ok(Self {
+            ip: forwarded_for.or(peer),
+            user_agent,
+            request_id: parts.extensions.get::<RequestId>().map(|RequestId(id)| id.clone()),
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use utoipa::{OpenApi, ToSchema};
+use crate::{app::AppState, services::health::{CheckResult, CheckStatus, ReadinessReport}};
+
+#[derive(Serialize, ToSchema)]
+struct LivenessResponse {
+    #[schema(example = "ok")]
+    status: &'static str,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(health_check, liveness, readiness),
+    components(schemas(LivenessResponse, ReadinessReport, CheckResult, CheckStatus))
+)]
+pub(crate) struct HealthApi;
+
+pub fn health_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(health_check))
+        .route("/live", get(liveness))
+        .route("/ready", get(readiness))
+        .with_state(state)
+}
+
+/// Plain-text check kept for existing monitors
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "The server is up", body = String, content_type = "text/plain"))
+)]
+async fn health_check() -> &'static str {
+    "OK"
+}
+
+/// The process is up and answering requests; touches no dependencies
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, description = "The process is up", body = LivenessResponse))
+)]
+async fn liveness() -> Json<Value> {
+    Json(json!(LivenessResponse { status: "ok" }))
+}
+
+/// The instance can serve traffic: 503 when any check fails
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every check passed or only warned", body = ReadinessReport),
+        (status = 503, description = "Some check failed", body = ReadinessReport),
+    )
+)]
+async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    let report = state.health_service().readiness().await;
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(json!(report)))
+}
@@ -1,41 +1,94 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
+    middleware,
     response::Json,
     routing::{get, post, put, delete},
-    Router,
+    Extension, Router,
 };
-use tower_cookies::Cookies;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use anyhow::Result;
+use utoipa::{OpenApi, ToSchema};
 use crate::{
-    database::DatabasePool,
-    services::{memo::MemoService, session::SessionStore},
-    models::User,
+    api::{
+        client_info::ClientInfo,
+        middleware::{require_session, CurrentSession},
+        validation::{Valid, Validate},
+    },
+    app::AppState,
+    config::Config,
+    models::{audit_event::AuditEventType, quota::QuotaExceeded},
+    validation::{Validation, ValidationErrors},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct CreateMemoRequest {
+    /// Not blank, and at most `memo.max_content_chars` characters (10000 by default)
+    #[schema(min_length = 1)]
     content: String,
 }
 
-#[derive(Deserialize)]
+impl Validate for CreateMemoRequest {
+    fn validate(&self, config: &Config) -> Result<(), ValidationErrors> {
+        Validation::new()
+            .memo_content("content", &self.content, config.memo.max_content_chars)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 struct UpdateMemoRequest {
+    /// Not blank, and at most `memo.max_content_chars` characters (10000 by default)
+    #[schema(min_length = 1)]
     content: String,
 }
 
-#[derive(Serialize)]
+impl Validate for UpdateMemoRequest {
+    fn validate(&self, config: &Config) -> Result<(), ValidationErrors> {
+        Validation::new()
+            .memo_content("content", &self.content, config.memo.max_content_chars)
+            .finish()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 struct MemoResponse {
     id: String,
     content: String,
+    /// `forever` or `flush`
     memo_type: String,
+    #[schema(format = DateTime)]
     created_at: String,
+    #[schema(format = DateTime)]
     updated_at: Option<String>,
+    /// When a flush memo disappears; `null` for forever memos
+    #[schema(format = DateTime)]
     expires_at: Option<String>,
 }
 
-pub fn memo_routes(pool: DatabasePool, session_store: SessionStore) -> Router {
+/// A memo that does not fit the caller's quota
+#[derive(Serialize, ToSchema)]
+struct QuotaExceededResponse {
+    error: String,
+    /// `max_memos`, `max_flush_memos` or `max_bytes`
+    limit: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct MemoListResponse {
+    forever_memos: Vec<MemoResponse>,
+    flush_memos: Vec<MemoResponse>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(list_memos, create_forever_memo, create_flush_memo, update_forever_memo, delete_forever_memo, delete_flush_memo),
+    components(schemas(CreateMemoRequest, UpdateMemoRequest, MemoResponse, MemoListResponse, QuotaExceededResponse))
+)]
+pub(crate) struct MemoApi;
+
+pub fn memo_routes(state: AppState) -> Router {
     Router::new()
         .route("/", get(list_memos))
         .route("/forever", post(create_forever_memo))
@@ -43,42 +96,38 @@ pub fn memo_routes(pool: DatabasePool, session_store: SessionStore) -> Router {
         .route("/forever/:memo_id", put(update_forever_memo))
         .route("/forever/:memo_id", delete(delete_forever_memo))
         .route("/flush/:memo_id", delete(delete_flush_memo))
-        .with_state((pool, session_store))
-}
-
-// Helper function to get user from session
-async fn get_user_from_session(
-    session_store: &SessionStore,
-    cookies: &Cookies,
-) -> Result<String, (StatusCode, Json<Value>)> {
-    let session_cookie = cookies.get("session_id")
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "No session"}))))?;
-
-    // Get user ID from session store
-    let user_id = session_store.get_user_id(session_cookie.value())
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "Invalid session"}))))?;
-
-    Ok(user_id)
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_session))
+        .with_state(state)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/memos/forever",
+    tag = "memos",
+    request_body = CreateMemoRequest,
+    responses(
+        (status = 201, description = "Memo created", body = MemoResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 422, description = "Content is blank or too long", body = ValidationErrorResponse),
+        (status = 409, description = "The caller's memo count or storage limit is reached", body = QuotaExceededResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
 async fn create_forever_memo(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
-    cookies: Cookies,
-    Json(payload): Json<CreateMemoRequest>,
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(current): Extension<CurrentSession>,
+    Valid(payload): Valid<CreateMemoRequest>,
 ) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
-    // Validation
-    if payload.content.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Content cannot be empty"})),
-        ));
-    }
-
-    let user_id = get_user_from_session(&session_store, &cookies).await?;
-    let memo_service = MemoService::new(pool);
+    let user_id = current.user_id;
+    let memo_service = state.memo_service();
 
     match memo_service.create_forever_memo(&user_id, &payload.content).await {
         Ok(memo) => {
+            state.metrics.record_memo_operation("create", "forever");
+            audit_memo(&state, &client, AuditEventType::MemoCreated, &user_id, &memo.id, "forever").await;
             let response = MemoResponse {
                 id: memo.id,
                 content: memo.content,
@@ -89,31 +138,39 @@ async fn create_forever_memo(
             };
             Ok((StatusCode::CREATED, Json(json!(response))))
         }
-        Err(_) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to create memo"})),
-        )),
+        Err(err) => Err(memo_write_error(err, "Failed to create memo")),
     }
 }
 
+/// The memo expires after the configured flush memo lifetime
+#[utoipa::path(
+    post,
+    path = "/api/v1/memos/flush",
+    tag = "memos",
+    request_body = CreateMemoRequest,
+    responses(
+        (status = 201, description = "Memo created", body = MemoResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 422, description = "Content is blank or too long", body = ValidationErrorResponse),
+        (status = 409, description = "The caller's memo count or storage limit is reached", body = QuotaExceededResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
 async fn create_flush_memo(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
-    cookies: Cookies,
-    Json(payload): Json<CreateMemoRequest>,
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(current): Extension<CurrentSession>,
+    Valid(payload): Valid<CreateMemoRequest>,
 ) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
-    // Validation
-    if payload.content.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Content cannot be empty"})),
-        ));
-    }
-
-    let user_id = get_user_from_session(&session_store, &cookies).await?;
-    let memo_service = MemoService::new(pool);
+    let user_id = current.user_id;
+    let memo_service = state.memo_service();
 
     match memo_service.create_flush_memo(&user_id, &payload.content).await {
         Ok(memo) => {
+            state.metrics.record_memo_operation("create", "flush");
+            audit_memo(&state, &client, AuditEventType::MemoCreated, &user_id, &memo.id, "flush").await;
             let response = MemoResponse {
                 id: memo.id,
                 content: memo.content,
@@ -124,19 +181,27 @@ async fn create_flush_memo(
             };
             Ok((StatusCode::CREATED, Json(json!(response))))
         }
-        Err(_) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to create memo"})),
-        )),
+        Err(err) => Err(memo_write_error(err, "Failed to create memo")),
     }
 }
 
+/// The caller's memos, newest first; expired flush memos are left out
+#[utoipa::path(
+    get,
+    path = "/api/v1/memos",
+    tag = "memos",
+    responses(
+        (status = 200, description = "The caller's memos", body = MemoListResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
 async fn list_memos(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
-    cookies: Cookies,
+    State(state): State<AppState>,
+    Extension(current): Extension<CurrentSession>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let user_id = get_user_from_session(&session_store, &cookies).await?;
-    let memo_service = MemoService::new(pool);
+    let user_id = current.user_id;
+    let memo_service = state.memo_service();
 
     match memo_service.list_user_memos(&user_id).await {
         Ok(user_memos) => {
@@ -164,10 +229,7 @@ async fn list_memos(
                 })
                 .collect();
 
-            Ok(Json(json!({
-                "forever_memos": forever_memos,
-                "flush_memos": flush_memos
-            })))
+            Ok(Json(json!(MemoListResponse { forever_memos, flush_memos })))
         }
         Err(_) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -176,25 +238,37 @@ async fn list_memos(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/memos/forever/{memo_id}",
+    tag = "memos",
+    params(("memo_id" = String, Path, description = "Memo ID")),
+    request_body = UpdateMemoRequest,
+    responses(
+        (status = 200, description = "Memo updated", body = MemoResponse),
+        (status = 400, description = "Malformed JSON", body = ErrorResponse),
+        (status = 422, description = "Content is blank or too long", body = ValidationErrorResponse),
+        (status = 409, description = "The longer content exceeds the caller's storage limit", body = QuotaExceededResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+        (status = 404, description = "No such memo of the caller's", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
 async fn update_forever_memo(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
+    State(state): State<AppState>,
+    client: ClientInfo,
     Path(memo_id): Path<String>,
-    cookies: Cookies,
-    Json(payload): Json<UpdateMemoRequest>,
+    Extension(current): Extension<CurrentSession>,
+    Valid(payload): Valid<UpdateMemoRequest>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Validation
-    if payload.content.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Content cannot be empty"})),
-        ));
-    }
-
-    let user_id = get_user_from_session(&session_store, &cookies).await?;
-    let memo_service = MemoService::new(pool);
+    let user_id = current.user_id;
+    let memo_service = state.memo_service();
 
     match memo_service.update_forever_memo(&memo_id, &user_id, &payload.content).await {
         Ok(Some(memo)) => {
+            state.metrics.record_memo_operation("update", "forever");
+            audit_memo(&state, &client, AuditEventType::MemoUpdated, &user_id, &memo.id, "forever").await;
             let response = MemoResponse {
                 id: memo.id,
                 content: memo.content,
@@ -209,23 +283,38 @@ async fn update_forever_memo(
             StatusCode::NOT_FOUND,
             Json(json!({"error": "Memo not found"})),
         )),
-        Err(_) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to update memo"})),
-        )),
+        Err(err) => Err(memo_write_error(err, "Failed to update memo")),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/memos/forever/{memo_id}",
+    tag = "memos",
+    params(("memo_id" = String, Path, description = "Memo ID")),
+    responses(
+        (status = 204, description = "Memo deleted"),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+        (status = 404, description = "No such memo of the caller's", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
 async fn delete_forever_memo(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
+    State(state): State<AppState>,
+    client: ClientInfo,
     Path(memo_id): Path<String>,
-    cookies: Cookies,
+    Extension(current): Extension<CurrentSession>,
 ) -> Result<StatusCode, (StatusCode, Json<Value>)> {
-    let user_id = get_user_from_session(&session_store, &cookies).await?;
-    let memo_service = MemoService::new(pool);
+    let user_id = current.user_id;
+    let memo_service = state.memo_service();
 
     match memo_service.delete_forever_memo(&memo_id, &user_id).await {
-        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(true) => {
+            state.metrics.record_memo_operation("delete", "forever");
+            audit_memo(&state, &client, AuditEventType::MemoDeleted, &user_id, &memo_id, "forever").await;
+            Ok(StatusCode::NO_CONTENT)
+        }
         Ok(false) => Err((
             StatusCode::NOT_FOUND,
             Json(json!({"error": "Memo not found"})),
@@ -237,16 +326,34 @@ async fn delete_forever_memo(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/memos/flush/{memo_id}",
+    tag = "memos",
+    params(("memo_id" = String, Path, description = "Memo ID")),
+    responses(
+        (status = 204, description = "Memo deleted"),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+        (status = 404, description = "No such memo of the caller's", body = ErrorResponse),
+    ),
+    security(("session" = [], "csrf" = []))
+)]
 async fn delete_flush_memo(
-    State((pool, session_store)): State<(DatabasePool, SessionStore)>,
+    State(state): State<AppState>,
+    client: ClientInfo,
     Path(memo_id): Path<String>,
-    cookies: Cookies,
+    Extension(current): Extension<CurrentSession>,
 ) -> Result<StatusCode, (StatusCode, Json<Value>)> {
-    let user_id = get_user_from_session(&session_store, &cookies).await?;
-    let memo_service = MemoService::new(pool);
+    let user_id = current.user_id;
+    let memo_service = state.memo_service();
 
     match memo_service.delete_flush_memo(&memo_id, &user_id).await {
-        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(true) => {
+            state.metrics.record_memo_operation("delete", "flush");
+            audit_memo(&state, &client, AuditEventType::MemoDeleted, &user_id, &memo_id, "flush").await;
+            Ok(StatusCode::NO_CONTENT)
+        }
         Ok(false) => Err((
             StatusCode::NOT_FOUND,
             Json(json!({"error": "Memo not found"})),
@@ -256,4 +363,33 @@ async fn delete_flush_memo(
             Json(json!({"error": "Failed to delete memo"})),
         )),
     }
-}
\ No newline at end of file
+}
+
+/// 409 for a write refused by the quota, 500 with `message` otherwise
+fn memo_write_error(err: anyhow::Error, message: &str) -> (StatusCode, Json<Value>) {
+    match err.downcast_ref::<QuotaExceeded>() {
+        Some(exceeded) => {
+            let response = QuotaExceededResponse {
+                error: exceeded.to_string(),
+                limit: exceeded.limit.to_string(),
+            };
+            (StatusCode::CONFLICT, Json(json!(response)))
+        }
+        None => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": message}))),
+    }
+}
+
+async fn audit_memo(
+    state: &AppState,
+    client: &ClientInfo,
+    event_type: AuditEventType,
+    user_id: &str,
+    memo_id: &str,
+    memo_type: &str,
+) {
+    let event = client
+        .event(event_type)
+        .user(user_id)
+        .details(json!({"memo_id": memo_id, "memo_type": memo_type}));
+    state.audit_service().record(event).await;
+}
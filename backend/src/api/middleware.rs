@@ -1,13 +1,187 @@
-// Placeholder for middleware - authentication, session management, etc.
 use axum::{
-    extract::Request,
-    http::StatusCode,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Json, Response},
 };
+use serde_json::{json, Value};
+use tower_cookies::Cookies;
+use crate::{
+    api::{
+        client_info::ClientInfo,
+        session_cookie::{tokens_match, CSRF_HEADER},
+    },
+    app::AppState,
+    models::User,
+    services::rate_limit::RouteClass,
+};
+
+/// The authenticated session, inserted into request extensions by [`require_session`]
+#[derive(Clone, Debug)]
+pub struct CurrentSession {
+    pub session_id: String,
+    pub user_id: String,
+}
+
+/// Header carrying the request ID, both on requests and responses
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Burst size of the client's bucket for this kind of request
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "ratelimit-limit";
+
+/// Requests the client can still make at once
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "ratelimit-remaining";
+
+/// Seconds until the client's bucket is full again
+pub const RATE_LIMIT_RESET_HEADER: &str = "ratelimit-reset";
+
+/// ID correlating every log line of one request, inserted into request
+/// extensions by [`request_id`]
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+/// Request ID middleware, layered outside the trace layer so its span can record the ID.
+///
+/// Keeps an incoming `X-Request-Id` set by a proxy when it looks like an ID
+/// (up to 128 characters of `[A-Za-z0-9._-]`), otherwise generates a UUID,
+/// and echoes the ID in the response.
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let header = HeaderValue::from_str(&id).expect("request ID is a valid header value");
+
+    request.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+    request.extensions_mut().insert(RequestId(id));
+
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    response
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Rate limiting middleware for the API routes.
+///
+/// Requests count against the signed-in user when the session cookie is
+/// valid, otherwise against the client's IP address, with separate buckets
+/// for reads and writes. Behind a proxy the address is the one it appended
+/// to `X-Forwarded-For` (see [`ClientInfo`]), so clients cannot get fresh
+/// buckets by sending made-up entries. Responses carry the `RateLimit-*`
+/// headers; a refused request gets 429 with `Retry-After`.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    cookies: Cookies,
+    request: Request,
+    next: Next,
+) -> Response {
+    let user_id = state
+        .cookie_settings
+        .session_id(&cookies)
+        .and_then(|session_id| state.session_store.get_user_id(&session_id));
+    let client_key = match user_id {
+        Some(user_id) => format!("user:{}", user_id),
+        None => format!("ip:{}", client.ip.as_deref().unwrap_or("unknown")),
+    };
+    let class = if request.method().is_safe() { RouteClass::Read } else { RouteClass::Write };
+    let decision = state.rate_limiter.check(&client_key, class);
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        tracing::warn!(client = %client_key, class = class.as_str(), "Rate limit exceeded");
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({"error": "Too many requests"})),
+        )
+            .into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(decision.retry_after_seconds));
+        response
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(decision.limit));
+    headers.insert(RATE_LIMIT_REMAINING_HEADER, HeaderValue::from(decision.remaining));
+    headers.insert(RATE_LIMIT_RESET_HEADER, HeaderValue::from(decision.reset_seconds));
+    response
+}
+
+/// Session authentication middleware.
+///
+/// Rejects requests without a valid session cookie and extends the idle
+/// expiry of valid sessions on every request. Also rejects unsafe
+/// methods (POST/PUT/DELETE/...) whose `X-CSRF-Token` header does not match
+/// the token bound to the session.
+pub async fn require_session(
+    State(state): State<AppState>,
+    cookies: Cookies,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let session_id = state.cookie_settings.session_id(&cookies)
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "No session"}))))?;
+
+    let session = state.session_store.touch_session(&session_id)
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "Invalid session"}))))?;
+
+    if !request.method().is_safe() {
+        let csrf_valid = request
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|token| tokens_match(token, &session.csrf_token));
+
+        if !csrf_valid {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(json!({"error": "Missing or invalid CSRF token"})),
+            ));
+        }
+    }
+
+    // Ties every log line of the request, services included, to the user
+    tracing::Span::current().record("user_id", session.user_id.as_str());
+
+    request.extensions_mut().insert(CurrentSession {
+        session_id,
+        user_id: session.user_id,
+    });
 
-// Session authentication middleware (placeholder)
-pub async fn auth_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    // TODO: Implement session authentication
     Ok(next.run(request).await)
-}
\ No newline at end of file
+}
+
+/// Admin authorization middleware, layered inside [`require_session`].
+///
+/// The role is re-read from the database on every request so that revoking
+/// it takes effect immediately.
+pub async fn require_admin(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let current = request.extensions().get::<CurrentSession>().cloned()
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "No session"}))))?;
+
+    let user = User::find_by_id(&state.db, &current.user_id)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Internal error"}))))?;
+
+    match user {
+        Some(user) if user.is_admin && !user.is_disabled() => Ok(next.run(request).await),
+        _ => Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "Admin privileges required"})),
+        )),
+    }
+}
@@ -1,6 +1,18 @@
+pub mod admin;
 pub mod auth;
+pub mod client_info;
+pub mod health;
 pub mod memo;
+pub mod metrics;
 pub mod middleware;
+pub mod openapi;
+pub mod security_headers;
+pub mod session_cookie;
+pub mod validation;
+pub mod version;
 
+pub use admin::*;
 pub use auth::*;
-pub use memo::*;
\ No newline at end of file
+pub use health::*;
+pub use memo::*;
+pub use metrics::*;
\ No newline at end of file
@@ -0,0 +1,146 @@
+//! OpenAPI document built from the handlers' `#[utoipa::path]` annotations
+//! and their request and response types. Each API module describes its own
+//! routes; [`openapi`] merges them.
+
+use axum::{response::Json, routing::get, Router};
+use serde::Serialize;
+use utoipa::{
+    openapi::{
+        header::Header,
+        schema::{ObjectBuilder, SchemaType},
+        security::{ApiKey, ApiKeyValue, SecurityScheme},
+        Components, Content, OpenApi as OpenApiDocument, Ref, ResponseBuilder, Server,
+    },
+    Modify, OpenApi, ToSchema,
+};
+use utoipa_swagger_ui::{Config, SwaggerUi};
+use crate::{
+    api::{
+        admin::AdminApi, auth::AuthApi, health::HealthApi, memo::MemoApi, session_cookie::SESSION_COOKIE,
+        validation::ValidationErrorResponse,
+    },
+    models::{
+        quota::{QuotaLimits, QuotaStatus, QuotaUsage},
+        AuditEvent,
+    },
+    validation::FieldError,
+};
+
+/// Where the document is served, relative to the base path
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Where Swagger UI is served, relative to the base path
+pub const DOCS_PATH: &str = "/api/docs";
+
+/// Body of every error response
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// Body of responses that only confirm an action
+#[derive(Serialize, ToSchema)]
+pub struct MessageResponse {
+    pub message: String,
+}
+
+/// Audit events, newest first
+#[derive(Serialize, ToSchema)]
+pub struct AuditEventsResponse {
+    pub events: Vec<AuditEvent>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Memory Memo API",
+        description = "Personal memo application with forever and flush memo types. \
+                       The unversioned /api/... paths are deprecated aliases of /api/v1.",
+        version = "1.0.0",
+    ),
+    components(schemas(
+        ErrorResponse, MessageResponse, ValidationErrorResponse, FieldError, AuditEventsResponse, AuditEvent,
+        QuotaStatus, QuotaLimits, QuotaUsage,
+    )),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "auth", description = "Accounts and sessions"),
+        (name = "memos", description = "The signed-in user's memos"),
+        (name = "admin", description = "Administration; requires an admin account"),
+        (name = "health", description = "Liveness and readiness probes"),
+    )
+)]
+struct ApiDoc;
+
+/// The `session` and `csrf` schemes named by the handlers' `security(...)`
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let components = openapi.components.get_or_insert_with(Components::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                SESSION_COOKIE,
+                "Session cookie set by login",
+            ))),
+        );
+        components.add_security_scheme(
+            "csrf",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-CSRF-Token",
+                "The csrf_token returned by login, required on POST, PUT and DELETE",
+            ))),
+        );
+    }
+}
+
+/// The 429 every API operation can answer when rate limiting is enabled.
+/// The probes are not rate limited. Applied after the modules' paths are
+/// merged, since `modifiers(...)` only sees the paths of [`ApiDoc`].
+struct RateLimitResponses;
+
+impl Modify for RateLimitResponses {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let response = ResponseBuilder::new()
+            .description("Too many requests; retry after the number of seconds in Retry-After")
+            .header("Retry-After", Header::new(ObjectBuilder::new().schema_type(SchemaType::Integer)))
+            .content("application/json", Content::new(Ref::from_schema_name("ErrorResponse")))
+            .build();
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if !path.starts_with("/api/") {
+                continue;
+            }
+            for operation in item.operations.values_mut() {
+                operation.responses.responses.insert("429".to_string(), response.clone().into());
+            }
+        }
+    }
+}
+
+/// The complete API description, with paths relative to the base path
+pub fn openapi() -> OpenApiDocument {
+    let mut document = ApiDoc::openapi();
+    // utoipa copies the crate's license, and this crate declares none
+    document.info.license = None;
+    for part in [AuthApi::openapi(), MemoApi::openapi(), AdminApi::openapi(), HealthApi::openapi()] {
+        document.merge(part);
+    }
+    RateLimitResponses.modify(&mut document);
+    document
+}
+
+/// Routes serving the document and Swagger UI. The paths include the base
+/// path because Swagger UI redirects to absolute URLs, so these routes are
+/// merged after the rest of the app has been nested under it.
+pub fn openapi_routes(base_path: &str) -> Router {
+    let mut document = openapi();
+    if !base_path.is_empty() {
+        document.servers = Some(vec![Server::new(base_path)]);
+    }
+    let document_url = format!("{}{}", base_path, OPENAPI_PATH);
+
+    Router::new()
+        .route(&document_url, get(move || async move { Json(document) }))
+        .merge(SwaggerUi::new(format!("{}{}", base_path, DOCS_PATH)).config(Config::new([document_url])))
+}
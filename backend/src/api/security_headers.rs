@@ -0,0 +1,198 @@
+//! Security headers sent with every response, and the endpoint browsers
+//! report Content-Security-Policy violations to.
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Request, State},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use anyhow::{Context, Result};
+use crate::{api::openapi::DOCS_PATH, config::Config};
+
+/// Where browsers send violation reports, relative to the base path
+pub const CSP_REPORT_PATH: &str = "/api/csp-report";
+
+/// Largest report accepted; real ones are well under 2 KiB
+const MAX_REPORT_BYTES: usize = 16 * 1024;
+
+/// Violations logged per report; browsers batch a handful at most
+const MAX_LOGGED_VIOLATIONS: usize = 5;
+
+/// Longest URL or directive logged, in characters
+const MAX_LOGGED_FIELD_CHARS: usize = 200;
+
+/// Name of the reporting endpoint in `Reporting-Endpoints` and `report-to`
+const REPORT_GROUP: &str = "csp";
+
+/// Policy for `index.html` and `app.js`: everything from the app itself,
+/// except the Google Fonts stylesheet and font files. No inline scripts or
+/// styles.
+const FRONTEND_POLICY: &str = "default-src 'self'; script-src 'self'; \
+     style-src 'self' https://fonts.googleapis.com; font-src https://fonts.gstatic.com; img-src 'self'; \
+     connect-src 'self'; object-src 'none'; base-uri 'self'; form-action 'self'";
+
+/// Policy for Swagger UI, which sets inline styles and uses data: images.
+/// Its scripts are files, so they stay restricted to the app.
+const DOCS_POLICY: &str = "default-src 'self'; script-src 'self'; style-src 'self' 'unsafe-inline'; \
+     img-src 'self' data:; connect-src 'self'; object-src 'none'; base-uri 'self'; form-action 'self'";
+
+/// Header values built once from `[security_headers]`
+#[derive(Clone)]
+pub struct SecurityHeaders {
+    /// `Content-Security-Policy`, or its report-only variant
+    csp_header: HeaderName,
+    frontend_policy: HeaderValue,
+    docs_policy: HeaderValue,
+    /// Requests under this path get `docs_policy`
+    docs_path: String,
+    reporting_endpoints: HeaderValue,
+    referrer_policy: HeaderValue,
+    /// `DENY` unless some origin may frame the app
+    frame_options: Option<HeaderValue>,
+    hsts: Option<HeaderValue>,
+}
+
+impl SecurityHeaders {
+    pub fn new(config: &Config) -> Result<Self> {
+        let settings = &config.security_headers;
+        let base_path = &config.server.base_path;
+        let report_path = format!("{}{}", base_path, CSP_REPORT_PATH);
+
+        let frame_ancestors = if settings.frame_ancestors.is_empty() {
+            "'none'".to_string()
+        } else {
+            settings.frame_ancestors.join(" ")
+        };
+        // The report-uri is for browsers without the Reporting API
+        let built_in = |policy: &str| {
+            format!(
+                "{}; frame-ancestors {}; report-uri {}; report-to {}",
+                policy, frame_ancestors, report_path, REPORT_GROUP
+            )
+        };
+        let frontend_policy = match &settings.content_security_policy {
+            Some(policy) => policy.clone(),
+            None => built_in(FRONTEND_POLICY),
+        };
+
+        Ok(Self {
+            csp_header: if settings.csp_report_only {
+                header::CONTENT_SECURITY_POLICY_REPORT_ONLY
+            } else {
+                header::CONTENT_SECURITY_POLICY
+            },
+            frontend_policy: frontend_policy
+                .parse()
+                .context("security_headers.content_security_policy is not a valid header value")?,
+            docs_policy: built_in(DOCS_POLICY).parse()?,
+            docs_path: format!("{}{}", base_path, DOCS_PATH),
+            reporting_endpoints: format!("{}=\"{}\"", REPORT_GROUP, report_path).parse()?,
+            referrer_policy: settings.referrer_policy.parse()?,
+            frame_options: settings
+                .frame_ancestors
+                .is_empty()
+                .then(|| HeaderValue::from_static("DENY")),
+            hsts: config
+                .hsts()
+                .then(|| format!("max-age={}", settings.hsts_max_age_seconds).parse())
+                .transpose()?,
+        })
+    }
+}
+
+/// Add the security headers to every response
+pub async fn security_headers(State(settings): State<SecurityHeaders>, request: Request, next: Next) -> Response {
+    let policy = if request.uri().path().starts_with(&settings.docs_path) {
+        settings.docs_policy.clone()
+    } else {
+        settings.frontend_policy.clone()
+    };
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    headers.insert(settings.csp_header.clone(), policy);
+    headers.insert(
+        HeaderName::from_static("reporting-endpoints"),
+        settings.reporting_endpoints.clone(),
+    );
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(header::REFERRER_POLICY, settings.referrer_policy.clone());
+    if let Some(frame_options) = &settings.frame_options {
+        headers.insert(header::X_FRAME_OPTIONS, frame_options.clone());
+    }
+    if let Some(hsts) = &settings.hsts {
+        headers.insert(header::STRICT_TRANSPORT_SECURITY, hsts.clone());
+    }
+    response
+}
+
+pub fn csp_report_routes() -> Router {
+    Router::new()
+        .route(CSP_REPORT_PATH, post(csp_report))
+        .layer(DefaultBodyLimit::max(MAX_REPORT_BYTES))
+}
+
+/// Log the violations in a report. Browsers send either a single
+/// `{"csp-report": {...}}` (`report-uri`) or a list of Reporting API
+/// reports (`report-to`), whatever the content type says.
+///
+/// Anyone can post here, so only the first few violations are logged, with
+/// their fields shortened.
+async fn csp_report(body: Bytes) -> StatusCode {
+    let Ok(report) = serde_json::from_slice::<Value>(&body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let violations: Vec<[Value; 3]> = match report {
+        Value::Array(reports) => reports
+            .into_iter()
+            .filter(|report| report["type"] == "csp-violation")
+            .map(|mut report| {
+                let body = report["body"].take();
+                [
+                    body["documentURL"].clone(),
+                    body["effectiveDirective"].clone(),
+                    body["blockedURL"].clone(),
+                ]
+            })
+            .collect(),
+        Value::Object(_) => {
+            let body = &report["csp-report"];
+            vec![[
+                body["document-uri"].clone(),
+                body["effective-directive"]
+                    .as_str()
+                    .map_or_else(|| body["violated-directive"].clone(), Value::from),
+                body["blocked-uri"].clone(),
+            ]]
+        }
+        _ => return StatusCode::BAD_REQUEST,
+    };
+
+    for [document, directive, blocked] in violations.iter().take(MAX_LOGGED_VIOLATIONS) {
+        tracing::info!(
+            document = %log_field(document),
+            directive = %log_field(directive),
+            blocked = %log_field(blocked),
+            "Content-Security-Policy violation"
+        );
+    }
+    if violations.len() > MAX_LOGGED_VIOLATIONS {
+        tracing::info!("{} more violations in the report not logged", violations.len() - MAX_LOGGED_VIOLATIONS);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// A report field as logged: strings without quotes, cut to a bounded length
+fn log_field(value: &Value) -> String {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    text.chars().take(MAX_LOGGED_FIELD_CHARS).collect()
+}
@@ -0,0 +1,118 @@
+use tower_cookies::{
+    cookie::{time, SameSite},
+    Cookie, Cookies, Key,
+};
+use crate::config::{Config, SameSitePolicy};
+use crate::services::session::Session;
+
+/// Name of the encrypted cookie carrying the session ID
+pub const SESSION_COOKIE: &str = "session_id";
+/// Name of the script-readable cookie carrying the CSRF token
+pub const CSRF_COOKIE: &str = "csrf_token";
+/// Header the frontend echoes the CSRF token in on mutating requests
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// How session and CSRF cookies are sealed and which attributes they carry
+#[derive(Clone)]
+pub struct CookieSettings {
+    key: Key,
+    pub secure: bool,
+    pub same_site: SameSite,
+    /// Path the cookies are scoped to
+    pub path: String,
+}
+
+impl CookieSettings {
+    /// Settings with the given key, `SameSite=Lax`, no `Secure` flag and path `/`
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            secure: false,
+            same_site: SameSite::Lax,
+            path: "/".to_string(),
+        }
+    }
+
+    /// Settings from the `[session]` config section.
+    ///
+    /// Without a configured secret a random key is generated, which config
+    /// validation only allows outside production since sessions will not
+    /// survive a restart.
+    pub fn from_config(config: &Config) -> Self {
+        let key = match &config.session.secret {
+            Some(secret) => Key::derive_from(secret.as_bytes()),
+            None => {
+                tracing::warn!("No session secret configured; using a random key for this process");
+                Key::generate()
+            }
+        };
+
+        let mut settings = Self::new(key);
+        settings.secure = config.cookie_secure();
+        settings.same_site = match config.session.cookie_same_site {
+            SameSitePolicy::Lax => SameSite::Lax,
+            SameSitePolicy::Strict => SameSite::Strict,
+        };
+        settings.path = config.server.cookie_path().to_string();
+        settings
+    }
+
+    /// A cookie with the shared attributes; without `max_age` it is a
+    /// browser-session cookie
+    fn build(
+        &self,
+        name: &'static str,
+        value: String,
+        http_only: bool,
+        max_age: Option<time::Duration>,
+    ) -> Cookie<'static> {
+        let mut cookie = Cookie::build((name, value))
+            .path(self.path.clone())
+            .http_only(http_only)
+            .secure(self.secure)
+            .same_site(self.same_site)
+            .build();
+        if let Some(max_age) = max_age {
+            cookie.set_max_age(max_age);
+        }
+        cookie
+    }
+
+    /// Set the encrypted session cookie and the matching CSRF cookie.
+    ///
+    /// "Remember me" cookies live until the session's absolute expiry, counted
+    /// from its last activity. Other sessions get browser-session cookies that
+    /// go away when the browser closes. Expiry is enforced server-side either way.
+    pub fn set_session(&self, cookies: &Cookies, session_id: &str, session: &Session) {
+        let max_age = session.remember_me.then(|| {
+            let lifetime = session.absolute_expires_at - session.last_seen_at;
+            time::Duration::seconds(lifetime.num_seconds().max(0))
+        });
+        cookies
+            .private(&self.key)
+            .add(self.build(SESSION_COOKIE, session_id.to_string(), true, max_age));
+        // Readable by the frontend so it can echo the token in the CSRF header
+        cookies.add(self.build(CSRF_COOKIE, session.csrf_token.clone(), false, max_age));
+    }
+
+    /// Session ID from the request, if the cookie is present and was issued by us
+    pub fn session_id(&self, cookies: &Cookies) -> Option<String> {
+        cookies
+            .private(&self.key)
+            .get(SESSION_COOKIE)
+            .map(|cookie| cookie.value().to_string())
+    }
+
+    /// Expire both session cookies
+    pub fn clear_session(&self, cookies: &Cookies) {
+        for name in [SESSION_COOKIE, CSRF_COOKIE] {
+            cookies.remove(Cookie::build(name).path(self.path.clone()).build());
+        }
+    }
+}
+
+/// Compare two tokens without leaking the mismatch position through timing
+pub fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,61 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+use crate::{
+    app::AppState,
+    config::Config,
+    validation::{FieldError, ValidationErrors},
+};
+
+/// A request body that can check itself against the validation rules
+pub trait Validate {
+    fn validate(&self, config: &Config) -> Result<(), ValidationErrors>;
+}
+
+/// JSON body that has been parsed and validated before the handler runs.
+///
+/// Malformed JSON is rejected with the status axum chooses and a JSON error
+/// body; a body that breaks the rules gets 422 with every field error.
+pub struct Valid<T>(pub T);
+
+/// Body of a 422 response
+#[derive(Serialize, ToSchema)]
+pub struct ValidationErrorResponse {
+    /// Every field message, joined
+    pub error: String,
+    pub fields: Vec<FieldError>,
+}
+
+#[async_trait]
+impl<T> FromRequest<AppState> for Valid<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request(request: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Json(payload) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(json_rejection)?;
+        payload.validate(&state.config).map_err(validation_failed)?;
+        Ok(Self(payload))
+    }
+}
+
+fn json_rejection(rejection: JsonRejection) -> (StatusCode, Json<Value>) {
+    (rejection.status(), Json(json!({"error": rejection.body_text()})))
+}
+
+pub fn validation_failed(errors: ValidationErrors) -> (StatusCode, Json<Value>) {
+    let response = ValidationErrorResponse {
+        error: errors.to_string(),
+        fields: errors.fields,
+    };
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(json!(response)))
+}
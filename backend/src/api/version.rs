@@ -0,0 +1,92 @@
+//! API versions. Each is served at `/api/<name>`; the unversioned `/api/...`
+//! paths that predate versioning remain as deprecated aliases of v1.
+
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+    Router,
+};
+use chrono::{NaiveDate, NaiveTime};
+use crate::{
+    api::{admin_routes, auth_routes, memo_routes},
+    app::AppState,
+};
+
+/// A version of the API and the router serving it
+pub struct ApiVersion {
+    /// Path segment after `/api`, e.g. `v1`
+    pub name: &'static str,
+    pub routes: fn(AppState) -> Router,
+}
+
+/// Every version served, oldest first. A new version is added here with its
+/// own router and mounted next to the existing ones, which stay unchanged.
+pub const API_VERSIONS: &[ApiVersion] = &[ApiVersion { name: "v1", routes: v1_routes }];
+
+/// Version the unversioned paths are aliases of
+pub const UNVERSIONED_ALIAS_OF: &str = "v1";
+
+/// When the unversioned paths were deprecated (2026-10-18), as Unix time
+const UNVERSIONED_DEPRECATED_AT: i64 = 1_792_281_600;
+
+pub const DEPRECATION_HEADER: &str = "deprecation";
+pub const SUNSET_HEADER: &str = "sunset";
+
+pub fn v1_routes(state: AppState) -> Router {
+    Router::new()
+        .nest("/admin", admin_routes(state.clone()))
+        .nest("/auth", auth_routes(state.clone()))
+        .nest("/memos", memo_routes(state))
+}
+
+/// The v1 routes at the unversioned paths, answering with `Deprecation`,
+/// `Sunset` and a `Link` to the same path under `/api/v1`
+pub fn unversioned_routes(state: AppState) -> Router {
+    let notice = DeprecationNotice {
+        deprecation: HeaderValue::from_str(&format!("@{}", UNVERSIONED_DEPRECATED_AT))
+            .expect("valid header value"),
+        sunset: http_date(state.config.api.unversioned_sunset),
+        api_prefix: format!("{}/api", state.config.server.base_path),
+    };
+    v1_routes(state).route_layer(middleware::from_fn_with_state(notice, deprecated_alias))
+}
+
+#[derive(Clone)]
+struct DeprecationNotice {
+    deprecation: HeaderValue,
+    sunset: HeaderValue,
+    /// `/api` under the base path, where the successor version is found
+    api_prefix: String,
+}
+
+async fn deprecated_alias(
+    State(notice): State<DeprecationNotice>,
+    OriginalUri(uri): OriginalUri,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(HeaderName::from_static(DEPRECATION_HEADER), notice.deprecation);
+    headers.insert(HeaderName::from_static(SUNSET_HEADER), notice.sunset);
+
+    if let Some(rest) = uri.path().strip_prefix(&notice.api_prefix) {
+        let link = format!(
+            "<{}/{}{}>; rel=\"successor-version\"",
+            notice.api_prefix, UNVERSIONED_ALIAS_OF, rest
+        );
+        if let Ok(link) = HeaderValue::from_str(&link) {
+            headers.insert(header::LINK, link);
+        }
+    }
+    response
+}
+
+/// Midnight UTC of `date` in the IMF-fixdate form HTTP headers use
+fn http_date(date: NaiveDate) -> HeaderValue {
+    let midnight = date.and_time(NaiveTime::MIN).and_utc();
+    HeaderValue::from_str(&midnight.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+        .expect("valid header value")
+}
@@ -1,21 +1,154 @@
-use axum::{routing::get, Router};
-use tower_http::{trace::TraceLayer, cors::CorsLayer, services::ServeDir};
+use std::sync::Arc;
+use axum::{
+    extract::{OriginalUri, Request},
+    http::{header, HeaderValue, Uri},
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use tower_http::{trace::TraceLayer, cors::CorsLayer};
 use tower_cookies::CookieManagerLayer;
 use anyhow::Result;
 use crate::{
-    api::{auth_routes, memo_routes},
-    database::DatabasePool,
-    services::SessionStore,
+    api::{
+        health_routes, metrics_routes,
+        middleware::{
+            rate_limit, request_id, RATE_LIMIT_LIMIT_HEADER, RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER,
+            REQUEST_ID_HEADER,
+        },
+        openapi::openapi_routes,
+        security_headers::{csp_report_routes, security_headers, SecurityHeaders},
+        session_cookie::{CookieSettings, CSRF_HEADER},
+        version::{unversioned_routes, API_VERSIONS, DEPRECATION_HEADER, SUNSET_HEADER},
+    },
+    assets::StaticFiles,
+    clock::{system_clock, OffsetClock, SharedClock},
+    config::Config,
+    database::Database,
+    models::quota::QuotaLimits,
+    storage::SqlPool,
+    logging::request_span,
+    metrics::{track_requests, Metrics},
+    services::{
+        session::SessionPolicy, AuditService, BackupService, CleanupService, HealthService, MemoService, PasswordHasher,
+        RateLimiter, SessionStore,
+    },
 };
 
-pub async fn create_app(pool: DatabasePool) -> Result<Router> {
-    let session_store = SessionStore::new();
+/// State shared by all API routers
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Database,
+    pub session_store: SessionStore,
+    /// Request buckets of the API clients, checked when `rate_limit.enabled`
+    pub rate_limiter: RateLimiter,
+    pub password_hasher: PasswordHasher,
+    pub cookie_settings: CookieSettings,
+    pub config: Arc<Config>,
+    pub metrics: Metrics,
+    /// Clock for memo expiry and cleanup. Sessions and audit events keep
+    /// the system clock, so shifting it does not log anyone out.
+    pub clock: SharedClock,
+    /// Adjustable offset behind `clock`, present only when `debug.time_offset` is enabled
+    pub time_offset: Option<Arc<OffsetClock>>,
+}
+
+impl AppState {
+    pub fn new(db: Database, config: Config) -> Result<Self> {
+        let time_offset = config
+            .debug
+            .time_offset
+            .then(|| Arc::new(OffsetClock::new(system_clock())));
+        let clock = match &time_offset {
+            Some(offset) => offset.clone() as SharedClock,
+            None => system_clock(),
+        };
+
+        Ok(Self {
+            db,
+            session_store: SessionStore::with_policy(SessionPolicy::from(&config.session)),
+            rate_limiter: RateLimiter::from(&config.rate_limit),
+            password_hasher: PasswordHasher::new(config.password)?,
+            cookie_settings: CookieSettings::from_config(&config),
+            config: Arc::new(config),
+            metrics: Metrics::new()?,
+            clock,
+            time_offset,
+        })
+    }
+
+    /// Use `clock` for memo expiry and cleanup, e.g. a manual clock in
+    /// tests. Replaces the debug time offset, if one was configured.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self.time_offset = None;
+        self
+    }
+
+    pub fn audit_service(&self) -> AuditService {
+        AuditService::new(self.db.clone())
+    }
+
+    /// Readiness checks for the configured database and cleanup job
+    pub fn health_service(&self) -> HealthService {
+        HealthService::new(
+            self.db.clone(),
+            &self.config.database.url,
+            self.config.health.min_free_disk_mb * 1024 * 1024,
+            self.config.cleanup.max_age(),
+        )
+        .with_clock(self.clock.clone())
+    }
+
+    /// Cleanup service that reports each run to the metrics and enforces
+    /// audit log retention
+    pub fn cleanup_service(&self) -> CleanupService {
+        CleanupService::new(self.db.clone())
+            .with_metrics(self.metrics.clone())
+            .with_audit_retention(self.config.audit.retention())
+            .with_clock(self.clock.clone())
+    }
+
+    /// Backups in the configured directory, with its rotation and
+    /// verification; `None` unless the database is SQLite
+    pub fn backup_service(&self) -> Option<BackupService> {
+        let Some(SqlPool::Sqlite(pool)) = self.db.sql_pool() else {
+            return None;
+        };
+        let backup = &self.config.backup;
+        Some(
+            BackupService::new(pool.clone(), backup.dir.clone())
+                .with_rotation(backup.keep)
+                .with_verification(backup.verify),
+        )
+    }
+
+    /// Memo service using the configured flush memo lifetime and quotas
+    pub fn memo_service(&self) -> MemoService {
+        MemoService::with_flush_ttl(self.db.clone(), self.config.memo.flush_ttl())
+            .with_clock(self.clock.clone())
+            .with_quota(QuotaLimits::from(&self.config.quota))
+    }
+}
+
+pub async fn create_app(db: Database, config: Config) -> Result<Router> {
+    router(AppState::new(db, config)?)
+}
+
+/// Build the application router around existing state
+pub fn router(state: AppState) -> Result<Router> {
     // Create CORS layer
+    let origins = state
+        .config
+        .server
+        .cors_origins
+        .iter()
+        .map(|origin| origin.parse::<HeaderValue>())
+        .collect::<Result<Vec<_>, _>>()?;
     let cors = CorsLayer::new()
-        .allow_origin("http://127.0.0.1:8080".parse::<axum::http::HeaderValue>()?)
-        .allow_origin("http://localhost:8080".parse::<axum::http::HeaderValue>()?)
-        .allow_origin("http://127.0.0.1:3000".parse::<axum::http::HeaderValue>()?)
-        .allow_origin("http://localhost:3000".parse::<axum::http::HeaderValue>()?)
+        .allow_origin(origins)
         .allow_methods([
             axum::http::Method::GET,
             axum::http::Method::POST,
@@ -27,22 +160,110 @@ pub async fn create_app(pool: DatabasePool) -> Result<Router> {
             axum::http::header::CONTENT_TYPE,
             axum::http::header::AUTHORIZATION,
             axum::http::header::ACCEPT,
+            axum::http::HeaderName::from_static(CSRF_HEADER),
+            axum::http::HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        // Lets the frontend quote the ID when reporting an error, and
+        // scripts notice they are calling a deprecated path
+        .expose_headers([
+            axum::http::HeaderName::from_static(REQUEST_ID_HEADER),
+            axum::http::HeaderName::from_static(DEPRECATION_HEADER),
+            axum::http::HeaderName::from_static(SUNSET_HEADER),
+            axum::http::header::LINK,
+            axum::http::HeaderName::from_static(RATE_LIMIT_LIMIT_HEADER),
+            axum::http::HeaderName::from_static(RATE_LIMIT_REMAINING_HEADER),
+            axum::http::HeaderName::from_static(RATE_LIMIT_RESET_HEADER),
+            axum::http::header::RETRY_AFTER,
         ])
         .allow_credentials(true);
 
-    let app = Router::new()
-        .route("/health", get(health_check))
-        .nest("/api/auth", auth_routes(pool.clone(), session_store.clone()))
-        .nest("/api/memos", memo_routes(pool.clone(), session_store.clone()))
-        // Serve static files from frontend directory
-        .nest_service("/", ServeDir::new("../frontend"))
+    let base_path = state.config.server.base_path.clone();
+    let frontend_config = frontend_config_script(&base_path);
+    let static_files = state.config.server.static_files();
+
+    let index = get({
+        let static_files = static_files.clone();
+        move |OriginalUri(uri): OriginalUri, request: Request| serve_index(uri, request, static_files)
+    });
+
+    let mut routes = Router::new()
+        .route("/", index.clone())
+        .nest("/health", health_routes(state.clone()))
+        .route("/config.js", get(move || async move { frontend_config }))
+        // Serve the frontend, embedded or from disk
+        .fallback(move |request: Request| async move { static_files.serve(request).await });
+    // Each API version at /api/<name>, side by side
+    let mut api = Router::new();
+    for version in API_VERSIONS {
+        api = api.nest(&format!("/api/{}", version.name), (version.routes)(state.clone()));
+    }
+    if state.config.api.unversioned_aliases {
+        api = api.nest("/api", unversioned_routes(state.clone()));
+    }
+    // Anyone can post reports, so they count against the client like API calls
+    if state.config.security_headers.enabled {
+        api = api.merge(csp_report_routes());
+    }
+    // Only the API is rate limited; probes, metrics and the frontend are not
+    if state.config.rate_limit.enabled {
+        api = api.layer(middleware::from_fn_with_state(state.clone(), rate_limit));
+    }
+    routes = routes.merge(api);
+    // With its own bind address, /metrics is served by `metrics_router` instead
+    if state.config.metrics.enabled && state.config.metrics.bind.is_none() {
+        routes = routes.merge(metrics_routes(state.clone()));
+    }
+
+    // Mount everything under the base path. Nesting only matches the bare
+    // prefix, so the trailing-slash form gets its own index route.
+    let app = if base_path.is_empty() {
+        routes
+    } else {
+        Router::new()
+            .route(&format!("{}/", base_path), index)
+            .nest(&base_path, routes)
+    };
+
+    let mut app = app
+        .merge(openapi_routes(&base_path))
+        .layer(middleware::from_fn_with_state(state.metrics.clone(), track_requests));
+    if state.config.security_headers.enabled {
+        let headers = SecurityHeaders::new(&state.config)?;
+        app = app.layer(middleware::from_fn_with_state(headers, security_headers));
+    }
+    let app = app
         .layer(cors)
         .layer(CookieManagerLayer::new())
-        .layer(TraceLayer::new_for_http());
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(middleware::from_fn(request_id));
 
     Ok(app)
 }
 
-async fn health_check() -> &'static str {
-    "OK"
+/// Router for the separate metrics listener configured by `metrics.bind`
+pub fn metrics_router(state: AppState) -> Router {
+    metrics_routes(state)
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(middleware::from_fn(request_id))
+}
+
+/// Serve `index.html`, redirecting the bare base path to its trailing-slash
+/// form so the frontend's relative URLs resolve inside the prefix
+async fn serve_index(uri: Uri, request: Request, static_files: StaticFiles) -> Response {
+    if !uri.path().ends_with('/') {
+        return Redirect::permanent(&format!("{}/", uri.path())).into_response();
+    }
+    static_files.serve_index(request).await
+}
+
+/// Script loaded by `index.html` that tells the frontend where the API lives
+fn frontend_config_script(base_path: &str) -> impl IntoResponse + Clone {
+    let config = json!({ "basePath": base_path });
+    (
+        [
+            (header::CONTENT_TYPE, "application/javascript; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        format!("window.MEMORY_MEMO_CONFIG = {};\n", config),
+    )
 }
\ No newline at end of file
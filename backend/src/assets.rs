@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tower_http::services::{ServeDir, ServeFile};
+
+/// A frontend file compiled into the binary by `build.rs`
+pub struct Asset {
+    pub path: &'static str,
+    /// Content hash shared by all encodings of the file
+    pub etag: &'static str,
+    pub body: &'static [u8],
+    pub gzip: Option<&'static [u8]>,
+    pub brotli: Option<&'static [u8]>,
+}
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+/// Cache policy for assets whose file name carries a content hash
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// Cache policy for everything else: always revalidate with the ETag
+const REVALIDATE: &str = "no-cache";
+
+/// Look up an embedded asset by its path relative to `frontend/`
+pub fn get(path: &str) -> Option<&'static Asset> {
+    ASSETS.iter().find(|asset| asset.path == path)
+}
+
+/// Paths of all embedded assets
+pub fn paths() -> impl Iterator<Item = &'static str> {
+    ASSETS.iter().map(|asset| asset.path)
+}
+
+/// Where the frontend is served from
+#[derive(Clone, Debug)]
+pub enum StaticFiles {
+    /// Files compiled into the binary
+    Embedded,
+    /// Files read from a directory on every request, for frontend development
+    Disk(PathBuf),
+}
+
+impl StaticFiles {
+    /// Serve the file named by the request path
+    pub async fn serve(&self, request: Request) -> Response {
+        match self {
+            StaticFiles::Embedded => {
+                let path = request.uri().path().trim_start_matches('/');
+                let path = if path.is_empty() || path.ends_with('/') {
+                    format!("{}index.html", path)
+                } else {
+                    path.to_string()
+                };
+                serve_embedded(&path, request.method(), request.headers())
+            }
+            StaticFiles::Disk(dir) => match ServeDir::new(dir).try_call(request).await {
+                Ok(response) => response.into_response(),
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            },
+        }
+    }
+
+    /// Serve `index.html` regardless of the request path
+    pub async fn serve_index(&self, request: Request) -> Response {
+        match self {
+            StaticFiles::Embedded => serve_embedded("index.html", request.method(), request.headers()),
+            StaticFiles::Disk(dir) => match ServeFile::new(dir.join("index.html")).try_call(request).await {
+                Ok(response) => response.into_response(),
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            },
+        }
+    }
+}
+
+fn serve_embedded(path: &str, method: &Method, headers: &HeaderMap) -> Response {
+    if method != Method::GET && method != Method::HEAD {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    let Some(asset) = get(path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let (encoding, body) = select_encoding(asset, headers);
+    // Each encoding is a different representation, so a strong ETag must differ too
+    let etag = match encoding {
+        Some(encoding) => format!("\"{}-{}\"", asset.etag, encoding),
+        None => format!("\"{}\"", asset.etag),
+    };
+    let cache_control = if is_hashed(path) { IMMUTABLE } else { REVALIDATE };
+
+    let mut response = if if_none_match(headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let body = if method == Method::HEAD { Body::empty() } else { Body::from(body) };
+        let mut response = Response::new(body);
+        let response_headers = response.headers_mut();
+        response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type(path)));
+        response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body_len(asset, encoding)));
+        if let Some(encoding) = encoding {
+            response_headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        }
+        response
+    };
+
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::ETAG, HeaderValue::from_str(&etag).expect("hex ETag is a valid header"));
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    if asset.gzip.is_some() || asset.brotli.is_some() {
+        response_headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+    response
+}
+
+/// Pick the best precompressed variant the client accepts
+fn select_encoding(asset: &'static Asset, headers: &HeaderMap) -> (Option<&'static str>, &'static [u8]) {
+    let accepts = |encoding: &str| {
+        headers
+            .get_all(header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let name = parts.next().unwrap_or_default();
+                let rejected = parts.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q == 0.0)
+                });
+                name.eq_ignore_ascii_case(encoding) && !rejected
+            })
+    };
+
+    match (asset.brotli, asset.gzip) {
+        (Some(brotli), _) if accepts("br") => (Some("br"), brotli),
+        (_, Some(gzip)) if accepts("gzip") => (Some("gzip"), gzip),
+        _ => (None, asset.body),
+    }
+}
+
+fn body_len(asset: &Asset, encoding: Option<&str>) -> usize {
+    match encoding {
+        Some("br") => asset.brotli.map_or(0, <[u8]>::len),
+        Some("gzip") => asset.gzip.map_or(0, <[u8]>::len),
+        _ => asset.body.len(),
+    }
+}
+
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').map(str::trim).any(|tag| tag == etag || tag == "*"))
+}
+
+/// Whether a file name carries a content hash, like `app.3f9c2a1b.js`
+pub fn is_hashed(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let mut segments = file_name.rsplit('.');
+    let _extension = segments.next();
+    match (segments.next(), segments.next()) {
+        (Some(hash), Some(_stem)) => hash.len() >= 8 && hash.chars().all(|c| c.is_ascii_hexdigit()),
+        _ => false,
+    }
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or_default() {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,312 @@
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use crate::config::Config;
+use crate::database::Database;
+use crate::storage::SqlPool;
+use crate::models::{AuditEventType, NewAuditEvent, User};
+use crate::services::{AdminService, AuditService, AuthService, BackupService, CleanupService, MemoService, PasswordHasher};
+use crate::services::backup::{snapshot_database, verify_snapshot};
+
+/// Memory Memo server and administration tools
+#[derive(Debug, Parser)]
+#[command(name = "memory_memo", version)]
+pub struct Cli {
+    /// Path to a TOML config file (defaults to $CONFIG_FILE, then ./memory_memo.toml if present)
+    #[arg(long, short, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Defaults to `serve` when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the web server
+    Serve,
+    /// Manage user accounts
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Clean up expired flush memos
+    Cleanup {
+        #[command(subcommand)]
+        command: CleanupCommand,
+    },
+    /// Export a user's memos as JSON
+    Export {
+        /// Username whose memos to export
+        #[arg(long)]
+        user: String,
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Apply pending database migrations
+    Migrate,
+    /// Write a consistent snapshot of the database, or manage snapshots
+    #[command(args_conflicts_with_subcommands = true)]
+    Backup {
+        /// Snapshot path (defaults to a timestamped file in the backup directory,
+        /// which is then rotated)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        #[command(subcommand)]
+        command: Option<BackupCommand>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// Create a user
+    Create {
+        username: String,
+        /// Read from stdin when omitted
+        #[arg(long)]
+        password: Option<String>,
+        /// Grant the admin role
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Delete a user and all of their memos
+    Delete { username: String },
+    /// Set a new password for a user
+    ResetPassword {
+        username: String,
+        /// Read from stdin when omitted
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// List users with memo counts
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BackupCommand {
+    /// List snapshots in the backup directory
+    List,
+    /// Check a snapshot's integrity and schema version
+    Verify { path: PathBuf },
+    /// Replace the database contents with a snapshot, after backing up the current data
+    Restore { path: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CleanupCommand {
+    /// Delete expired flush memos and audit events past retention
+    Run,
+    /// Count expired flush memos without deleting them
+    DryRun,
+    /// Show flush memo statistics and recent cleanup runs
+    Stats,
+}
+
+/// Run a non-`serve` command against the given database, writing its output to `out`
+pub async fn run_command(command: Command, db: Database, config: &Config, out: &mut impl Write) -> Result<()> {
+    match command {
+        Command::Serve => Err(anyhow!("serve is handled by the binary")),
+        Command::User { command } => run_user_command(command, db, config, out).await,
+        Command::Cleanup { command } => run_cleanup_command(command, db, config, out).await,
+        Command::Export { user, output } => export_user(&db, &user, output.as_deref(), out).await,
+        Command::Migrate => {
+            // Migrations already ran when the database was opened
+            let status = db.migration_status().await?;
+            writeln!(out, "Database is at migration version {}", status.applied)?;
+            Ok(())
+        }
+        Command::Backup { output, command } => run_backup_command(command, output, db, config, out).await,
+    }
+}
+
+async fn run_user_command(command: UserCommand, db: Database, config: &Config, out: &mut impl Write) -> Result<()> {
+    let hasher = PasswordHasher::new(config.password)?;
+    let admin_service = AdminService::with_hasher(db.clone(), hasher.clone());
+    let audit_service = AuditService::new(db.clone());
+    // Actions taken from a shell have no acting account
+    let cli_event = |event_type, user_id: &str| {
+        NewAuditEvent::new(event_type).user(user_id).details(json!({"source": "cli"}))
+    };
+
+    match command {
+        UserCommand::Create { username, password, admin } => {
+            let password = password_or_stdin(password)?;
+            let auth_service = AuthService::with_hasher(db.clone(), hasher);
+            let user = auth_service.register(&username, &password).await?;
+            if admin {
+                admin_service.set_admin(&user.id, true).await?;
+            }
+            audit_service.record(cli_event(AuditEventType::AdminUserCreated, &user.id)).await;
+            writeln!(out, "Created user {} ({}){}", user.username, user.id, if admin { " as admin" } else { "" })?;
+        }
+        UserCommand::Delete { username } => {
+            let user = find_user(&db, &username).await?;
+            admin_service.delete_user(&user.id).await?;
+            audit_service.record(cli_event(AuditEventType::AdminUserDeleted, &user.id)).await;
+            writeln!(out, "Deleted user {}", user.username)?;
+        }
+        UserCommand::ResetPassword { username, password } => {
+            let user = find_user(&db, &username).await?;
+            let password = password_or_stdin(password)?;
+            admin_service.reset_password(&user.id, &password).await?;
+            audit_service.record(cli_event(AuditEventType::PasswordChanged, &user.id)).await;
+            writeln!(out, "Password reset for {}", user.username)?;
+        }
+        UserCommand::List => {
+            writeln!(out, "{:<36}  {:<20}  {:>7}  {:>5}  {:<5}  {:<8}  CREATED", "ID", "USERNAME", "FOREVER", "FLUSH", "ADMIN", "DISABLED")?;
+            for user in admin_service.list_users().await? {
+                writeln!(
+                    out,
+                    "{:<36}  {:<20}  {:>7}  {:>5}  {:<5}  {:<8}  {}",
+                    user.id,
+                    user.username,
+                    user.forever_memo_count,
+                    user.flush_memo_count,
+                    if user.is_admin { "yes" } else { "no" },
+                    if user.disabled_at.is_some() { "yes" } else { "no" },
+                    user.created_at.to_rfc3339(),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_cleanup_command(command: CleanupCommand, db: Database, config: &Config, out: &mut impl Write) -> Result<()> {
+    // The same retention as the server's scheduled job
+    let cleanup_service = CleanupService::new(db).with_audit_retention(config.audit.retention());
+
+    match command {
+        CleanupCommand::Run => {
+            let cleaned_count = cleanup_service.cleanup_expired_flush_memos().await?;
+            writeln!(out, "Removed {} expired flush memos", cleaned_count)?;
+            let purged_count = cleanup_service.purge_audit_events().await?;
+            writeln!(
+                out,
+                "Removed {} audit events older than {} days",
+                purged_count, config.audit.retention_days
+            )?;
+        }
+        CleanupCommand::DryRun => {
+            let count = cleanup_service.cleanup_expired_flush_memos_dry_run().await?;
+            writeln!(out, "{} expired flush memos would be removed", count)?;
+        }
+        CleanupCommand::Stats => {
+            let stats = cleanup_service.get_cleanup_statistics().await?;
+            writeln!(out, "Flush memos: {} total, {} active, {} expired", stats.total_count, stats.active_count, stats.expired_count)?;
+            match stats.last_cleanup {
+                Some(time) => writeln!(out, "Last cleanup: {}", time.to_rfc3339())?,
+                None => writeln!(out, "Last cleanup: never")?,
+            }
+            for (cleaned_count, run_time) in cleanup_service.get_cleanup_history(10).await? {
+                writeln!(out, "  {}  removed {}", run_time.to_rfc3339(), cleaned_count)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_backup_command(
+    command: Option<BackupCommand>,
+    output: Option<PathBuf>,
+    db: Database,
+    config: &Config,
+    out: &mut impl Write,
+) -> Result<()> {
+    // Snapshots are taken with SQLite's VACUUM INTO; PostgreSQL has pg_dump
+    let Some(SqlPool::Sqlite(pool)) = db.sql_pool() else {
+        return Err(anyhow!("Backups are only available with SQLite; use pg_dump for PostgreSQL"));
+    };
+    let backup_service = BackupService::new(pool.clone(), config.backup.dir.clone())
+        .with_rotation(config.backup.keep)
+        .with_verification(config.backup.verify);
+
+    match command {
+        None => match output {
+            Some(path) => {
+                snapshot_database(pool, &path).await?;
+                if config.backup.verify {
+                    verify_snapshot(&path).await?.ensure_usable()?;
+                }
+                writeln!(out, "Backup written to {}", path.display())?;
+            }
+            None => {
+                let backup = backup_service.create_backup().await?;
+                writeln!(out, "Backup written to {}", backup_service.dir().join(&backup.name).display())?;
+            }
+        },
+        Some(BackupCommand::List) => {
+            writeln!(out, "{:<40}  {:>12}  CREATED", "NAME", "BYTES")?;
+            for backup in backup_service.list_backups().await? {
+                writeln!(out, "{:<40}  {:>12}  {}", backup.name, backup.size_bytes, backup.created_at.to_rfc3339())?;
+            }
+        }
+        Some(BackupCommand::Verify { path }) => {
+            let check = verify_snapshot(&path).await?;
+            check.ensure_usable()?;
+            writeln!(out, "{} is intact (schema version {})", path.display(), check.schema_version)?;
+        }
+        Some(BackupCommand::Restore { path }) => {
+            let report = backup_service.restore(&path).await?;
+            AuditService::new(db.clone())
+                .record(NewAuditEvent::new(AuditEventType::AdminBackupRestored).details(json!({
+                    "source": "cli",
+                    "path": path.display().to_string(),
+                    "schema_version": report.schema_version,
+                    "safety_backup": report.safety_backup.name,
+                })))
+                .await;
+            writeln!(out, "Restored {} (schema version {})", path.display(), report.schema_version)?;
+            writeln!(out, "Previous data saved as {}", backup_service.dir().join(&report.safety_backup.name).display())?;
+        }
+    }
+    Ok(())
+}
+
+async fn export_user(db: &Database, username: &str, output: Option<&Path>, out: &mut impl Write) -> Result<()> {
+    let user = find_user(db, username).await?;
+    let memos = MemoService::new(db.clone()).list_user_memos(&user.id).await?;
+
+    let export = json!({
+        "user": user.to_public(),
+        "exported_at": Utc::now(),
+        "forever_memos": memos.forever_memos,
+        "flush_memos": memos.flush_memos,
+    });
+    let body = serde_json::to_string_pretty(&export)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, body)?;
+            writeln!(out, "Exported memos for {} to {}", user.username, path.display())?;
+        }
+        None => writeln!(out, "{}", body)?,
+    }
+    Ok(())
+}
+
+async fn find_user(db: &Database, username: &str) -> Result<User> {
+    User::find_by_username(db, username)
+        .await?
+        .ok_or_else(|| anyhow!("User {} not found", username))
+}
+
+fn password_or_stdin(password: Option<String>) -> Result<String> {
+    if let Some(password) = password {
+        return Ok(password);
+    }
+
+    eprint!("Password: ");
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err(anyhow!("No password given"));
+    }
+    Ok(password)
+}
@@ -0,0 +1,1137 @@
+use std::env;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use anyhow::{Result, anyhow};
+use chrono::{Duration, NaiveDate};
+use serde::Deserialize;
+use crate::assets::StaticFiles;
+use crate::services::password::{Argon2Config, PasswordHasher};
+use crate::storage::Backend;
+
+/// Config file read when neither `--config` nor `CONFIG_FILE` is given, if it exists
+pub const DEFAULT_CONFIG_FILE: &str = "memory_memo.toml";
+
+/// Application configuration.
+///
+/// Loaded from an optional TOML file, then overridden by environment
+/// variables (see `.env.example` for the names), then validated.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub environment: Environment,
+    pub logging: LoggingConfig,
+    pub server: ServerConfig,
+    pub tls: TlsConfig,
+    pub api: ApiConfig,
+    pub rate_limit: RateLimitConfig,
+    pub security_headers: SecurityHeadersConfig,
+    pub database: DatabaseConfig,
+    pub session: SessionConfig,
+    pub password: Argon2Config,
+    pub memo: MemoConfig,
+    pub quota: QuotaConfig,
+    pub cleanup: CleanupConfig,
+    pub health: HealthConfig,
+    pub metrics: MetricsConfig,
+    pub audit: AuditConfig,
+    pub backup: BackupConfig,
+    pub admin: AdminConfig,
+    pub debug: DebugConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Environment {
+    #[default]
+    Development,
+    Production,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// `tracing` filter directives, e.g. `info` or `memory_memo=debug,tower_http=info`
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
+            filter: "info".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log shippers
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// IP address to bind to
+    pub host: String,
+    pub port: u16,
+    /// Origins allowed to make credentialed cross-origin requests
+    pub cors_origins: Vec<String>,
+    /// Serve the frontend from this directory instead of the copy embedded in
+    /// the binary, so edits show up without rebuilding
+    pub static_dir: Option<PathBuf>,
+    /// Path prefix everything is served under, e.g. `/memory_memo` (empty for the root)
+    pub base_path: String,
+    /// How long in-flight requests may run after a shutdown signal
+    pub shutdown_timeout_seconds: u64,
+    /// Take the client IP from the last `X-Forwarded-For` entry, the one the
+    /// reverse proxy in front of the app appended. Only enable behind such a
+    /// proxy, or clients can spoof their address.
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+            cors_origins: [
+                "http://127.0.0.1:8080",
+                "http://localhost:8080",
+                "http://127.0.0.1:3000",
+                "http://localhost:3000",
+            ]
+            .map(String::from)
+            .to_vec(),
+            static_dir: None,
+            base_path: String::new(),
+            shutdown_timeout_seconds: 25,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn bind_addr(&self) -> Result<SocketAddr> {
+        let ip: IpAddr = self
+            .host
+            .parse()
+            .map_err(|_| anyhow!("server.host must be an IP address, got {:?}", self.host))?;
+        Ok(SocketAddr::new(ip, self.port))
+    }
+
+    pub fn shutdown_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.shutdown_timeout_seconds)
+    }
+
+    pub fn static_files(&self) -> StaticFiles {
+        match &self.static_dir {
+            Some(dir) => StaticFiles::Disk(dir.clone()),
+            None => StaticFiles::Embedded,
+        }
+    }
+
+    /// Path cookies are scoped to: the base path, or `/` when serving at the root
+    pub fn cookie_path(&self) -> &str {
+        if self.base_path.is_empty() {
+            "/"
+        } else {
+            &self.base_path
+        }
+    }
+}
+
+/// HTTPS served by the app itself. Certificates are reloaded when their
+/// files change, so renewals need no restart.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain; HTTPS is on when this and `key_path` are set
+    pub cert_path: Option<PathBuf>,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1)
+    pub key_path: Option<PathBuf>,
+    /// How often to check the files for a renewed certificate; 0 disables reloading
+    pub reload_interval_seconds: u64,
+    /// Also listen for plain HTTP on this port, redirecting everything to HTTPS
+    pub redirect_http_port: Option<u16>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            cert_path: None,
+            key_path: None,
+            reload_interval_seconds: 30,
+            redirect_http_port: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn enabled(&self) -> bool {
+        self.cert_path.is_some() && self.key_path.is_some()
+    }
+
+    pub fn reload_interval(&self) -> Option<std::time::Duration> {
+        (self.reload_interval_seconds > 0).then(|| std::time::Duration::from_secs(self.reload_interval_seconds))
+    }
+}
+
+/// The unversioned `/api/...` paths, kept as deprecated aliases of `/api/v1`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// Serve the aliases; turn off once every client has moved to `/api/v1`
+    pub unversioned_aliases: bool,
+    /// Date announced in the aliases' `Sunset` header
+    pub unversioned_sunset: NaiveDate,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            unversioned_aliases: true,
+            unversioned_sunset: NaiveDate::from_ymd_opt(2027, 4, 18).expect("valid date"),
+        }
+    }
+}
+
+/// Token buckets per client (the signed-in user, otherwise the IP address),
+/// one for reads and one for writes
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Sustained GET requests per minute
+    pub read_per_minute: u32,
+    /// GET requests allowed at once after a quiet period
+    pub read_burst: u32,
+    pub write_per_minute: u32,
+    pub write_burst: u32,
+    /// Requests per minute from all clients together; 0 for no global limit
+    pub global_per_minute: u32,
+    /// Save the buckets on shutdown and restore them on startup
+    pub persist: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            read_per_minute: 300,
+            read_burst: 100,
+            write_per_minute: 60,
+            write_burst: 30,
+            global_per_minute: 0,
+            persist: false,
+        }
+    }
+}
+
+/// Headers telling browsers what the frontend may load and how it may be
+/// embedded, sent on every response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityHeadersConfig {
+    pub enabled: bool,
+    /// Replaces the built-in Content-Security-Policy of the frontend and
+    /// API; Swagger UI keeps its own
+    pub content_security_policy: Option<String>,
+    /// Only report violations instead of blocking, to try out a policy
+    pub csp_report_only: bool,
+    /// Origins allowed to show the app in a frame; empty forbids framing
+    pub frame_ancestors: Vec<String>,
+    pub referrer_policy: String,
+    /// Send Strict-Transport-Security. Defaults to on when the app serves
+    /// HTTPS itself; behind a TLS-terminating proxy, turn it on here.
+    pub hsts: Option<bool>,
+    pub hsts_max_age_seconds: u64,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            content_security_policy: None,
+            csp_report_only: false,
+            frame_ancestors: Vec::new(),
+            referrer_policy: "no-referrer".to_string(),
+            hsts: None,
+            hsts_max_age_seconds: 365 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Values `security_headers.referrer_policy` accepts
+pub const REFERRER_POLICIES: [&str; 8] = [
+    "no-referrer",
+    "no-referrer-when-downgrade",
+    "origin",
+    "origin-when-cross-origin",
+    "same-origin",
+    "strict-origin",
+    "strict-origin-when-cross-origin",
+    "unsafe-url",
+];
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    /// Size of the connection pool
+    pub max_connections: u32,
+    /// How long a request waits for a free connection before failing
+    pub acquire_timeout_seconds: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite://./memory_memo.db".to_string(),
+            max_connections: 10,
+            acquire_timeout_seconds: 30,
+        }
+    }
+}
+
+impl fmt::Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("url", &self.redacted_url())
+            .field("max_connections", &self.max_connections)
+            .field("acquire_timeout_seconds", &self.acquire_timeout_seconds)
+            .finish()
+    }
+}
+
+impl DatabaseConfig {
+    /// Default pool settings for the database at `url`
+    pub fn with_url(url: impl Into<String>) -> Self {
+        Self { url: url.into(), ..Self::default() }
+    }
+
+    /// The URL without its password, fit for logs
+    pub fn redacted_url(&self) -> String {
+        let Ok(mut url) = url::Url::parse(&self.url) else {
+            return self.url.clone();
+        };
+        let has_password_param = url.query_pairs().any(|(key, _)| key == "password");
+        if url.password().is_none() && !has_password_param {
+            // Leave SQLite paths exactly as configured
+            return self.url.clone();
+        }
+
+        let _ = url.set_password(None);
+        if has_password_param {
+            let pairs: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(key, _)| key != "password")
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+            url.set_query(None);
+            if !pairs.is_empty() {
+                url.query_pairs_mut().extend_pairs(pairs);
+            }
+        }
+        url.to_string()
+    }
+
+    pub fn acquire_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.acquire_timeout_seconds)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    pub idle_timeout_minutes: i64,
+    pub absolute_timeout_hours: i64,
+    pub remember_me_days: i64,
+    /// Key material for encrypting session cookies, at least 32 bytes.
+    /// A random key is used when unset, outside production only.
+    pub secret: Option<String>,
+    /// Defaults to true in production or when serving HTTPS
+    pub cookie_secure: Option<bool>,
+    pub cookie_same_site: SameSitePolicy,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_minutes: 120,
+            absolute_timeout_hours: 24,
+            remember_me_days: 30,
+            secret: None,
+            cookie_secure: None,
+            cookie_same_site: SameSitePolicy::Lax,
+        }
+    }
+}
+
+impl fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("idle_timeout_minutes", &self.idle_timeout_minutes)
+            .field("absolute_timeout_hours", &self.absolute_timeout_hours)
+            .field("remember_me_days", &self.remember_me_days)
+            .field("secret", &redacted(&self.secret))
+            .field("cookie_secure", &self.cookie_secure)
+            .field("cookie_same_site", &self.cookie_same_site)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SameSitePolicy {
+    #[default]
+    Lax,
+    Strict,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoConfig {
+    /// How long flush memos live before they expire
+    pub flush_ttl_hours: i64,
+    /// Longest memo accepted, in characters
+    pub max_content_chars: usize,
+}
+
+impl Default for MemoConfig {
+    fn default() -> Self {
+        Self {
+            flush_ttl_hours: 24,
+            max_content_chars: 10_000,
+        }
+    }
+}
+
+impl MemoConfig {
+    pub fn flush_ttl(&self) -> Duration {
+        Duration::hours(self.flush_ttl_hours)
+    }
+}
+
+/// Per-user limits; 0 means unlimited. Admins can override them for single
+/// accounts through `/api/v1/admin/users/{user_id}/quota`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuotaConfig {
+    /// Forever and flush memos together
+    pub max_memos: u64,
+    pub max_flush_memos: u64,
+    /// Total memo content, in UTF-8 bytes
+    pub max_bytes: u64,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_memos: 5_000,
+            max_flush_memos: 500,
+            max_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CleanupConfig {
+    /// Periodically delete expired flush memos while serving
+    pub enabled: bool,
+    pub interval_minutes: u64,
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: 60,
+        }
+    }
+}
+
+impl CleanupConfig {
+    pub fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.interval_minutes * 60)
+    }
+
+    /// Age after which the last successful run counts as overdue: three
+    /// missed intervals, or `None` when the job is disabled
+    pub fn max_age(&self) -> Option<Duration> {
+        self.enabled.then(|| Duration::minutes(self.interval_minutes as i64 * 3))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    /// Readiness fails when the database directory has less free space than this
+    pub min_free_disk_mb: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self { min_free_disk_mb: 100 }
+    }
+}
+
+/// Prometheus `/metrics` endpoint. When enabled it must be protected by a
+/// bearer token, a separate bind address, or both.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Scrapers must send `Authorization: Bearer <token>`
+    pub token: Option<String>,
+    /// Serve `/metrics` on its own listener, e.g. `127.0.0.1:9100`, instead of the main one
+    pub bind: Option<String>,
+}
+
+impl fmt::Debug for MetricsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricsConfig")
+            .field("enabled", &self.enabled)
+            .field("token", &redacted(&self.token))
+            .field("bind", &self.bind)
+            .finish()
+    }
+}
+
+impl MetricsConfig {
+    pub fn bind_addr(&self) -> Result<Option<SocketAddr>> {
+        self.bind
+            .as_deref()
+            .map(|bind| {
+                bind.parse()
+                    .map_err(|_| anyhow!("metrics.bind must be an address like 127.0.0.1:9100, got {:?}", bind))
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    /// Audit events older than this are deleted by the cleanup job
+    pub retention_days: i64,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self { retention_days: 90 }
+    }
+}
+
+impl AuditConfig {
+    pub fn retention(&self) -> Duration {
+        Duration::days(self.retention_days)
+    }
+}
+
+/// Scheduled online snapshots of the database
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    /// Take a snapshot every `interval_hours` while serving
+    pub enabled: bool,
+    /// Where scheduled, CLI and admin API snapshots are written
+    pub dir: PathBuf,
+    pub interval_hours: u64,
+    /// Number of snapshots kept in `dir`; older ones are deleted after each backup
+    pub keep: usize,
+    /// Run an integrity check on each new snapshot and discard it if it fails
+    pub verify: bool,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from("backups"),
+            interval_hours: 24,
+            keep: 7,
+            verify: true,
+        }
+    }
+}
+
+impl BackupConfig {
+    pub fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.interval_hours * 60 * 60)
+    }
+}
+
+/// Admin account ensured at startup: created with `password` if missing, or
+/// promoted if `password` matches the existing user
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for AdminConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdminConfig")
+            .field("username", &self.username)
+            .field("password", &redacted(&self.password))
+            .finish()
+    }
+}
+
+/// Tools for demonstrating the app, never for production
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DebugConfig {
+    /// Let admins shift the clock used for memo expiry through
+    /// `/api/admin/debug/clock`, to show flush memos expiring on staging
+    pub time_offset: bool,
+}
+
+/// Stand-in for secrets in `Debug` output, so a logged config never leaks them
+fn redacted(secret: &Option<String>) -> Option<&'static str> {
+    secret.as_ref().map(|_| "[redacted]")
+}
+
+impl Config {
+    /// Load the config file (explicit path, `CONFIG_FILE`, or [`DEFAULT_CONFIG_FILE`]
+    /// if present), apply environment overrides and validate the result
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os("CONFIG_FILE").map(PathBuf::from))
+            .or_else(|| Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|p| p.exists()));
+
+        let contents = match &path {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .map_err(|e| anyhow!("Failed to read config file {}: {}", path.display(), e))?,
+            ),
+            None => None,
+        };
+
+        Self::from_sources(contents.as_deref(), |name| env::var(name).ok())
+            .map_err(|e| match &path {
+                Some(path) => anyhow!("{}: {}", path.display(), e),
+                None => e,
+            })
+    }
+
+    /// Build a validated config from TOML contents and an environment lookup
+    pub fn from_sources(toml: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut config: Config = match toml {
+            Some(contents) => toml::from_str(contents).map_err(|e| anyhow!("Invalid config file: {}", e))?,
+            None => Config::default(),
+        };
+        config.apply_env(env)?;
+        config.normalize();
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<()> {
+        fn parse<T: FromStr>(name: &str, value: String) -> Result<T> {
+            value
+                .parse()
+                .map_err(|_| anyhow!("{} has an invalid value {:?}", name, value))
+        }
+
+        if let Some(value) = env("RUST_ENV") {
+            self.environment = parse("RUST_ENV", value)?;
+        }
+        if let Some(value) = env("LOG_FORMAT") {
+            self.logging.format = parse("LOG_FORMAT", value)?;
+        }
+        if let Some(value) = env("RUST_LOG") {
+            self.logging.filter = value;
+        }
+        if let Some(value) = env("HOST") {
+            self.server.host = value;
+        }
+        if let Some(value) = env("PORT") {
+            self.server.port = parse("PORT", value)?;
+        }
+        if let Some(value) = env("CORS_ORIGIN") {
+            self.server.cors_origins = value
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Some(value) = env("STATIC_DIR") {
+            self.server.static_dir = Some(PathBuf::from(value)).filter(|dir| !dir.as_os_str().is_empty());
+        }
+        if let Some(value) = env("BASE_PATH") {
+            self.server.base_path = value;
+        }
+        if let Some(value) = env("SHUTDOWN_TIMEOUT_SECONDS") {
+            self.server.shutdown_timeout_seconds = parse("SHUTDOWN_TIMEOUT_SECONDS", value)?;
+        }
+        if let Some(value) = env("TRUST_FORWARDED_FOR") {
+            self.server.trust_forwarded_for = parse_bool("TRUST_FORWARDED_FOR", &value)?;
+        }
+        if let Some(value) = env("TLS_CERT_PATH") {
+            self.tls.cert_path = Some(PathBuf::from(value)).filter(|path| !path.as_os_str().is_empty());
+        }
+        if let Some(value) = env("TLS_KEY_PATH") {
+            self.tls.key_path = Some(PathBuf::from(value)).filter(|path| !path.as_os_str().is_empty());
+        }
+        if let Some(value) = env("TLS_RELOAD_INTERVAL_SECONDS") {
+            self.tls.reload_interval_seconds = parse("TLS_RELOAD_INTERVAL_SECONDS", value)?;
+        }
+        if let Some(value) = env("TLS_REDIRECT_HTTP_PORT") {
+            self.tls.redirect_http_port = Some(parse("TLS_REDIRECT_HTTP_PORT", value)?);
+        }
+        if let Some(value) = env("API_UNVERSIONED_ALIASES") {
+            self.api.unversioned_aliases = parse_bool("API_UNVERSIONED_ALIASES", &value)?;
+        }
+        if let Some(value) = env("API_UNVERSIONED_SUNSET") {
+            self.api.unversioned_sunset = parse("API_UNVERSIONED_SUNSET", value)?;
+        }
+        if let Some(value) = env("RATE_LIMIT_ENABLED") {
+            self.rate_limit.enabled = parse_bool("RATE_LIMIT_ENABLED", &value)?;
+        }
+        if let Some(value) = env("RATE_LIMIT_READ_PER_MINUTE") {
+            self.rate_limit.read_per_minute = parse("RATE_LIMIT_READ_PER_MINUTE", value)?;
+        }
+        if let Some(value) = env("RATE_LIMIT_READ_BURST") {
+            self.rate_limit.read_burst = parse("RATE_LIMIT_READ_BURST", value)?;
+        }
+        if let Some(value) = env("RATE_LIMIT_WRITE_PER_MINUTE") {
+            self.rate_limit.write_per_minute = parse("RATE_LIMIT_WRITE_PER_MINUTE", value)?;
+        }
+        if let Some(value) = env("RATE_LIMIT_WRITE_BURST") {
+            self.rate_limit.write_burst = parse("RATE_LIMIT_WRITE_BURST", value)?;
+        }
+        if let Some(value) = env("RATE_LIMIT_GLOBAL_PER_MINUTE") {
+            self.rate_limit.global_per_minute = parse("RATE_LIMIT_GLOBAL_PER_MINUTE", value)?;
+        }
+        if let Some(value) = env("RATE_LIMIT_PERSIST") {
+            self.rate_limit.persist = parse_bool("RATE_LIMIT_PERSIST", &value)?;
+        }
+        if let Some(value) = env("SECURITY_HEADERS_ENABLED") {
+            self.security_headers.enabled = parse_bool("SECURITY_HEADERS_ENABLED", &value)?;
+        }
+        if let Some(value) = env("CONTENT_SECURITY_POLICY") {
+            self.security_headers.content_security_policy = Some(value).filter(|policy| !policy.trim().is_empty());
+        }
+        if let Some(value) = env("CSP_REPORT_ONLY") {
+            self.security_headers.csp_report_only = parse_bool("CSP_REPORT_ONLY", &value)?;
+        }
+        if let Some(value) = env("FRAME_ANCESTORS") {
+            self.security_headers.frame_ancestors = value
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Some(value) = env("REFERRER_POLICY") {
+            self.security_headers.referrer_policy = value;
+        }
+        if let Some(value) = env("HSTS_ENABLED") {
+            self.security_headers.hsts = Some(parse_bool("HSTS_ENABLED", &value)?);
+        }
+        if let Some(value) = env("HSTS_MAX_AGE_SECONDS") {
+            self.security_headers.hsts_max_age_seconds = parse("HSTS_MAX_AGE_SECONDS", value)?;
+        }
+        if let Some(value) = env("DATABASE_URL") {
+            self.database.url = value;
+        }
+        if let Some(value) = env("MAX_CONNECTIONS") {
+            self.database.max_connections = parse("MAX_CONNECTIONS", value)?;
+        }
+        if let Some(value) = env("TIMEOUT_SECONDS") {
+            self.database.acquire_timeout_seconds = parse("TIMEOUT_SECONDS", value)?;
+        }
+        if let Some(value) = env("SESSION_IDLE_TIMEOUT_MINUTES") {
+            self.session.idle_timeout_minutes = parse("SESSION_IDLE_TIMEOUT_MINUTES", value)?;
+        }
+        if let Some(value) = env("SESSION_ABSOLUTE_TIMEOUT_HOURS") {
+            self.session.absolute_timeout_hours = parse("SESSION_ABSOLUTE_TIMEOUT_HOURS", value)?;
+        }
+        if let Some(value) = env("SESSION_REMEMBER_ME_DAYS") {
+            self.session.remember_me_days = parse("SESSION_REMEMBER_ME_DAYS", value)?;
+        }
+        if let Some(value) = env("SESSION_SECRET") {
+            self.session.secret = Some(value);
+        }
+        if let Some(value) = env("COOKIE_SECURE") {
+            self.session.cookie_secure = Some(parse_bool("COOKIE_SECURE", &value)?);
+        }
+        if let Some(value) = env("COOKIE_SAME_SITE") {
+            self.session.cookie_same_site = parse("COOKIE_SAME_SITE", value)?;
+        }
+        if let Some(value) = env("ARGON2_MEMORY_KIB") {
+            self.password.memory_kib = parse("ARGON2_MEMORY_KIB", value)?;
+        }
+        if let Some(value) = env("ARGON2_ITERATIONS") {
+            self.password.iterations = parse("ARGON2_ITERATIONS", value)?;
+        }
+        if let Some(value) = env("ARGON2_PARALLELISM") {
+            self.password.parallelism = parse("ARGON2_PARALLELISM", value)?;
+        }
+        if let Some(value) = env("FLUSH_MEMO_TTL_HOURS") {
+            self.memo.flush_ttl_hours = parse("FLUSH_MEMO_TTL_HOURS", value)?;
+        }
+        if let Some(value) = env("MEMO_MAX_CONTENT_CHARS") {
+            self.memo.max_content_chars = parse("MEMO_MAX_CONTENT_CHARS", value)?;
+        }
+        if let Some(value) = env("QUOTA_MAX_MEMOS") {
+            self.quota.max_memos = parse("QUOTA_MAX_MEMOS", value)?;
+        }
+        if let Some(value) = env("QUOTA_MAX_FLUSH_MEMOS") {
+            self.quota.max_flush_memos = parse("QUOTA_MAX_FLUSH_MEMOS", value)?;
+        }
+        if let Some(value) = env("QUOTA_MAX_BYTES") {
+            self.quota.max_bytes = parse("QUOTA_MAX_BYTES", value)?;
+        }
+        if let Some(value) = env("ENABLE_CLEANUP_JOB") {
+            self.cleanup.enabled = parse_bool("ENABLE_CLEANUP_JOB", &value)?;
+        }
+        if let Some(value) = env("CLEANUP_INTERVAL_MINUTES") {
+            self.cleanup.interval_minutes = parse("CLEANUP_INTERVAL_MINUTES", value)?;
+        }
+        if let Some(value) = env("HEALTH_MIN_FREE_DISK_MB") {
+            self.health.min_free_disk_mb = parse("HEALTH_MIN_FREE_DISK_MB", value)?;
+        }
+        if let Some(value) = env("METRICS_ENABLED") {
+            self.metrics.enabled = parse_bool("METRICS_ENABLED", &value)?;
+        }
+        if let Some(value) = env("METRICS_TOKEN") {
+            self.metrics.token = Some(value).filter(|token| !token.is_empty());
+        }
+        if let Some(value) = env("METRICS_BIND") {
+            self.metrics.bind = Some(value).filter(|bind| !bind.is_empty());
+        }
+        if let Some(value) = env("AUDIT_RETENTION_DAYS") {
+            self.audit.retention_days = parse("AUDIT_RETENTION_DAYS", value)?;
+        }
+        if let Some(value) = env("BACKUP_ENABLED") {
+            self.backup.enabled = parse_bool("BACKUP_ENABLED", &value)?;
+        }
+        if let Some(value) = env("BACKUP_DIR") {
+            self.backup.dir = PathBuf::from(value);
+        }
+        if let Some(value) = env("BACKUP_INTERVAL_HOURS") {
+            self.backup.interval_hours = parse("BACKUP_INTERVAL_HOURS", value)?;
+        }
+        if let Some(value) = env("BACKUP_KEEP") {
+            self.backup.keep = parse("BACKUP_KEEP", value)?;
+        }
+        if let Some(value) = env("BACKUP_VERIFY") {
+            self.backup.verify = parse_bool("BACKUP_VERIFY", &value)?;
+        }
+        if let Some(value) = env("ADMIN_USERNAME") {
+            self.admin.username = Some(value);
+        }
+        if let Some(value) = env("ADMIN_PASSWORD") {
+            self.admin.password = Some(value);
+        }
+        if let Some(value) = env("DEBUG_TIME_OFFSET") {
+            self.debug.time_offset = parse_bool("DEBUG_TIME_OFFSET", &value)?;
+        }
+        Ok(())
+    }
+
+    /// Canonicalize values that have several equivalent spellings
+    fn normalize(&mut self) {
+        // "/memory_memo/" and "/memory_memo" mean the same; "/" means no prefix
+        let base_path = self.server.base_path.trim().trim_end_matches('/');
+        self.server.base_path = base_path.to_string();
+    }
+
+    /// Check the whole config, reporting every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.filter) {
+            errors.push(format!("logging.filter: {}", e));
+        }
+        if let Err(e) = self.server.bind_addr() {
+            errors.push(e.to_string());
+        }
+        for origin in &self.server.cors_origins {
+            let valid = (origin.starts_with("http://") || origin.starts_with("https://"))
+                && origin.parse::<axum::http::HeaderValue>().is_ok()
+                && !origin.ends_with('/');
+            if !valid {
+                errors.push(format!("server.cors_origins: {:?} is not an origin like https://example.com", origin));
+            }
+        }
+        let base_path = &self.server.base_path;
+        let valid_base_path = base_path.is_empty()
+            || (base_path.starts_with('/')
+                && !base_path.contains("//")
+                && base_path
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.' | '~')));
+        if !valid_base_path {
+            errors.push(format!("server.base_path must look like /memory_memo, got {:?}", base_path));
+        }
+        if let Some(dir) = &self.server.static_dir {
+            if !dir.join("index.html").is_file() {
+                errors.push(format!("server.static_dir: {} does not contain index.html", dir.display()));
+            }
+        }
+        if self.server.shutdown_timeout_seconds == 0 {
+            errors.push("server.shutdown_timeout_seconds must be positive".to_string());
+        }
+        let tls = &self.tls;
+        if tls.cert_path.is_some() != tls.key_path.is_some() {
+            errors.push("tls.cert_path and tls.key_path must be set together".to_string());
+        }
+        for (name, path) in [("tls.cert_path", &tls.cert_path), ("tls.key_path", &tls.key_path)] {
+            if let Some(path) = path.as_ref().filter(|path| !path.is_file()) {
+                errors.push(format!("{}: {} is not a file", name, path.display()));
+            }
+        }
+        if let Some(port) = tls.redirect_http_port {
+            if !tls.enabled() {
+                errors.push("tls.redirect_http_port requires tls.cert_path and tls.key_path".to_string());
+            } else if port == self.server.port {
+                errors.push("tls.redirect_http_port must differ from server.port".to_string());
+            }
+        }
+        let rate_limit = &self.rate_limit;
+        for (name, value) in [
+            ("rate_limit.read_per_minute", rate_limit.read_per_minute),
+            ("rate_limit.read_burst", rate_limit.read_burst),
+            ("rate_limit.write_per_minute", rate_limit.write_per_minute),
+            ("rate_limit.write_burst", rate_limit.write_burst),
+        ] {
+            if value == 0 {
+                errors.push(format!("{} must be positive", name));
+            }
+        }
+        let security_headers = &self.security_headers;
+        if let Some(policy) = &security_headers.content_security_policy {
+            if policy.parse::<axum::http::HeaderValue>().is_err() {
+                errors.push("security_headers.content_security_policy is not a valid header value".to_string());
+            }
+        }
+        for origin in &security_headers.frame_ancestors {
+            let valid = origin == "'self'"
+                || ((origin.starts_with("http://") || origin.starts_with("https://"))
+                    && !origin.contains(|c: char| c.is_whitespace() || c == ';' || c == ',' || c == '\''));
+            if !valid {
+                errors.push(format!(
+                    "security_headers.frame_ancestors: {:?} is not an origin like https://example.com or 'self'",
+                    origin
+                ));
+            }
+        }
+        if !REFERRER_POLICIES.contains(&security_headers.referrer_policy.as_str()) {
+            errors.push(format!(
+                "security_headers.referrer_policy must be one of {}, got {:?}",
+                REFERRER_POLICIES.join(", "),
+                security_headers.referrer_policy
+            ));
+        }
+        if self.database.url.trim().is_empty() {
+            errors.push("database.url must not be empty".to_string());
+        }
+
+        let session = &self.session;
+        check_duration(&mut errors, "session.idle_timeout_minutes", session.idle_timeout_minutes, MINUTES_PER_DAY);
+        check_duration(&mut errors, "session.absolute_timeout_hours", session.absolute_timeout_hours, HOURS_PER_DAY);
+        check_duration(&mut errors, "session.remember_me_days", session.remember_me_days, 1);
+        if session.idle_timeout_minutes > session.absolute_timeout_hours.saturating_mul(60) {
+            errors.push("session.idle_timeout_minutes must not exceed session.absolute_timeout_hours".to_string());
+        }
+        match &session.secret {
+            Some(secret) if secret.len() < MIN_SECRET_BYTES => {
+                errors.push(format!("session.secret must be at least {} bytes long", MIN_SECRET_BYTES));
+            }
+            None if self.is_production() => errors.push("session.secret must be set in production".to_string()),
+            _ => {}
+        }
+
+        match Backend::from_url(&self.database.url) {
+            Ok(Backend::Sqlite) => {
+                if let Err(e) = crate::storage::sqlite::connect_options(&self.database.url) {
+                    errors.push(format!("database.url: {}", e));
+                }
+            }
+            Ok(Backend::Postgres) => {
+                if self.backup.enabled {
+                    errors.push("backup.enabled requires a SQLite database; use pg_dump for PostgreSQL".to_string());
+                }
+            }
+            Err(e) => errors.push(format!("database.url: {}", e)),
+        }
+        if self.database.max_connections == 0 {
+            errors.push("database.max_connections must be positive".to_string());
+        }
+        if self.database.acquire_timeout_seconds == 0 {
+            errors.push("database.acquire_timeout_seconds must be positive".to_string());
+        }
+        if let Err(e) = PasswordHasher::new(self.password) {
+            errors.push(format!("password: {}", e));
+        }
+        check_duration(&mut errors, "memo.flush_ttl_hours", self.memo.flush_ttl_hours, HOURS_PER_DAY);
+        if self.memo.max_content_chars == 0 {
+            errors.push("memo.max_content_chars must be positive".to_string());
+        }
+        let cleanup_interval = i64::try_from(self.cleanup.interval_minutes).unwrap_or(i64::MAX);
+        check_duration(&mut errors, "cleanup.interval_minutes", cleanup_interval, MINUTES_PER_DAY);
+        check_duration(&mut errors, "audit.retention_days", self.audit.retention_days, 1);
+        let backup_interval = i64::try_from(self.backup.interval_hours).unwrap_or(i64::MAX);
+        check_duration(&mut errors, "backup.interval_hours", backup_interval, HOURS_PER_DAY);
+        if self.backup.keep == 0 {
+            errors.push("backup.keep must be at least 1".to_string());
+        }
+        let metrics = &self.metrics;
+        if let Err(e) = metrics.bind_addr() {
+            errors.push(e.to_string());
+        }
+        if metrics.token.as_ref().is_some_and(|token| token.len() < MIN_METRICS_TOKEN_BYTES) {
+            errors.push(format!("metrics.token must be at least {} bytes long", MIN_METRICS_TOKEN_BYTES));
+        }
+        if metrics.enabled && metrics.token.is_none() && metrics.bind.is_none() {
+            errors.push("metrics.enabled requires metrics.token or metrics.bind to restrict access".to_string());
+        }
+        if self.admin.password.is_some() && self.admin.username.is_none() {
+            errors.push("admin.password is set without admin.username".to_string());
+        }
+        if self.debug.time_offset && self.is_production() {
+            errors.push("debug.time_offset must not be enabled in production".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid configuration:\n  - {}", errors.join("\n  - ")))
+        }
+    }
+
+    pub fn is_production(&self) -> bool {
+        self.environment == Environment::Production
+    }
+
+    /// Whether cookies carry the `Secure` attribute
+    pub fn cookie_secure(&self) -> bool {
+        self.session.cookie_secure.unwrap_or(self.is_production() || self.tls.enabled())
+    }
+
+    /// Whether responses carry `Strict-Transport-Security`
+    pub fn hsts(&self) -> bool {
+        self.security_headers.hsts.unwrap_or(self.tls.enabled())
+    }
+}
+
+/// Minimum length of the session secret in bytes
+pub const MIN_SECRET_BYTES: usize = 32;
+
+/// Minimum length of the metrics bearer token in bytes
+pub const MIN_METRICS_TOKEN_BYTES: usize = 16;
+
+/// Longest duration setting accepted, about ten years. Anything longer is a
+/// mistake, and would overflow date arithmetic somewhere down the line.
+pub const MAX_DURATION_DAYS: i64 = 3650;
+
+const HOURS_PER_DAY: i64 = 24;
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+/// Check a duration setting, counted in units of which there are `per_day`
+/// in a day, is positive and at most [`MAX_DURATION_DAYS`]
+fn check_duration(errors: &mut Vec<String>, name: &str, value: i64, per_day: i64) {
+    if value <= 0 {
+        errors.push(format!("{} must be positive, got {}", name, value));
+    } else if value > MAX_DURATION_DAYS * per_day {
+        errors.push(format!(
+            "{} must be at most {} ({} days), got {}",
+            name,
+            MAX_DURATION_DAYS * per_day,
+            MAX_DURATION_DAYS,
+            value
+        ));
+    }
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(anyhow!("{} must be true or false, got {:?}", name, value)),
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("log format must be text or json, got {:?}", value)),
+        }
+    }
+}
+
+impl TryFrom<String> for LogFormat {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl FromStr for Environment {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "development" | "dev" => Ok(Environment::Development),
+            "production" | "prod" => Ok(Environment::Production),
+            _ => Err(format!("environment must be development or production, got {:?}", value)),
+        }
+    }
+}
+
+impl TryFrom<String> for Environment {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Environment::Development => "development",
+            Environment::Production => "production",
+        })
+    }
+}
+
+impl FromStr for SameSitePolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "lax" => Ok(SameSitePolicy::Lax),
+            "strict" => Ok(SameSitePolicy::Strict),
+            _ => Err(format!("cookie_same_site must be Lax or Strict, got {:?}", value)),
+        }
+    }
+}
+
+impl TryFrom<String> for SameSitePolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
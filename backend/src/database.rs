@@ -1,29 +1,40 @@
-use sqlx::{Pool, Sqlite, SqlitePool};
-use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
 use anyhow::Result;
+use crate::config::DatabaseConfig;
+use crate::storage::{self, PostgresStorage, SqliteStorage};
 
-pub type DatabasePool = Pool<Sqlite>;
+pub use crate::storage::Database;
 
-/// Initialize the database connection pool and run migrations
-pub async fn init_database(database_url: &str) -> Result<DatabasePool> {
-    // Create database file if it doesn't exist
-    if let Some(parent) = Path::new(database_url.strip_prefix("sqlite://").unwrap_or(database_url)).parent() {
-        tokio::fs::create_dir_all(parent).await?;
+/// Connect to the database named by the config and run its migrations
+pub async fn init_database(config: &DatabaseConfig) -> Result<Database> {
+    storage::connect(config).await
+}
+
+/// Path of the database file behind a `sqlite:` URL, or `None` for in-memory
+/// databases and other backends
+pub fn sqlite_file_path(database_url: &str) -> Option<PathBuf> {
+    let rest = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"))?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if path.is_empty() || path == ":memory:" || query.split('&').any(|param| param == "mode=memory") {
+        return None;
     }
+    Some(PathBuf::from(path))
+}
 
-    // Connect to database
-    let pool = SqlitePool::connect(database_url).await?;
-    
-    // Run migrations
-    sqlx::migrate!("./migrations").run(&pool).await?;
-    
-    tracing::info!("Database initialized successfully");
-    Ok(pool)
+/// Flush pending writes and close every connection
+pub async fn close_database(db: &Database) {
+    db.close().await;
+    tracing::info!("Database closed");
 }
 
-/// Create an in-memory database for testing
-pub async fn create_test_database() -> Result<DatabasePool> {
-    let pool = SqlitePool::connect("sqlite::memory:").await?;
-    sqlx::migrate!("./migrations").run(&pool).await?;
-    Ok(pool)
-}
\ No newline at end of file
+/// Create an empty database for testing: an in-memory SQLite database, or a
+/// private schema on the PostgreSQL server named by `TEST_DATABASE_URL`
+pub async fn create_test_database() -> Result<Database> {
+    match std::env::var("TEST_DATABASE_URL") {
+        Ok(url) if !url.starts_with("sqlite:") => Ok(Arc::new(PostgresStorage::isolated(&url).await?)),
+        _ => Ok(Arc::new(SqliteStorage::in_memory().await?)),
+    }
+}
@@ -1,8 +1,18 @@
+pub mod clock;
+pub mod config;
 pub mod database;
 pub mod models;
 pub mod services;
+pub mod shutdown;
+pub mod storage;
+pub mod tls;
+pub mod validation;
 pub mod api;
 pub mod app;
+pub mod assets;
+pub mod cli;
+pub mod logging;
+pub mod metrics;
 
 pub use database::*;
 pub use models::*;
@@ -0,0 +1,61 @@
+use anyhow::{Result, anyhow};
+use axum::extract::Request;
+use tracing::{level_filters::LevelFilter, Span, Subscriber};
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::MakeWriter,
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+use crate::api::middleware::REQUEST_ID_HEADER;
+use crate::config::{LogFormat, LoggingConfig};
+use crate::metrics::QueryTimingLayer;
+
+/// Install the global subscriber: log lines in the configured format, plus
+/// the sqlx statement events that feed the query latency metric
+pub fn init(config: &LoggingConfig) -> Result<()> {
+    let filter = EnvFilter::try_new(&config.filter)?;
+    tracing_subscriber::registry()
+        .with(fmt_layer(config.format, std::io::stdout).with_filter(filter))
+        .with(QueryTimingLayer.with_filter(Targets::new().with_target("sqlx::query", LevelFilter::DEBUG)))
+        .try_init()
+        .map_err(|e| anyhow!("Failed to initialize logging: {}", e))
+}
+
+/// Formatting layer writing to `writer`. JSON lines carry the fields of every
+/// enclosing span, so `request_id` and `user_id` appear on service logs too.
+pub fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync + 'static>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
+
+/// Span wrapping each HTTP request. `user_id` is filled in once the session
+/// is known. Only the path is recorded: query strings and headers may carry
+/// credentials.
+pub fn request_span(request: &Request) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+        user_id = tracing::field::Empty,
+    )
+}
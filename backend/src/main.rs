@@ -1,34 +1,163 @@
-use memory_memo::{create_app, database::init_database};
-use tracing_subscriber;
-use std::env;
+use clap::Parser;
+use memory_memo::{
+    cli::{run_command, Cli, Command},
+    config::Config,
+    database::{close_database, init_database, Database},
+    logging,
+    metrics_router, router,
+    services::{AdminService, PasswordHasher},
+    shutdown::{serve_tls_with_shutdown, serve_with_shutdown, termination_signal},
+    tls::{redirect_router, TlsCertificate},
+    AppState,
+};
+use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
 
-    // Get database URL from environment or use default
-    let database_url = env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "sqlite://./memory_memo.db".to_string());
+    // Load and validate configuration before touching the database
+    let config = Config::load(cli.config.as_deref())?;
 
-    // Create database connection pool and run migrations
-    let pool = init_database(&database_url).await?;
+    // Initialize tracing in the configured format
+    logging::init(&config.logging)?;
 
-    // Create app
-    let app = create_app(pool).await?;
+    // Connect to the configured database and run migrations
+    let db = init_database(&config.database).await?;
 
-    // Get port from environment or use default
-    let port = env::var("PORT")
-        .unwrap_or_else(|_| "3000".to_string());
-    
-    let bind_addr = format!("127.0.0.1:{}", port);
+    let result = match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(db.clone(), config).await,
+        command => run_command(command, db.clone(), &config, &mut std::io::stdout())
+            .await
+            .map_err(Into::into),
+    };
+
+    close_database(&db).await;
+    result
+}
+
+async fn serve(db: Database, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    // Bootstrap the first admin account if requested
+    if let Some(admin_username) = &config.admin.username {
+        let admin_service = AdminService::with_hasher(db.clone(), PasswordHasher::new(config.password)?);
+        let admin = admin_service.bootstrap_admin(admin_username, config.admin.password.as_deref()).await?;
+        println!("👑 Admin user: {}", admin.username);
+    }
+
+    // Cancelled on SIGTERM/SIGINT to stop the server and background jobs together
+    let shutdown = CancellationToken::new();
+
+    let bind_addr = config.server.bind_addr()?;
+    let metrics_addr = config.metrics.enabled.then(|| config.metrics.bind_addr()).transpose()?.flatten();
+    let drain_timeout = config.server.shutdown_timeout();
+    // Fail before binding anything if the certificate cannot be used
+    let certificate = if config.tls.enabled() {
+        Some(TlsCertificate::load(&config.tls).await?)
+    } else {
+        None
+    };
+    let database_url = config.database.redacted_url();
+
+    // Create app, restoring sessions (and rate limits, if persisted) saved by the previous shutdown
+    let state = AppState::new(db.clone(), config)?;
+    let restored = state.session_store.load(&db).await?;
+    let persist_rate_limits = state.config.rate_limit.enabled && state.config.rate_limit.persist;
+    if persist_rate_limits {
+        state.rate_limiter.load(&db).await?;
+    }
+    let app = router(state.clone())?;
+
+    // Periodically remove expired flush memos
+    let cleanup_job = state.config.cleanup.enabled.then(|| {
+        state.cleanup_service().spawn_periodic(state.config.cleanup.interval(), shutdown.clone())
+    });
+
+    // Scheduled online snapshots with rotation
+    let backup_job = state
+        .backup_service()
+        .filter(|_| state.config.backup.enabled)
+        .map(|backup| backup.spawn_periodic(state.config.backup.interval(), shutdown.clone()));
+
+    // Metrics on their own listener, e.g. reachable only from the private network
+    if let Some(metrics_addr) = metrics_addr {
+        let listener = tokio::net::TcpListener::bind(metrics_addr).await?;
+        let metrics_app = metrics_router(state.clone());
+        let stop = shutdown.clone();
+        tokio::spawn(async move {
+            let served = axum::serve(listener, metrics_app)
+                .with_graceful_shutdown(stop.cancelled_owned())
+                .await;
+            if let Err(e) = served {
+                tracing::error!("Metrics listener failed: {}", e);
+            }
+        });
+        println!("📈 Metrics on http://{}/metrics", metrics_addr);
+    }
+
+    // Plain HTTP next to HTTPS, only to send clients over
+    if let Some(redirect_port) = state.config.tls.redirect_http_port.filter(|_| certificate.is_some()) {
+        let redirect_addr = std::net::SocketAddr::new(bind_addr.ip(), redirect_port);
+        let listener = tokio::net::TcpListener::bind(redirect_addr).await?;
+        let redirect_app = redirect_router(bind_addr.port());
+        let stop = shutdown.clone();
+        tokio::spawn(async move {
+            let served = axum::serve(listener, redirect_app)
+                .with_graceful_shutdown(stop.cancelled_owned())
+                .await;
+            if let Err(e) = served {
+                tracing::error!("HTTP redirect listener failed: {}", e);
+            }
+        });
+        println!("↪️  Redirecting http://{} to HTTPS", redirect_addr);
+    }
+
+    // Pick up renewed certificates without a restart
+    if let (Some(certificate), Some(interval)) = (&certificate, state.config.tls.reload_interval()) {
+        certificate.clone().spawn_reloader(interval, shutdown.clone());
+    }
 
     // Start server
-    let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
-    println!("🚀 Server running on http://{}", bind_addr);
+    let listener = tokio::net::TcpListener::bind(bind_addr).await?;
+    let scheme = if certificate.is_some() { "https" } else { "http" };
+    println!("🚀 Server running on {}://{}", scheme, bind_addr);
     println!("📁 Database: {}", database_url);
+    if restored > 0 {
+        println!("🔑 Restored {} sessions", restored);
+    }
+
+    let served = match certificate {
+        Some(certificate) => {
+            let tls = certificate.rustls_config();
+            serve_tls_with_shutdown(listener, tls, app, termination_signal(), shutdown.clone(), drain_timeout).await
+        }
+        None => serve_with_shutdown(listener, app, termination_signal(), shutdown.clone(), drain_timeout).await,
+    };
+    // Wind down the same way when the server failed, so sessions are still saved
+    shutdown.cancel();
+    match &served {
+        Ok(true) => {}
+        Ok(false) => tracing::warn!("Shutdown timeout reached before all requests finished"),
+        Err(e) => tracing::error!("Server failed: {:#}", e),
+    }
+
+    // Let a cleanup run or backup in progress finish before closing the database
+    if let Some(job) = cleanup_job {
+        if tokio::time::timeout(drain_timeout, job).await.is_err() {
+            tracing::warn!("Cleanup job did not stop within {:?}", drain_timeout);
+        }
+    }
+    if let Some(job) = backup_job {
+        if tokio::time::timeout(drain_timeout, job).await.is_err() {
+            tracing::warn!("Backup job did not stop within {:?}", drain_timeout);
+        }
+    }
 
-    axum::serve(listener, app).await?;
+    let saved = state.session_store.save(&db).await?;
+    if persist_rate_limits {
+        state.rate_limiter.save(&db).await?;
+    }
+    served?;
+    println!("👋 Shut down cleanly, saved {} sessions", saved);
 
     Ok(())
-}
\ No newline at end of file
+}
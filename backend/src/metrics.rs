@@ -0,0 +1,179 @@
+use std::sync::LazyLock;
+use std::time::Instant;
+use anyhow::Result;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::{Context, Layer};
+use crate::database::Database;
+
+/// Latency of every SQL statement, fed by [`QueryTimingLayer`].
+///
+/// sqlx reports statement timings only through tracing, which is
+/// process-wide, so this one histogram is shared by every [`Metrics`].
+static DB_QUERY_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    Histogram::with_opts(
+        HistogramOpts::new("db_query_duration_seconds", "SQL statement latency")
+            .buckets(vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]),
+    )
+    .expect("valid histogram options")
+});
+
+/// Prometheus collectors for the `/metrics` endpoint
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    logins: IntCounterVec,
+    memo_operations: IntCounterVec,
+    cleanup_removed: Histogram,
+    active_sessions: IntGauge,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("memory_memo".to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )?;
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route"),
+            &["method", "route"],
+        )?;
+        let logins = IntCounterVec::new(
+            Opts::new("auth_logins_total", "Login attempts by outcome"),
+            &["outcome"],
+        )?;
+        let memo_operations = IntCounterVec::new(
+            Opts::new("memo_operations_total", "Memo writes by operation and memo type"),
+            &["operation", "memo_type"],
+        )?;
+        let cleanup_removed = Histogram::with_opts(
+            HistogramOpts::new("cleanup_flush_memos_removed", "Expired flush memos removed per cleanup run")
+                .buckets(vec![0.0, 1.0, 10.0, 100.0, 1000.0, 10000.0]),
+        )?;
+        let active_sessions = IntGauge::new("sessions_active", "Sessions that have not expired")?;
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )?;
+        let pool_max_connections = IntGauge::new("db_pool_max_connections", "Database pool size limit")?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_duration.clone()))?;
+        registry.register(Box::new(logins.clone()))?;
+        registry.register(Box::new(memo_operations.clone()))?;
+        registry.register(Box::new(cleanup_removed.clone()))?;
+        registry.register(Box::new(active_sessions.clone()))?;
+        registry.register(Box::new(pool_connections.clone()))?;
+        registry.register(Box::new(pool_max_connections.clone()))?;
+        registry.register(Box::new(DB_QUERY_DURATION.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_duration,
+            logins,
+            memo_operations,
+            cleanup_removed,
+            active_sessions,
+            pool_connections,
+            pool_max_connections,
+        })
+    }
+
+    pub fn record_login(&self, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.logins.with_label_values(&[outcome]).inc();
+    }
+
+    /// `operation` is `create`, `update` or `delete`; `memo_type` is `forever` or `flush`
+    pub fn record_memo_operation(&self, operation: &str, memo_type: &str) {
+        self.memo_operations.with_label_values(&[operation, memo_type]).inc();
+    }
+
+    pub fn record_cleanup_run(&self, cleaned_count: u64) {
+        self.cleanup_removed.observe(cleaned_count as f64);
+    }
+
+    /// Refresh point-in-time gauges and encode everything in the text format
+    pub fn render(&self, active_sessions: usize, db: &Database) -> Result<String> {
+        self.active_sessions.set(active_sessions as i64);
+        if let Some(pool) = db.sql_pool() {
+            let (size, idle, max) = pool.connections();
+            self.pool_connections.with_label_values(&["idle"]).set(idle as i64);
+            self.pool_connections.with_label_values(&["in_use"]).set(size as i64 - idle as i64);
+            self.pool_max_connections.set(max as i64);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+/// Count and time every request by its route template, so `/api/memos/forever/:id`
+/// is one series however many memos there are
+pub async fn track_requests(State(metrics): State<Metrics>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        // Static files and 404s share one series
+        .unwrap_or_else(|| "fallback".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics.http_requests.with_label_values(&[&method, &route, &status]).inc();
+    metrics
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
+
+/// Tracing layer recording the `elapsed_secs` sqlx attaches to each statement event.
+///
+/// Install it with a filter enabling `sqlx::query` at debug level, the level
+/// sqlx logs statements at.
+pub struct QueryTimingLayer;
+
+impl<S: tracing::Subscriber> Layer<S> for QueryTimingLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != "sqlx::query" {
+            return;
+        }
+        let mut visitor = ElapsedVisitor(None);
+        event.record(&mut visitor);
+        if let Some(elapsed) = visitor.0 {
+            DB_QUERY_DURATION.observe(elapsed);
+        }
+    }
+}
+
+struct ElapsedVisitor(Option<f64>);
+
+impl Visit for ElapsedVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
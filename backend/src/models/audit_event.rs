@@ -0,0 +1,198 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json, FromRow};
+use anyhow::{Result, anyhow};
+use utoipa::{IntoParams, ToSchema};
+use crate::database::Database;
+
+/// What happened. Stored as the snake_case name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    Register,
+    LoginSucceeded,
+    LoginFailed,
+    Logout,
+    PasswordChanged,
+    SessionsRevoked,
+    MemoCreated,
+    MemoUpdated,
+    MemoDeleted,
+    AdminUserDisabled,
+    AdminUserEnabled,
+    AdminUserCreated,
+    AdminUserDeleted,
+    AdminQuotaChanged,
+    AdminCleanupRun,
+    AdminCleanupForced,
+    AdminBackupCreated,
+    AdminBackupRestored,
+    AdminTimeOffsetChanged,
+}
+
+impl AuditEventType {
+    pub const ALL: [AuditEventType; 19] = [
+        AuditEventType::Register,
+        AuditEventType::LoginSucceeded,
+        AuditEventType::LoginFailed,
+        AuditEventType::Logout,
+        AuditEventType::PasswordChanged,
+        AuditEventType::SessionsRevoked,
+        AuditEventType::MemoCreated,
+        AuditEventType::MemoUpdated,
+        AuditEventType::MemoDeleted,
+        AuditEventType::AdminUserDisabled,
+        AuditEventType::AdminUserEnabled,
+        AuditEventType::AdminUserCreated,
+        AuditEventType::AdminUserDeleted,
+        AuditEventType::AdminQuotaChanged,
+        AuditEventType::AdminCleanupRun,
+        AuditEventType::AdminCleanupForced,
+        AuditEventType::AdminBackupCreated,
+        AuditEventType::AdminBackupRestored,
+        AuditEventType::AdminTimeOffsetChanged,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditEventType::Register => "register",
+            AuditEventType::LoginSucceeded => "login_succeeded",
+            AuditEventType::LoginFailed => "login_failed",
+            AuditEventType::Logout => "logout",
+            AuditEventType::PasswordChanged => "password_changed",
+            AuditEventType::SessionsRevoked => "sessions_revoked",
+            AuditEventType::MemoCreated => "memo_created",
+            AuditEventType::MemoUpdated => "memo_updated",
+            AuditEventType::MemoDeleted => "memo_deleted",
+            AuditEventType::AdminUserDisabled => "admin_user_disabled",
+            AuditEventType::AdminUserEnabled => "admin_user_enabled",
+            AuditEventType::AdminUserCreated => "admin_user_created",
+            AuditEventType::AdminUserDeleted => "admin_user_deleted",
+            AuditEventType::AdminQuotaChanged => "admin_quota_changed",
+            AuditEventType::AdminCleanupRun => "admin_cleanup_run",
+            AuditEventType::AdminCleanupForced => "admin_cleanup_forced",
+            AuditEventType::AdminBackupCreated => "admin_backup_created",
+            AuditEventType::AdminBackupRestored => "admin_backup_restored",
+            AuditEventType::AdminTimeOffsetChanged => "admin_time_offset_changed",
+        }
+    }
+}
+
+impl fmt::Display for AuditEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditEventType {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|event_type| event_type.as_str() == value)
+            .ok_or_else(|| anyhow!("Unknown audit event type {:?}", value))
+    }
+}
+
+/// A recorded event
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub event_type: String,
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    /// Event-specific JSON, e.g. the memo ID
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<Json<serde_json::Value>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An event about to be recorded
+#[derive(Debug, Clone)]
+pub struct NewAuditEvent {
+    pub event_type: AuditEventType,
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub details: Option<serde_json::Value>,
+}
+
+impl NewAuditEvent {
+    pub fn new(event_type: AuditEventType) -> Self {
+        Self {
+            event_type,
+            user_id: None,
+            actor_id: None,
+            ip: None,
+            user_agent: None,
+            request_id: None,
+            details: None,
+        }
+    }
+
+    /// The account the event concerns
+    pub fn user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Who caused the event, when not the account holder
+    pub fn actor(mut self, actor_id: impl Into<String>) -> Self {
+        self.actor_id = Some(actor_id.into());
+        self
+    }
+
+    pub fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Filters for the admin audit query; unset fields match everything
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub event_type: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+/// Most events a single query returns
+pub const MAX_AUDIT_QUERY_LIMIT: i64 = 500;
+
+impl AuditEvent {
+    pub async fn record(db: &Database, event: &NewAuditEvent) -> Result<()> {
+        db.insert_audit_event(event, Utc::now()).await
+    }
+
+    /// Events concerning a user, newest first
+    pub async fn list_for_user(db: &Database, user_id: &str, limit: i64) -> Result<Vec<Self>> {
+        Self::query(db, &AuditQuery {
+            user_id: Some(user_id.to_string()),
+            limit: Some(limit),
+            ..AuditQuery::default()
+        })
+        .await
+    }
+
+    /// Events matching every given filter, newest first
+    pub async fn query(db: &Database, filter: &AuditQuery) -> Result<Vec<Self>> {
+        db.query_audit_events(filter).await
+    }
+
+    /// Delete events older than `cutoff`, returning how many were removed
+    pub async fn delete_older_than(db: &Database, cutoff: DateTime<Utc>) -> Result<u64> {
+        db.delete_audit_events_before(cutoff).await
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration};
-use sqlx::{FromRow, SqlitePool};
+use sqlx::FromRow;
+use crate::database::Database;
+use crate::validation::Validation;
 use anyhow::{Result, anyhow};
 use uuid::Uuid;
 
@@ -13,119 +15,80 @@ pub struct FlushMemo {
     pub expires_at: DateTime<Utc>,
 }
 
+/// Default lifetime of a flush memo
+pub const DEFAULT_FLUSH_TTL_HOURS: i64 = 24;
+
 impl FlushMemo {
-    /// Create a new flush memo that expires in 24 hours
-    pub async fn create(pool: &SqlitePool, user_id: &str, content: &str) -> Result<Self> {
-        // Validate content is not empty
-        if content.trim().is_empty() {
-            return Err(anyhow!("Memo content cannot be empty"));
-        }
+    /// Create a new flush memo at `now` that expires 24 hours later
+    pub async fn create(db: &Database, user_id: &str, content: &str, now: DateTime<Utc>) -> Result<Self> {
+        Self::create_with_ttl(db, user_id, content, Duration::hours(DEFAULT_FLUSH_TTL_HOURS), now).await
+    }
+
+    /// Create a new flush memo at `now` that expires after `ttl`
+    pub async fn create_with_ttl(
+        db: &Database,
+        user_id: &str,
+        content: &str,
+        ttl: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<Self> {
+        Validation::new().not_blank("content", "Memo content", content).finish()?;
 
         // Generate UUID for memo ID
         let memo_id = Uuid::new_v4().to_string();
         
-        // Calculate expiry time (24 hours from now)
-        let created_at = Utc::now();
-        let expires_at = created_at + Duration::hours(24);
+        // Calculate expiry time
+        let created_at = now;
+        let expires_at = created_at + ttl;
         
-        // Insert memo into database
-        sqlx::query(
-            "INSERT INTO flush_memos (id, user_id, content, created_at, expires_at) VALUES (?, ?, ?, ?, ?)"
-        )
-        .bind(&memo_id)
-        .bind(user_id)
-        .bind(content)
-        .bind(created_at)
-        .bind(expires_at)
-        .execute(pool)
-        .await
-        .map_err(|e| anyhow!("Failed to create flush memo: {}", e))?;
-
-        // Retrieve the created memo
-        let memo = sqlx::query_as::<_, FlushMemo>(
-            "SELECT id, user_id, content, created_at, expires_at FROM flush_memos WHERE id = ?"
-        )
-        .bind(&memo_id)
-        .fetch_one(pool)
-        .await?;
+        let memo = FlushMemo {
+            id: memo_id,
+            user_id: user_id.to_string(),
+            content: content.to_string(),
+            created_at,
+            expires_at,
+        };
+        db.insert_flush_memo(&memo).await?;
 
         Ok(memo)
     }
 
-    /// List all non-expired flush memos for a specific user, ordered by newest first
-    pub async fn list_by_user(pool: &SqlitePool, user_id: &str) -> Result<Vec<Self>> {
-        let now = Utc::now();
-        let memos = sqlx::query_as::<_, FlushMemo>(
-            "SELECT id, user_id, content, created_at, expires_at FROM flush_memos 
-             WHERE user_id = ? AND expires_at > ? ORDER BY created_at DESC"
-        )
-        .bind(user_id)
-        .bind(now)
-        .fetch_all(pool)
-        .await?;
-
-        Ok(memos)
+    /// List the user's flush memos not yet expired at `now`, ordered by newest first
+    pub async fn list_by_user(db: &Database, user_id: &str, now: DateTime<Utc>) -> Result<Vec<Self>> {
+        db.list_flush_memos(user_id, now).await
     }
 
     /// Delete a flush memo (only if owned by the user)
-    pub async fn delete(pool: &SqlitePool, memo_id: &str, user_id: &str) -> Result<()> {
-        let rows_affected = sqlx::query(
-            "DELETE FROM flush_memos WHERE id = ? AND user_id = ?"
-        )
-        .bind(memo_id)
-        .bind(user_id)
-        .execute(pool)
-        .await?
-        .rows_affected();
-
-        if rows_affected == 0 {
+    pub async fn delete(db: &Database, memo_id: &str, user_id: &str) -> Result<()> {
+        if !db.delete_flush_memo(memo_id, user_id).await? {
             return Err(anyhow!("Memo not found or access denied"));
         }
 
         Ok(())
     }
 
-    /// Find a specific flush memo by ID (only if owned by the user and not expired)
+    /// Find a specific flush memo by ID (only if owned by the user and not expired at `now`)
     pub async fn find_by_id_and_user(
-        pool: &SqlitePool, 
+        db: &Database, 
         memo_id: &str, 
-        user_id: &str
+        user_id: &str,
+        now: DateTime<Utc>,
     ) -> Result<Option<Self>> {
-        let now = Utc::now();
-        let memo = sqlx::query_as::<_, FlushMemo>(
-            "SELECT id, user_id, content, created_at, expires_at FROM flush_memos 
-             WHERE id = ? AND user_id = ? AND expires_at > ?"
-        )
-        .bind(memo_id)
-        .bind(user_id)
-        .bind(now)
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(memo)
+        db.find_flush_memo(memo_id, user_id, now).await
     }
 
-    /// Cleanup expired flush memos (returns number of deleted memos)
-    pub async fn cleanup_expired(pool: &SqlitePool) -> Result<u64> {
-        let now = Utc::now();
-        let result = sqlx::query(
-            "DELETE FROM flush_memos WHERE expires_at <= ?"
-        )
-        .bind(now)
-        .execute(pool)
-        .await?;
-
-        Ok(result.rows_affected())
+    /// Cleanup memos expired at `now` (returns number of deleted memos)
+    pub async fn cleanup_expired(db: &Database, now: DateTime<Utc>) -> Result<u64> {
+        db.delete_expired_flush_memos(now).await
     }
 
-    /// Check if this memo is expired
-    pub fn is_expired(&self) -> bool {
-        Utc::now() > self.expires_at
+    /// Check if this memo has expired at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now > self.expires_at
     }
 
-    /// Get remaining time until expiry
-    pub fn time_until_expiry(&self) -> Option<Duration> {
-        let now = Utc::now();
+    /// Get remaining time from `now` until expiry
+    pub fn time_until_expiry(&self, now: DateTime<Utc>) -> Option<Duration> {
         if now >= self.expires_at {
             None
         } else {
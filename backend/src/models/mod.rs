@@ -1,7 +1,10 @@
 pub mod user;
 pub mod forever_memo;
 pub mod flush_memo;
+pub mod audit_event;
+pub mod quota;
 
 pub use user::User;
 pub use forever_memo::ForeverMemo;
-pub use flush_memo::FlushMemo;
\ No newline at end of file
+pub use flush_memo::FlushMemo;
+pub use audit_event::{AuditEvent, AuditEventType, NewAuditEvent};
\ No newline at end of file
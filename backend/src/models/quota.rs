@@ -0,0 +1,123 @@
+//! Per-user storage quotas. Every account gets the limits from the `[quota]`
+//! config, and an admin can override any of them for a single account. In
+//! both places 0 means unlimited.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::config::QuotaConfig;
+
+/// Limits in force for one user; `null` is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct QuotaLimits {
+    /// Forever and flush memos together
+    pub max_memos: Option<u64>,
+    pub max_flush_memos: Option<u64>,
+    /// Total memo content, in UTF-8 bytes
+    pub max_bytes: Option<u64>,
+}
+
+impl From<&QuotaConfig> for QuotaLimits {
+    fn from(config: &QuotaConfig) -> Self {
+        let limit = |max: u64| (max > 0).then_some(max);
+        Self {
+            max_memos: limit(config.max_memos),
+            max_flush_memos: limit(config.max_flush_memos),
+            max_bytes: limit(config.max_bytes),
+        }
+    }
+}
+
+impl QuotaLimits {
+    /// Whether `added` can be stored on top of `usage`. Only the counts that
+    /// grow are checked, so a user who is over a lowered limit can still
+    /// delete memos and shorten them.
+    pub fn check(&self, usage: QuotaUsage, added: QuotaUsage) -> Result<(), QuotaExceeded> {
+        let over = |limit: Option<u64>, current: u64, added: u64| {
+            limit.filter(|&max| added > 0 && current.saturating_add(added) > max)
+        };
+
+        if let Some(max) = over(self.max_memos, usage.memos, added.memos) {
+            return Err(QuotaExceeded { limit: "max_memos", max });
+        }
+        if let Some(max) = over(self.max_flush_memos, usage.flush_memos, added.flush_memos) {
+            return Err(QuotaExceeded { limit: "max_flush_memos", max });
+        }
+        if let Some(max) = over(self.max_bytes, usage.bytes, added.bytes) {
+            return Err(QuotaExceeded { limit: "max_bytes", max });
+        }
+        Ok(())
+    }
+}
+
+/// An admin's override for one user. Unset fields fall back to the
+/// configured limit; 0 lifts the limit for this user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaOverride {
+    #[serde(default)]
+    pub max_memos: Option<u64>,
+    #[serde(default)]
+    pub max_flush_memos: Option<u64>,
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+impl QuotaOverride {
+    /// Overrides nothing
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The limits of a user with this override
+    pub fn apply(&self, defaults: QuotaLimits) -> QuotaLimits {
+        let pick = |custom: Option<u64>, default: Option<u64>| match custom {
+            Some(0) => None,
+            Some(max) => Some(max),
+            None => default,
+        };
+        QuotaLimits {
+            max_memos: pick(self.max_memos, defaults.max_memos),
+            max_flush_memos: pick(self.max_flush_memos, defaults.max_flush_memos),
+            max_bytes: pick(self.max_bytes, defaults.max_bytes),
+        }
+    }
+}
+
+/// What a user stores. Flush memos count until they expire, not until the
+/// cleanup job removes them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct QuotaUsage {
+    /// Forever and flush memos together
+    pub memos: u64,
+    pub flush_memos: u64,
+    /// Total memo content, in UTF-8 bytes
+    pub bytes: u64,
+}
+
+/// A user's limits with how much of them is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct QuotaStatus {
+    pub limits: QuotaLimits,
+    pub usage: QuotaUsage,
+}
+
+/// A memo change that would take a user past one of their limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// Name of the limit, as in [`QuotaLimits`]
+    pub limit: &'static str,
+    pub max: u64,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            "max_memos" => write!(f, "Memo limit reached ({} memos)", self.max),
+            "max_flush_memos" => write!(f, "Flush memo limit reached ({} flush memos)", self.max),
+            _ => write!(f, "Storage limit reached ({} bytes)", self.max),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
@@ -1,81 +1,108 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
-use sqlx::{FromRow, SqlitePool};
-use bcrypt::{hash, verify, DEFAULT_COST};
-use anyhow::{Result, anyhow};
+use sqlx::FromRow;
+use anyhow::Result;
+use utoipa::ToSchema;
 use uuid::Uuid;
+use crate::services::password::{PasswordCheck, PasswordHasher};
+use crate::database::Database;
+use crate::validation::Validation;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: String,
     pub username: String,
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
+    pub is_admin: bool,
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+// Written out so the password hash never ends up in logs
+impl std::fmt::Debug for User {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("username", &self.username)
+            .field("password_hash", &"[redacted]")
+            .field("created_at", &self.created_at)
+            .field("is_admin", &self.is_admin)
+            .field("disabled_at", &self.disabled_at)
+            .finish()
+    }
 }
 
 impl User {
     /// Create a new user with hashed password
-    pub async fn create(pool: &SqlitePool, username: &str, password: &str) -> Result<Self> {
-        // Validate username length
-        if username.len() < 3 || username.len() > 50 {
-            return Err(anyhow!("Username must be between 3 and 50 characters"));
-        }
+    pub async fn create(db: &Database, username: &str, password: &str) -> Result<Self> {
+        Self::create_with_hasher(db, username, password, &PasswordHasher::default()).await
+    }
+
+    /// Create a new user, hashing the password with the given hasher
+    pub async fn create_with_hasher(
+        db: &Database,
+        username: &str,
+        password: &str,
+        hasher: &PasswordHasher,
+    ) -> Result<Self> {
+        Validation::new().username("username", username).finish()?;
 
         // Hash the password
-        let password_hash = hash(password, DEFAULT_COST)?;
+        let password_hash = hasher.hash(password)?;
         
         // Generate UUID for user ID
         let user_id = Uuid::new_v4().to_string();
         
-        // Insert user into database
-        sqlx::query(
-            "INSERT INTO users (id, username, password_hash) VALUES (?, ?, ?)"
-        )
-        .bind(&user_id)
-        .bind(username)
-        .bind(&password_hash)
-        .execute(pool)
-        .await
-        .map_err(|e| anyhow!("Failed to create user: {}", e))?;
-
-        // Retrieve the created user
-        let user = sqlx::query_as::<_, User>(
-            "SELECT id, username, password_hash, created_at FROM users WHERE id = ?"
-        )
-        .bind(&user_id)
-        .fetch_one(pool)
-        .await?;
-
-        Ok(user)
+        db.insert_user(&user_id, username, &password_hash).await
     }
 
     /// Find user by username
-    pub async fn find_by_username(pool: &SqlitePool, username: &str) -> Result<Option<Self>> {
-        let user = sqlx::query_as::<_, User>(
-            "SELECT id, username, password_hash, created_at FROM users WHERE username = ?"
-        )
-        .bind(username)
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(user)
+    pub async fn find_by_username(db: &Database, username: &str) -> Result<Option<Self>> {
+        db.find_user_by_username(username).await
     }
 
     /// Find user by ID
-    pub async fn find_by_id(pool: &SqlitePool, user_id: &str) -> Result<Option<Self>> {
-        let user = sqlx::query_as::<_, User>(
-            "SELECT id, username, password_hash, created_at FROM users WHERE id = ?"
-        )
-        .bind(user_id)
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(user)
+    pub async fn find_by_id(db: &Database, user_id: &str) -> Result<Option<Self>> {
+        db.find_user_by_id(user_id).await
+    }
+
+    /// Replace the stored password hash
+    pub async fn update_password_hash(db: &Database, user_id: &str, password_hash: &str) -> Result<()> {
+        db.update_password_hash(user_id, password_hash).await
+    }
+
+    /// Grant or revoke the admin role
+    pub async fn set_admin(db: &Database, user_id: &str, is_admin: bool) -> Result<bool> {
+        db.set_admin(user_id, is_admin).await
+    }
+
+    /// Disable or re-enable an account
+    pub async fn set_disabled(db: &Database, user_id: &str, disabled: bool) -> Result<bool> {
+        db.set_disabled_at(user_id, disabled.then(Utc::now)).await
+    }
+
+    /// Delete a user together with all of their memos
+    pub async fn delete(db: &Database, user_id: &str) -> Result<bool> {
+        db.delete_user(user_id).await
+    }
+
+    /// List all users with their memo counts, oldest account first
+    pub async fn list_with_memo_counts(db: &Database) -> Result<Vec<UserSummary>> {
+        db.list_users_with_memo_counts(Utc::now()).await
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
     }
 
     /// Verify password against stored hash
     pub fn verify_password(&self, password: &str) -> bool {
-        verify(password, &self.password_hash).unwrap_or(false)
+        self.check_password(password, &PasswordHasher::default()).is_valid()
+    }
+
+    /// Verify password and report whether the stored hash should be upgraded
+    pub fn check_password(&self, password: &str, hasher: &PasswordHasher) -> PasswordCheck {
+        hasher.verify(password, &self.password_hash)
     }
 
     /// Get public user info (without password hash)
@@ -84,13 +111,27 @@ impl User {
             id: self.id.clone(),
             username: self.username.clone(),
             created_at: self.created_at,
+            is_admin: self.is_admin,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PublicUser {
     pub id: String,
     pub username: String,
     pub created_at: DateTime<Utc>,
-}
\ No newline at end of file
+    pub is_admin: bool,
+}
+
+/// Account overview for administrators
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserSummary {
+    pub id: String,
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+    pub is_admin: bool,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub forever_memo_count: i64,
+    pub flush_memo_count: i64,
+}
@@ -0,0 +1,110 @@
+use crate::models::User;
+use crate::models::quota::QuotaOverride;
+use crate::models::user::{PublicUser, UserSummary};
+use crate::database::Database;
+use crate::validation::Validation;
+use crate::services::password::PasswordHasher;
+use anyhow::{Result, anyhow};
+
+#[derive(Clone)]
+pub struct AdminService {
+    db: Database,
+    hasher: PasswordHasher,
+}
+
+impl AdminService {
+    pub fn new(db: Database) -> Self {
+        Self::with_hasher(db, PasswordHasher::default())
+    }
+
+    pub fn with_hasher(db: Database, hasher: PasswordHasher) -> Self {
+        Self { db, hasher }
+    }
+
+    /// List all users with memo counts
+    pub async fn list_users(&self) -> Result<Vec<UserSummary>> {
+        User::list_with_memo_counts(&self.db).await
+    }
+
+    /// Disable or re-enable an account (returns false if the user does not exist)
+    #[tracing::instrument(skip(self))]
+    pub async fn set_disabled(&self, user_id: &str, disabled: bool) -> Result<bool> {
+        User::set_disabled(&self.db, user_id, disabled).await
+    }
+
+    /// Grant or revoke the admin role (returns false if the user does not exist)
+    pub async fn set_admin(&self, user_id: &str, is_admin: bool) -> Result<bool> {
+        User::set_admin(&self.db, user_id, is_admin).await
+    }
+
+    /// Delete an account and all of its memos (returns false if the user does not exist)
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_user(&self, user_id: &str) -> Result<bool> {
+        User::delete(&self.db, user_id).await
+    }
+
+    /// Replace a user's password (returns false if the user does not exist)
+    #[tracing::instrument(skip(self, new_password))]
+    pub async fn reset_password(&self, user_id: &str, new_password: &str) -> Result<bool> {
+        Validation::new().new_password("password", new_password).finish()?;
+
+        if User::find_by_id(&self.db, user_id).await?.is_none() {
+            return Ok(false);
+        }
+
+        let password_hash = self.hasher.hash(new_password)?;
+        User::update_password_hash(&self.db, user_id, &password_hash).await?;
+        Ok(true)
+    }
+
+    /// A user's quota override, or `None` if the user does not exist
+    pub async fn quota_override(&self, user_id: &str) -> Result<Option<QuotaOverride>> {
+        if User::find_by_id(&self.db, user_id).await?.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(self.db.find_quota_override(user_id).await?))
+    }
+
+    /// Replace a user's quota override (returns false if the user does not exist)
+    #[tracing::instrument(skip(self))]
+    pub async fn set_quota_override(&self, user_id: &str, quota: &QuotaOverride) -> Result<bool> {
+        self.db.set_quota_override(user_id, quota).await
+    }
+
+    /// Make sure an admin account with the given username exists.
+    ///
+    /// A missing account is created with `password`. An existing user is only
+    /// promoted if `password` is theirs, so registering the configured
+    /// username first does not earn anyone the admin role.
+    pub async fn bootstrap_admin(&self, username: &str, password: Option<&str>) -> Result<PublicUser> {
+        let user = match User::find_by_username(&self.db, username).await? {
+            Some(user) if user.is_admin => user,
+            Some(user) => {
+                let verified = password.map(|password| user.check_password(password, &self.hasher));
+                if !verified.is_some_and(|check| check.is_valid()) {
+                    return Err(anyhow!(
+                        "User {} already exists and is not an admin; the admin password does not match it",
+                        username
+                    ));
+                }
+                user
+            }
+            None => {
+                let password = password
+                    .ok_or_else(|| anyhow!("A password is required to create admin user {}", username))?;
+                Validation::new().new_password("password", password).finish()?;
+                User::create_with_hasher(&self.db, username, password, &self.hasher).await?
+            }
+        };
+
+        if !user.is_admin {
+            User::set_admin(&self.db, &user.id, true).await?;
+            tracing::info!("Granted admin role to user {}", user.username);
+        }
+
+        let user = User::find_by_id(&self.db, &user.id)
+            .await?
+            .ok_or_else(|| anyhow!("Admin user disappeared during bootstrap"))?;
+        Ok(user.to_public())
+    }
+}
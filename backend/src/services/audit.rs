@@ -0,0 +1,41 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use crate::database::Database;
+use crate::models::audit_event::{AuditEvent, AuditQuery, NewAuditEvent};
+
+#[derive(Clone)]
+pub struct AuditService {
+    db: Database,
+}
+
+impl AuditService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Record an event. A failed write is logged rather than returned: losing
+    /// an audit entry must not undo the action it describes.
+    pub async fn record(&self, event: NewAuditEvent) {
+        if let Err(e) = AuditEvent::record(&self.db, &event).await {
+            tracing::error!(event_type = %event.event_type, "Failed to record audit event: {}", e);
+        }
+    }
+
+    /// A user's own account activity, newest first
+    pub async fn activity(&self, user_id: &str, limit: i64) -> Result<Vec<AuditEvent>> {
+        AuditEvent::list_for_user(&self.db, user_id, limit).await
+    }
+
+    pub async fn query(&self, filter: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        AuditEvent::query(&self.db, filter).await
+    }
+
+    /// Delete events older than `retention`, returning how many were removed
+    pub async fn purge_older_than(&self, retention: Duration) -> Result<u64> {
+        let removed = AuditEvent::delete_older_than(&self.db, Utc::now() - retention).await?;
+        if removed > 0 {
+            tracing::info!("Purged {} audit events older than {} days", removed, retention.num_days());
+        }
+        Ok(removed)
+    }
+}
@@ -1,37 +1,62 @@
 use crate::models::User;
 use crate::models::user::PublicUser;
-use crate::database::DatabasePool;
+use crate::database::Database;
+use crate::validation::Validation;
+use crate::services::password::{PasswordCheck, PasswordHasher};
 use anyhow::{Result, anyhow};
 
 #[derive(Clone)]
 pub struct AuthService {
-    pool: DatabasePool,
+    db: Database,
+    hasher: PasswordHasher,
 }
 
 impl AuthService {
-    pub fn new(pool: DatabasePool) -> Self {
-        Self { pool }
+    pub fn new(db: Database) -> Self {
+        Self::with_hasher(db, PasswordHasher::default())
+    }
+
+    pub fn with_hasher(db: Database, hasher: PasswordHasher) -> Self {
+        Self { db, hasher }
     }
 
     /// Register a new user
+    #[tracing::instrument(skip(self, password))]
     pub async fn register(&self, username: &str, password: &str) -> Result<PublicUser> {
-        // Validate password length (minimum 8 characters)
-        if password.len() < 8 {
-            return Err(anyhow!("Password must be at least 8 characters long"));
-        }
+        Validation::new().new_password("password", password).finish()?;
 
-        let user = User::create(&self.pool, username, password).await?;
+        let user = User::create_with_hasher(&self.db, username, password, &self.hasher).await?;
         Ok(user.to_public())
     }
 
     /// Login with username and password
+    #[tracing::instrument(skip(self, password))]
     pub async fn login(&self, username: &str, password: &str) -> Result<PublicUser> {
-        let user = User::find_by_username(&self.pool, username)
+        let user = User::find_by_username(&self.db, username)
             .await?
             .ok_or_else(|| anyhow!("Invalid username or password"))?;
 
-        if !user.verify_password(password) {
-            return Err(anyhow!("Invalid username or password"));
+        let check = user.check_password(password, &self.hasher);
+        // Only reveal the account status to someone who knows the password
+        if check.is_valid() && user.is_disabled() {
+            return Err(anyhow!("Account is disabled"));
+        }
+
+        match check {
+            PasswordCheck::Invalid => return Err(anyhow!("Invalid username or password")),
+            PasswordCheck::Valid => {}
+            PasswordCheck::ValidNeedsRehash => {
+                // Upgrade legacy bcrypt or outdated Argon2 hashes while we have the plaintext.
+                // A failed upgrade must not block the login itself.
+                match self.hasher.hash(password) {
+                    Ok(new_hash) => {
+                        if let Err(e) = User::update_password_hash(&self.db, &user.id, &new_hash).await {
+                            tracing::warn!("Failed to upgrade password hash for user {}: {}", user.id, e);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to rehash password for user {}: {}", user.id, e),
+                }
+            }
         }
 
         Ok(user.to_public())
@@ -39,18 +64,13 @@ impl AuthService {
 
     /// Validate a user by ID (for session validation)
     pub async fn validate_user(&self, user_id: &str) -> Result<Option<PublicUser>> {
-        let user = User::find_by_id(&self.pool, user_id).await?;
+        let user = User::find_by_id(&self.db, user_id).await?;
         Ok(user.map(|u| u.to_public()))
     }
 
     /// Check if username exists
     pub async fn username_exists(&self, username: &str) -> Result<bool> {
-        let user = User::find_by_username(&self.pool, username).await?;
+        let user = User::find_by_username(&self.db, username).await?;
         Ok(user.is_some())
     }
-
-    /// Get user by ID (returns full user info for internal use)
-    async fn get_user_by_id(&self, user_id: &str) -> Result<Option<User>> {
-        User::find_by_id(&self.pool, user_id).await
-    }
 }
\ No newline at end of file
@@ -0,0 +1,369 @@
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePoolOptions}, ConnectOptions, Connection, SqliteConnection, SqlitePool};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::storage::sqlite::{latest_migration_version, MIGRATOR};
+
+/// File name prefix of snapshots managed by [`BackupService`]
+pub const BACKUP_PREFIX: &str = "memory_memo_backup_";
+
+/// A snapshot in the backup directory
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BackupInfo {
+    pub name: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Result of checking a snapshot file
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SnapshotCheck {
+    /// `PRAGMA integrity_check` reported no problems
+    pub integrity_ok: bool,
+    /// What `PRAGMA integrity_check` found, empty when `integrity_ok`
+    pub problems: Vec<String>,
+    /// Highest migration applied to the snapshot
+    pub schema_version: i64,
+}
+
+/// What a restore replaced the live data with
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    /// Schema version of the snapshot before it was migrated to the current one
+    pub schema_version: i64,
+    /// Snapshot of the data as it was just before the restore
+    pub safety_backup: BackupInfo,
+}
+
+/// Online snapshots of the live database, taken with `VACUUM INTO` so they
+/// are consistent even while the server is writing
+#[derive(Clone)]
+pub struct BackupService {
+    pool: SqlitePool,
+    dir: PathBuf,
+    keep: Option<usize>,
+    verify: bool,
+}
+
+impl BackupService {
+    pub fn new(pool: SqlitePool, dir: impl Into<PathBuf>) -> Self {
+        Self { pool, dir: dir.into(), keep: None, verify: false }
+    }
+
+    /// Delete all but the newest `keep` snapshots after each backup
+    pub fn with_rotation(mut self, keep: usize) -> Self {
+        self.keep = Some(keep);
+        self
+    }
+
+    /// Check each new snapshot and discard it if the check fails
+    pub fn with_verification(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Snapshot the database into the backup directory, then rotate old snapshots
+    #[tracing::instrument(skip(self))]
+    pub async fn create_backup(&self) -> Result<BackupInfo> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.next_backup_path();
+        snapshot_database(&self.pool, &path).await?;
+
+        if self.verify {
+            let verified = verify_snapshot(&path)
+                .await
+                .and_then(|check| check.ensure_usable().map(|_| check));
+            if let Err(e) = verified {
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(anyhow!("Backup {} failed verification: {}", path.display(), e));
+            }
+        }
+
+        let info = backup_info(&path).await?;
+        tracing::info!("Backup written to {} ({} bytes)", path.display(), info.size_bytes);
+
+        if let Some(keep) = self.keep {
+            let removed = self.rotate(keep).await?;
+            if removed > 0 {
+                tracing::info!("Removed {} old backups", removed);
+            }
+        }
+        Ok(info)
+    }
+
+    /// Snapshots in the backup directory, newest first
+    pub async fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut backups = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            if name.to_str().is_some_and(is_backup_name) {
+                backups.push(backup_info(&entry.path()).await?);
+            }
+        }
+        // Timestamped names sort chronologically
+        backups.sort_by(|a, b| b.name.cmp(&a.name));
+        Ok(backups)
+    }
+
+    /// Path of the snapshot called `name`, rejecting anything that is not a
+    /// snapshot name (such as a path escaping the backup directory)
+    pub fn backup_path(&self, name: &str) -> Result<PathBuf> {
+        if !is_backup_name(name) {
+            return Err(anyhow!("Invalid backup name {:?}", name));
+        }
+        let path = self.dir.join(name);
+        if !path.is_file() {
+            return Err(anyhow!("Backup {} not found", name));
+        }
+        Ok(path)
+    }
+
+    /// Delete all but the newest `keep` snapshots, returning how many were deleted
+    pub async fn rotate(&self, keep: usize) -> Result<usize> {
+        let backups = self.list_backups().await?;
+        let mut removed = 0;
+        for backup in backups.iter().skip(keep) {
+            tokio::fs::remove_file(self.dir.join(&backup.name)).await?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Replace the live data with the contents of `snapshot`.
+    ///
+    /// The snapshot must pass the integrity check and must not come from a
+    /// newer build; older snapshots are migrated first. A backup of the
+    /// current data is taken before anything is overwritten, and the copy
+    /// runs in one transaction so the server can keep running.
+    #[tracing::instrument(skip(self))]
+    pub async fn restore(&self, snapshot: &Path) -> Result<RestoreReport> {
+        let check = verify_snapshot(snapshot).await?;
+        check.ensure_usable()?;
+
+        // Migrate a scratch copy so its tables match the live schema
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let scratch = self.dir.join(format!(".restore_{}.db", Uuid::new_v4()));
+        tokio::fs::copy(snapshot, &scratch).await?;
+
+        let result = self.restore_from_scratch(&scratch, check.schema_version).await;
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let _ = tokio::fs::remove_file(format!("{}{}", scratch.display(), suffix)).await;
+        }
+        result
+    }
+
+    async fn restore_from_scratch(&self, scratch: &Path, schema_version: i64) -> Result<RestoreReport> {
+        let scratch_pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(SqliteConnectOptions::new().filename(scratch))
+            .await?;
+        let migrated = MIGRATOR.run(&scratch_pool).await;
+        scratch_pool.close().await;
+        migrated?;
+
+        let safety_backup = self.create_backup().await?;
+        copy_into_live(&self.pool, scratch).await?;
+
+        tracing::warn!("Database restored from a schema version {} snapshot", schema_version);
+        Ok(RestoreReport { schema_version, safety_backup })
+    }
+
+    /// Take a backup every `interval` until `shutdown` is cancelled. The first
+    /// one is taken after one interval, so restart loops don't pile up
+    /// snapshots.
+    pub fn spawn_periodic(self, interval: std::time::Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + interval;
+            let mut ticker = tokio::time::interval_at(start, interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.create_backup().await {
+                    tracing::error!("Scheduled backup failed: {}", e);
+                }
+            }
+            tracing::info!("Backup job stopped");
+        })
+    }
+
+    fn next_backup_path(&self) -> PathBuf {
+        let stem = format!("{}{}", BACKUP_PREFIX, Utc::now().format("%Y%m%d_%H%M%S"));
+        let mut path = self.dir.join(format!("{}.db", stem));
+        // Several backups within one second get a counter
+        let mut counter = 1;
+        while path.exists() {
+            path = self.dir.join(format!("{}_{}.db", stem, counter));
+            counter += 1;
+        }
+        path
+    }
+}
+
+impl SnapshotCheck {
+    /// Whether the snapshot can be restored by this build
+    pub fn ensure_usable(&self) -> Result<()> {
+        if !self.integrity_ok {
+            return Err(anyhow!("integrity check failed: {}", self.problems.join("; ")));
+        }
+        let latest = latest_migration_version();
+        if self.schema_version > latest {
+            return Err(anyhow!(
+                "snapshot is at schema version {}, newer than this build ({})",
+                self.schema_version,
+                latest
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Snapshot the database with `VACUUM INTO`, which is safe while the server is writing
+pub async fn snapshot_database(pool: &SqlitePool, path: &Path) -> Result<()> {
+    if path.exists() {
+        return Err(anyhow!("{} already exists", path.display()));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let target = path.to_str().ok_or_else(|| anyhow!("Backup path must be valid UTF-8"))?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(target)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Open a snapshot read-only and check its integrity and schema version
+pub async fn verify_snapshot(path: &Path) -> Result<SnapshotCheck> {
+    if !path.is_file() {
+        return Err(anyhow!("{} not found", path.display()));
+    }
+
+    let mut conn = SqliteConnectOptions::new().filename(path).read_only(true).connect().await?;
+    let result = check_connection(&mut conn).await;
+    conn.close().await?;
+    result
+}
+
+async fn check_connection(conn: &mut SqliteConnection) -> Result<SnapshotCheck> {
+    let messages: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check").fetch_all(&mut *conn).await?;
+    let integrity_ok = messages.len() == 1 && messages[0] == "ok";
+
+    let schema_version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|_| anyhow!("not a Memory Memo database (no migration history)"))?;
+
+    Ok(SnapshotCheck {
+        integrity_ok,
+        problems: if integrity_ok { Vec::new() } else { messages },
+        schema_version: schema_version.unwrap_or(0),
+    })
+}
+
+/// Replace the rows of every table with those of the migrated snapshot at
+/// `source`, on one connection and in one transaction
+async fn copy_into_live(pool: &SqlitePool, source: &Path) -> Result<()> {
+    let source = source.to_str().ok_or_else(|| anyhow!("Backup path must be valid UTF-8"))?;
+    let mut conn = pool.acquire().await?;
+
+    sqlx::query("ATTACH DATABASE ? AS restore_source")
+        .bind(source)
+        .execute(&mut *conn)
+        .await?;
+    let result = copy_tables(&mut conn).await;
+    if let Err(e) = sqlx::query("DETACH DATABASE restore_source").execute(&mut *conn).await {
+        tracing::warn!("Failed to detach restore source: {}", e);
+    }
+    result
+}
+
+async fn copy_tables(conn: &mut SqliteConnection) -> Result<()> {
+    const USER_TABLES: &str = "type = 'table' AND name NOT LIKE 'sqlite_%' AND name <> '_sqlx_migrations'";
+
+    let mut tx = conn.begin().await?;
+    // Rows are copied table by table, so references are only complete at commit
+    sqlx::query("PRAGMA defer_foreign_keys = ON").execute(&mut *tx).await?;
+
+    let live: Vec<String> = sqlx::query_scalar(&format!("SELECT name FROM main.sqlite_master WHERE {}", USER_TABLES))
+        .fetch_all(&mut *tx)
+        .await?;
+    let source: Vec<(String, String)> =
+        sqlx::query_as(&format!("SELECT name, sql FROM restore_source.sqlite_master WHERE {}", USER_TABLES))
+            .fetch_all(&mut *tx)
+            .await?;
+
+    for table in &live {
+        sqlx::query(&format!("DELETE FROM main.{}", quote_ident(table))).execute(&mut *tx).await?;
+    }
+    for (table, sql) in &source {
+        // Tables created lazily at runtime may exist on only one side
+        if !live.contains(table) {
+            sqlx::query(sql).execute(&mut *tx).await?;
+        }
+        let ident = quote_ident(table);
+        sqlx::query(&format!("INSERT INTO main.{} SELECT * FROM restore_source.{}", ident, ident))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Keep AUTOINCREMENT counters in step with the restored rows
+    let has_sequence = |schema: &str| format!("SELECT COUNT(*) FROM {}.sqlite_master WHERE name = 'sqlite_sequence'", schema);
+    let live_sequence: i64 = sqlx::query_scalar(&has_sequence("main")).fetch_one(&mut *tx).await?;
+    let source_sequence: i64 = sqlx::query_scalar(&has_sequence("restore_source")).fetch_one(&mut *tx).await?;
+    if live_sequence > 0 {
+        sqlx::query("DELETE FROM main.sqlite_sequence").execute(&mut *tx).await?;
+        if source_sequence > 0 {
+            sqlx::query("INSERT INTO main.sqlite_sequence SELECT * FROM restore_source.sqlite_sequence")
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn is_backup_name(name: &str) -> bool {
+    name.strip_prefix(BACKUP_PREFIX)
+        .and_then(|rest| rest.strip_suffix(".db"))
+        .is_some_and(|stamp| !stamp.is_empty() && stamp.chars().all(|c| c.is_ascii_digit() || c == '_'))
+}
+
+async fn backup_info(path: &Path) -> Result<BackupInfo> {
+    let metadata = tokio::fs::metadata(path).await?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("Invalid backup path {}", path.display()))?
+        .to_string();
+    Ok(BackupInfo {
+        name,
+        size_bytes: metadata.len(),
+        created_at: metadata.modified()?.into(),
+    })
+}
@@ -1,16 +1,25 @@
+use crate::clock::{system_clock, SharedClock};
 use crate::models::FlushMemo;
-use crate::database::DatabasePool;
+use crate::database::Database;
+use crate::metrics::Metrics;
+use crate::services::AuditService;
 use anyhow::Result;
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
 
 #[derive(Clone)]
 pub struct CleanupService {
-    pool: DatabasePool,
+    db: Database,
+    metrics: Option<Metrics>,
+    audit_retention: Option<Duration>,
+    clock: SharedClock,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CleanupStatistics {
     pub total_count: i64,
     pub expired_count: i64,
@@ -19,60 +28,92 @@ pub struct CleanupStatistics {
 }
 
 impl CleanupService {
-    pub fn new(pool: DatabasePool) -> Self {
-        Self { pool }
+    pub fn new(db: Database) -> Self {
+        Self { db, metrics: None, audit_retention: None, clock: system_clock() }
+    }
+
+    /// Judge expiry and date cleanup runs by `clock`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record how many memos each run removes
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Also purge audit events older than `retention` on each scheduled run
+    pub fn with_audit_retention(mut self, retention: Duration) -> Self {
+        self.audit_retention = Some(retention);
+        self
     }
 
     /// Clean up expired flush memos and return count of deleted memos
+    #[tracing::instrument(skip(self))]
     pub async fn cleanup_expired_flush_memos(&self) -> Result<u64> {
-        let cleaned_count = FlushMemo::cleanup_expired(&self.pool).await?;
+        let now = self.clock.now();
+        let cleaned_count = FlushMemo::cleanup_expired(&self.db, now).await?;
         
         // Log the cleanup run
-        self.log_cleanup_run(cleaned_count, Utc::now()).await?;
+        self.log_cleanup_run(cleaned_count, now).await?;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_cleanup_run(cleaned_count);
+        }
         
         tracing::info!("Cleanup completed: {} expired flush memos removed", cleaned_count);
         Ok(cleaned_count)
     }
 
+    /// Delete audit events past the retention set with
+    /// [`Self::with_audit_retention`], returning how many were removed
+    pub async fn purge_audit_events(&self) -> Result<u64> {
+        match self.audit_retention {
+            Some(retention) => AuditService::new(self.db.clone()).purge_older_than(retention).await,
+            None => Ok(0),
+        }
+    }
+
+    /// One scheduled run: expired flush memos, then audit events past retention
+    pub async fn run_scheduled(&self) -> Result<()> {
+        self.cleanup_expired_flush_memos().await?;
+        self.purge_audit_events().await?;
+        Ok(())
+    }
+
+    /// Run [`Self::run_scheduled`] every `interval` until `shutdown` is
+    /// cancelled. A run in progress is allowed to finish.
+    pub fn spawn_periodic(self, interval: std::time::Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_scheduled().await {
+                    tracing::error!("Scheduled cleanup failed: {}", e);
+                }
+            }
+            tracing::info!("Cleanup job stopped");
+        })
+    }
+
     /// Dry run cleanup - show what would be deleted without actually deleting
     pub async fn cleanup_expired_flush_memos_dry_run(&self) -> Result<u64> {
-        let now = Utc::now();
-        let result = sqlx::query(
-            "SELECT COUNT(*) as count FROM flush_memos WHERE expires_at <= ?"
-        )
-        .bind(now)
-        .fetch_one(&self.pool)
-        .await?;
-
-        let count: i64 = result.get("count");
-        tracing::info!("Dry run: {} expired flush memos would be removed", count);
-        Ok(count as u64)
+        let (_, expired_count) = self.db.count_flush_memos(self.clock.now()).await?;
+        tracing::info!("Dry run: {} expired flush memos would be removed", expired_count);
+        Ok(expired_count as u64)
     }
 
     /// Get cleanup statistics
     pub async fn get_cleanup_statistics(&self) -> Result<CleanupStatistics> {
-        let now = Utc::now();
-        
-        // Get total count
-        let total_result = sqlx::query("SELECT COUNT(*) as count FROM flush_memos")
-            .fetch_one(&self.pool)
-            .await?;
-        let total_count: i64 = total_result.get("count");
-        
-        // Get expired count
-        let expired_result = sqlx::query(
-            "SELECT COUNT(*) as count FROM flush_memos WHERE expires_at <= ?"
-        )
-        .bind(now)
-        .fetch_one(&self.pool)
-        .await?;
-        let expired_count: i64 = expired_result.get("count");
-        
+        let (total_count, expired_count) = self.db.count_flush_memos(self.clock.now()).await?;
         let active_count = total_count - expired_count;
-        
-        // Get last cleanup time (if cleanup log table exists)
-        let last_cleanup = self.get_last_cleanup_time().await.ok();
-        
+        let last_cleanup = self.db.last_cleanup().await?;
+
         Ok(CleanupStatistics {
             total_count,
             expired_count,
@@ -83,90 +124,25 @@ impl CleanupService {
 
     /// Get next suggested cleanup time (current time + 1 hour)
     pub async fn get_next_cleanup_time(&self) -> Result<DateTime<Utc>> {
-        Ok(Utc::now() + Duration::hours(1))
+        Ok(self.clock.now() + Duration::hours(1))
     }
 
-    /// Log cleanup run (create simple log table if needed)
+    /// Record a cleanup run in the cleanup log
     pub async fn log_cleanup_run(&self, cleaned_count: u64, run_time: DateTime<Utc>) -> Result<()> {
-        // Create cleanup log table if it doesn't exist
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS cleanup_logs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                cleaned_count INTEGER NOT NULL,
-                run_time DATETIME NOT NULL
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        // Insert log entry
-        sqlx::query(
-            "INSERT INTO cleanup_logs (cleaned_count, run_time) VALUES (?, ?)"
-        )
-        .bind(cleaned_count as i64)
-        .bind(run_time)
-        .execute(&self.pool)
-        .await?;
-        
+        self.db.log_cleanup_run(cleaned_count, run_time).await?;
         tracing::debug!("Logged cleanup run: {} items cleaned at {}", cleaned_count, run_time);
         Ok(())
     }
 
-    /// Get last cleanup time from logs
-    async fn get_last_cleanup_time(&self) -> Result<DateTime<Utc>> {
-        let result = sqlx::query(
-            "SELECT run_time FROM cleanup_logs ORDER BY run_time DESC LIMIT 1"
-        )
-        .fetch_one(&self.pool)
-        .await?;
-        
-        Ok(result.get("run_time"))
-    }
-
     /// Get cleanup history (last N runs)
     pub async fn get_cleanup_history(&self, limit: i64) -> Result<Vec<(u64, DateTime<Utc>)>> {
-        // Create table if it doesn't exist
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS cleanup_logs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                cleaned_count INTEGER NOT NULL,
-                run_time DATETIME NOT NULL
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        let rows = sqlx::query(
-            "SELECT cleaned_count, run_time FROM cleanup_logs ORDER BY run_time DESC LIMIT ?"
-        )
-        .bind(limit)
-        .fetch_all(&self.pool)
-        .await?;
-        
-        let history = rows
-            .into_iter()
-            .map(|row| {
-                let count: i64 = row.get("cleaned_count");
-                let time: DateTime<Utc> = row.get("run_time");
-                (count as u64, time)
-            })
-            .collect();
-        
-        Ok(history)
+        self.db.cleanup_history(limit).await
     }
 
     /// Force cleanup all flush memos regardless of expiry (for testing/admin use)
     pub async fn force_cleanup_all_flush_memos(&self) -> Result<u64> {
-        let result = sqlx::query("DELETE FROM flush_memos")
-            .execute(&self.pool)
-            .await?;
-        
-        let cleaned_count = result.rows_affected();
-        self.log_cleanup_run(cleaned_count, Utc::now()).await?;
+        let cleaned_count = self.db.delete_all_flush_memos().await?;
+        self.log_cleanup_run(cleaned_count, self.clock.now()).await?;
         
         tracing::warn!("Force cleanup: {} flush memos removed (all)", cleaned_count);
         Ok(cleaned_count)
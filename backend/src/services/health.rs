@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+use std::time::Instant;
+use anyhow::Result;
+use chrono::Duration;
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::clock::{system_clock, SharedClock};
+use crate::database::{sqlite_file_path, Database};
+
+/// Outcome of a single readiness check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    /// Worth attention, but the instance can still serve traffic
+    Warn,
+    Fail,
+    /// Not applicable in this configuration
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CheckResult {
+    pub status: CheckStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    /// `ok` unless some check failed
+    pub status: CheckStatus,
+    pub database: CheckResult,
+    pub migrations: CheckResult,
+    pub disk: CheckResult,
+    pub cleanup: CheckResult,
+}
+
+impl ReadinessReport {
+    pub fn is_ready(&self) -> bool {
+        self.status != CheckStatus::Fail
+    }
+}
+
+/// Dependency checks behind `/health/ready`
+#[derive(Clone)]
+pub struct HealthService {
+    db: Database,
+    database_path: Option<PathBuf>,
+    min_free_disk_bytes: u64,
+    /// How old the last successful cleanup may be; `None` when the job is disabled
+    cleanup_max_age: Option<Duration>,
+    clock: SharedClock,
+}
+
+impl HealthService {
+    pub fn new(
+        db: Database,
+        database_url: &str,
+        min_free_disk_bytes: u64,
+        cleanup_max_age: Option<Duration>,
+    ) -> Self {
+        Self {
+            db,
+            database_path: sqlite_file_path(database_url),
+            min_free_disk_bytes,
+            cleanup_max_age,
+            clock: system_clock(),
+        }
+    }
+
+    /// Judge the age of the last cleanup by `clock`, the one cleanup runs are dated with
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Run every check; checks are independent, so one failing does not skip the others
+    pub async fn readiness(&self) -> ReadinessReport {
+        let database = timed("database", self.check_database()).await;
+        let migrations = timed("migrations", self.check_migrations()).await;
+        let disk = timed("disk", async { self.check_disk() }).await;
+        let cleanup = timed("cleanup", self.check_cleanup()).await;
+
+        let failed = [&database, &migrations, &disk, &cleanup]
+            .iter()
+            .any(|check| check.status == CheckStatus::Fail);
+
+        ReadinessReport {
+            status: if failed { CheckStatus::Fail } else { CheckStatus::Ok },
+            database,
+            migrations,
+            disk,
+            cleanup,
+        }
+    }
+
+    async fn check_database(&self) -> Result<(CheckStatus, Option<String>)> {
+        self.db.ping().await?;
+        Ok((CheckStatus::Ok, Some(self.db.backend().to_string())))
+    }
+
+    async fn check_migrations(&self) -> Result<(CheckStatus, Option<String>)> {
+        let status = self.db.migration_status().await?;
+
+        if status.failed > 0 {
+            return Ok((CheckStatus::Fail, Some(format!("a migration after version {} failed", status.applied))));
+        }
+        let detail = Some(format!("version {} of {}", status.applied, status.latest));
+        if status.applied < status.latest {
+            Ok((CheckStatus::Fail, detail))
+        } else {
+            Ok((CheckStatus::Ok, detail))
+        }
+    }
+
+    fn check_disk(&self) -> Result<(CheckStatus, Option<String>)> {
+        let Some(path) = &self.database_path else {
+            return Ok((CheckStatus::Skipped, Some("database is not a local file".to_string())));
+        };
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => std::path::Path::new("."),
+        };
+
+        let available = fs2::available_space(dir)?;
+        let detail = Some(format!("{} MiB free", available / (1024 * 1024)));
+        if available < self.min_free_disk_bytes {
+            Ok((CheckStatus::Fail, detail))
+        } else {
+            Ok((CheckStatus::Ok, detail))
+        }
+    }
+
+    async fn check_cleanup(&self) -> Result<(CheckStatus, Option<String>)> {
+        let Some(max_age) = self.cleanup_max_age else {
+            return Ok((CheckStatus::Skipped, Some("cleanup job disabled".to_string())));
+        };
+
+        let last_run = self.db.last_cleanup().await?;
+
+        match last_run {
+            None => Ok((CheckStatus::Warn, Some("no cleanup has run yet".to_string()))),
+            Some(last_run) => {
+                let detail = Some(format!("last succeeded at {}", last_run.to_rfc3339()));
+                if self.clock.now() - last_run > max_age {
+                    Ok((CheckStatus::Warn, detail))
+                } else {
+                    Ok((CheckStatus::Ok, detail))
+                }
+            }
+        }
+    }
+}
+
+/// Run a check, recording its latency and turning errors into failures.
+///
+/// The report is public, so errors are only logged: storage errors can name
+/// hosts, databases and roles.
+async fn timed(
+    name: &str,
+    check: impl std::future::Future<Output = Result<(CheckStatus, Option<String>)>>,
+) -> CheckResult {
+    let started = Instant::now();
+    let (status, detail) = match check.await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            tracing::warn!("Readiness check {} failed: {:#}", name, e);
+            (CheckStatus::Fail, Some("check failed".to_string()))
+        }
+    };
+    CheckResult {
+        status,
+        latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+        detail,
+    }
+}
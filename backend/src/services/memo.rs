@@ -1,11 +1,18 @@
+use crate::clock::{system_clock, SharedClock};
 use crate::models::{ForeverMemo, FlushMemo};
-use crate::database::DatabasePool;
+use crate::models::flush_memo::DEFAULT_FLUSH_TTL_HOURS;
+use crate::models::quota::{QuotaLimits, QuotaStatus, QuotaUsage};
+use crate::database::Database;
 use anyhow::Result;
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone)]
 pub struct MemoService {
-    pool: DatabasePool,
+    db: Database,
+    flush_ttl: Duration,
+    clock: SharedClock,
+    quota: QuotaLimits,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -14,27 +21,75 @@ pub struct UserMemos {
     pub forever_memos: Vec<ForeverMemo>,
 }
 
+// Spans record IDs only; memo content is skipped so it never reaches the logs
 impl MemoService {
-    pub fn new(pool: DatabasePool) -> Self {
-        Self { pool }
+    pub fn new(db: Database) -> Self {
+        Self::with_flush_ttl(db, Duration::hours(DEFAULT_FLUSH_TTL_HOURS))
+    }
+
+    pub fn with_flush_ttl(db: Database, flush_ttl: Duration) -> Self {
+        Self { db, flush_ttl, clock: system_clock(), quota: QuotaLimits::default() }
+    }
+
+    /// Date flush memos and judge their expiry by `clock`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Hold every user to `quota` unless an admin has overridden it for
+    /// them. Without this there are no limits.
+    pub fn with_quota(mut self, quota: QuotaLimits) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// The limits in force for a user, with their current usage
+    pub async fn quota_status(&self, user_id: &str) -> Result<QuotaStatus> {
+        let (quota_override, usage) = tokio::join!(
+            self.db.find_quota_override(user_id),
+            self.db.memo_usage(user_id, self.clock.now())
+        );
+        Ok(QuotaStatus {
+            limits: quota_override?.apply(self.quota),
+            usage: usage?,
+        })
+    }
+
+    /// Fails with [`QuotaExceeded`](crate::models::quota::QuotaExceeded) if
+    /// `added` does not fit. The check and the write that follows are not
+    /// atomic, so concurrent requests can overshoot a limit slightly.
+    async fn check_quota(&self, user_id: &str, added: QuotaUsage) -> Result<()> {
+        let status = self.quota_status(user_id).await?;
+        status.limits.check(status.usage, added)?;
+        Ok(())
     }
 
     /// Create a new forever memo
+    #[tracing::instrument(skip(self, content))]
     pub async fn create_forever_memo(&self, user_id: &str, content: &str) -> Result<ForeverMemo> {
-        ForeverMemo::create(&self.pool, user_id, content).await
+        self.check_quota(user_id, QuotaUsage { memos: 1, flush_memos: 0, bytes: content.len() as u64 }).await?;
+        let memo = ForeverMemo::create(&self.db, user_id, content).await?;
+        tracing::debug!(memo_id = %memo.id, "Created forever memo");
+        Ok(memo)
     }
 
     /// Create a new flush memo
+    #[tracing::instrument(skip(self, content))]
     pub async fn create_flush_memo(&self, user_id: &str, content: &str) -> Result<FlushMemo> {
-        FlushMemo::create(&self.pool, user_id, content).await
+        self.check_quota(user_id, QuotaUsage { memos: 1, flush_memos: 1, bytes: content.len() as u64 }).await?;
+        let memo = FlushMemo::create_with_ttl(&self.db, user_id, content, self.flush_ttl, self.clock.now()).await?;
+        tracing::debug!(memo_id = %memo.id, "Created flush memo");
+        Ok(memo)
     }
 
     /// List all memos for a user (flush memos first, then forever memos)
+    #[tracing::instrument(skip(self))]
     pub async fn list_user_memos(&self, user_id: &str) -> Result<UserMemos> {
         // Get both types of memos concurrently
         let (flush_memos_result, forever_memos_result) = tokio::join!(
-            FlushMemo::list_by_user(&self.pool, user_id),
-            ForeverMemo::list_by_user(&self.pool, user_id)
+            FlushMemo::list_by_user(&self.db, user_id, self.clock.now()),
+            ForeverMemo::list_by_user(&self.db, user_id)
         );
 
         Ok(UserMemos {
@@ -46,43 +101,52 @@ impl MemoService {
     /// Delete a memo (works for both forever and flush memos)
     pub async fn delete_memo(&self, memo_id: &str, user_id: &str) -> Result<()> {
         // Try to delete from forever memos first
-        let forever_result = ForeverMemo::delete(&self.pool, memo_id, user_id).await;
+        let forever_result = ForeverMemo::delete(&self.db, memo_id, user_id).await;
         
         match forever_result {
             Ok(()) => Ok(()), // Successfully deleted from forever memos
             Err(_) => {
                 // If not found in forever memos, try flush memos
-                FlushMemo::delete(&self.pool, memo_id, user_id).await
+                FlushMemo::delete(&self.db, memo_id, user_id).await
             }
         }
     }
 
     /// Get a specific forever memo by ID (if owned by user)
     pub async fn get_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<Option<ForeverMemo>> {
-        ForeverMemo::find_by_id_and_user(&self.pool, memo_id, user_id).await
+        ForeverMemo::find_by_id_and_user(&self.db, memo_id, user_id).await
     }
 
     /// Get a specific flush memo by ID (if owned by user and not expired)
     pub async fn get_flush_memo(&self, memo_id: &str, user_id: &str) -> Result<Option<FlushMemo>> {
-        FlushMemo::find_by_id_and_user(&self.pool, memo_id, user_id).await
+        FlushMemo::find_by_id_and_user(&self.db, memo_id, user_id, self.clock.now()).await
     }
 
-    /// Update a forever memo's content
+    /// Update a forever memo's content. Only growth counts against the byte limit.
+    #[tracing::instrument(skip(self, new_content))]
     pub async fn update_forever_memo(&self, memo_id: &str, user_id: &str, new_content: &str) -> Result<Option<ForeverMemo>> {
-        ForeverMemo::update(&self.pool, memo_id, user_id, new_content).await
+        let Some(memo) = ForeverMemo::find_by_id_and_user(&self.db, memo_id, user_id).await? else {
+            return Ok(None);
+        };
+        let growth = new_content.len().saturating_sub(memo.content.len()) as u64;
+        self.check_quota(user_id, QuotaUsage { bytes: growth, ..QuotaUsage::default() }).await?;
+
+        ForeverMemo::update(&self.db, memo_id, user_id, new_content).await
     }
 
     /// Delete a forever memo specifically
+    #[tracing::instrument(skip(self))]
     pub async fn delete_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<bool> {
-        match ForeverMemo::delete(&self.pool, memo_id, user_id).await {
+        match ForeverMemo::delete(&self.db, memo_id, user_id).await {
             Ok(()) => Ok(true),
             Err(_) => Ok(false), // Memo not found or not owned by user
         }
     }
 
     /// Delete a flush memo specifically  
+    #[tracing::instrument(skip(self))]
     pub async fn delete_flush_memo(&self, memo_id: &str, user_id: &str) -> Result<bool> {
-        match FlushMemo::delete(&self.pool, memo_id, user_id).await {
+        match FlushMemo::delete(&self.db, memo_id, user_id).await {
             Ok(()) => Ok(true),
             Err(_) => Ok(false), // Memo not found or not owned by user
         }
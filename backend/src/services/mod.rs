@@ -2,8 +2,20 @@ pub mod auth;
 pub mod memo;
 pub mod cleanup;
 pub mod session;
+pub mod password;
+pub mod admin;
+pub mod audit;
+pub mod health;
+pub mod backup;
+pub mod rate_limit;
 
 pub use auth::AuthService;
 pub use memo::MemoService;
 pub use cleanup::CleanupService;
-pub use session::SessionStore;
\ No newline at end of file
+pub use session::SessionStore;
+pub use password::PasswordHasher;
+pub use admin::AdminService;
+pub use audit::AuditService;
+pub use health::HealthService;
+pub use backup::BackupService;
+pub use rate_limit::RateLimiter;
\ No newline at end of file
@@ -0,0 +1,133 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+
+/// Upper bound on accepted password length in bytes.
+///
+/// Argon2 itself accepts inputs of up to 4 GiB, but hashing cost grows with
+/// the input, so anything beyond this is rejected rather than hashed.
+pub const MAX_PASSWORD_BYTES: usize = 1024;
+
+/// Outcome of checking a password against a stored hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCheck {
+    /// The password does not match
+    Invalid,
+    /// The password matches and the hash is up to date
+    Valid,
+    /// The password matches but the hash uses a legacy algorithm or outdated parameters
+    ValidNeedsRehash,
+}
+
+impl PasswordCheck {
+    pub fn is_valid(self) -> bool {
+        !matches!(self, PasswordCheck::Invalid)
+    }
+}
+
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Argon2Config {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Config {
+    /// OWASP recommended minimum: 19 MiB memory, 2 iterations, 1 lane
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Hashes new passwords with Argon2id and verifies both Argon2 and legacy bcrypt hashes
+#[derive(Clone, Default)]
+pub struct PasswordHasher {
+    params: Params,
+}
+
+impl PasswordHasher {
+    pub fn new(config: Argon2Config) -> Result<Self> {
+        let params = Params::new(config.memory_kib, config.iterations, config.parallelism, None)
+            .map_err(|e| anyhow!("Invalid Argon2 parameters: {}", e))?;
+        Ok(Self { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    /// Reject passwords the hasher will not accept
+    pub fn check_length(password: &str) -> Result<()> {
+        if password.len() > MAX_PASSWORD_BYTES {
+            return Err(anyhow!("Password must be at most {} bytes long", MAX_PASSWORD_BYTES));
+        }
+        Ok(())
+    }
+
+    /// Hash a password with Argon2id using the configured parameters
+    pub fn hash(&self, password: &str) -> Result<String> {
+        Self::check_length(password)?;
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self
+            .argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| anyhow!("Failed to hash password: {}", e))?;
+        Ok(hash.to_string())
+    }
+
+    /// Verify a password against a stored Argon2 or bcrypt hash
+    pub fn verify(&self, password: &str, stored_hash: &str) -> PasswordCheck {
+        if password.len() > MAX_PASSWORD_BYTES {
+            return PasswordCheck::Invalid;
+        }
+
+        if is_bcrypt_hash(stored_hash) {
+            return match bcrypt::verify(password, stored_hash) {
+                Ok(true) => PasswordCheck::ValidNeedsRehash,
+                _ => PasswordCheck::Invalid,
+            };
+        }
+
+        let Ok(parsed) = PasswordHash::new(stored_hash) else {
+            return PasswordCheck::Invalid;
+        };
+
+        if self.argon2().verify_password(password.as_bytes(), &parsed).is_err() {
+            return PasswordCheck::Invalid;
+        }
+
+        if self.is_current(&parsed) {
+            PasswordCheck::Valid
+        } else {
+            PasswordCheck::ValidNeedsRehash
+        }
+    }
+
+    /// Whether a parsed hash was produced with Argon2id and the configured parameters
+    fn is_current(&self, parsed: &PasswordHash<'_>) -> bool {
+        if parsed.algorithm != Algorithm::Argon2id.ident() {
+            return false;
+        }
+        match Params::try_from(parsed) {
+            Ok(params) => {
+                params.m_cost() == self.params.m_cost()
+                    && params.t_cost() == self.params.t_cost()
+                    && params.p_cost() == self.params.p_cost()
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+fn is_bcrypt_hash(hash: &str) -> bool {
+    hash.starts_with("$2a$") || hash.starts_with("$2b$") || hash.starts_with("$2y$")
+}
@@ -0,0 +1,272 @@
+//! Token buckets limiting how fast each client calls the API. Every client
+//! has a bucket for reads and one for writes; each request takes a token,
+//! and tokens flow back at the configured rate up to the burst size.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use crate::clock::{system_clock, SharedClock};
+use crate::config::RateLimitConfig;
+use crate::database::Database;
+
+/// Most client buckets kept at once. Past this the oldest is forgotten, which
+/// hands that client a full bucket, so memory stays bounded however many
+/// addresses an attacker uses.
+pub const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// How often buckets that have refilled are dropped; a full bucket is the
+/// same as none
+const SWEEP_INTERVAL_SECONDS: i64 = 60;
+
+/// Key of the bucket shared by every client
+const GLOBAL_KEY: &str = "global";
+
+/// Routes limited together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    /// GET, HEAD and OPTIONS
+    Read,
+    /// Everything that can change data
+    Write,
+}
+
+impl RouteClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteClass::Read => "read",
+            RouteClass::Write => "write",
+        }
+    }
+}
+
+/// Burst size and sustained rate of one kind of bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RatePolicy {
+    /// Requests allowed at once from a full bucket
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl RatePolicy {
+    fn tokens_per_second(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+
+    /// Seconds until `tokens` grows to `target`
+    fn seconds_until(&self, tokens: f64, target: f64) -> u64 {
+        ((target - tokens).max(0.0) / self.tokens_per_second()).ceil() as u64
+    }
+}
+
+/// Tokens left in a bucket when it was last used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub tokens: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Bucket {
+    fn full(policy: &RatePolicy, now: DateTime<Utc>) -> Self {
+        Self { tokens: f64::from(policy.burst), updated_at: now }
+    }
+
+    /// Add the tokens that flowed back since the bucket was last used
+    fn refill(&mut self, policy: &RatePolicy, now: DateTime<Utc>) {
+        let elapsed = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+        self.tokens = (self.tokens + elapsed * policy.tokens_per_second()).min(f64::from(policy.burst));
+        self.updated_at = now;
+    }
+}
+
+/// Outcome of one request, reported in the `RateLimit-*` headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    /// Burst size of the client's bucket
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again
+    pub reset_seconds: u64,
+    /// Seconds until a refused request can succeed
+    pub retry_after_seconds: u64,
+}
+
+/// Client buckets in the order they were first tracked, and the global one
+#[derive(Default)]
+struct Buckets {
+    clients: HashMap<String, Bucket>,
+    order: VecDeque<String>,
+    global: Option<Bucket>,
+    swept_at: Option<DateTime<Utc>>,
+}
+
+impl Buckets {
+    /// Store a client's bucket, forgetting the oldest ones if there are too many
+    fn insert(&mut self, key: String, bucket: Bucket) {
+        if self.clients.insert(key.clone(), bucket).is_none() {
+            self.order.push_back(key);
+            while self.clients.len() > MAX_TRACKED_BUCKETS {
+                match self.order.pop_front() {
+                    Some(oldest) => self.clients.remove(&oldest),
+                    None => break,
+                };
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<Buckets>>,
+    read: RatePolicy,
+    write: RatePolicy,
+    global: Option<RatePolicy>,
+    clock: SharedClock,
+}
+
+impl From<&RateLimitConfig> for RateLimiter {
+    fn from(config: &RateLimitConfig) -> Self {
+        let read = RatePolicy { burst: config.read_burst, per_minute: config.read_per_minute };
+        let write = RatePolicy { burst: config.write_burst, per_minute: config.write_per_minute };
+        let global = (config.global_per_minute > 0)
+            .then_some(RatePolicy { burst: config.global_per_minute, per_minute: config.global_per_minute });
+        Self::new(read, write).with_global(global)
+    }
+}
+
+impl RateLimiter {
+    pub fn new(read: RatePolicy, write: RatePolicy) -> Self {
+        Self {
+            buckets: Arc::new(Mutex::new(Buckets::default())),
+            read,
+            write,
+            global: None,
+            clock: system_clock(),
+        }
+    }
+
+    /// Also cap the requests of all clients together
+    pub fn with_global(mut self, global: Option<RatePolicy>) -> Self {
+        self.global = global;
+        self
+    }
+
+    /// Refill buckets by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn policy(&self, class: RouteClass) -> &RatePolicy {
+        match class {
+            RouteClass::Read => &self.read,
+            RouteClass::Write => &self.write,
+        }
+    }
+
+    /// Take a token for a request from `client` (e.g. `user:<id>` or
+    /// `ip:<address>`). A refused request takes nothing.
+    pub fn check(&self, client: &str, class: RouteClass) -> RateLimitDecision {
+        let now = self.clock.now();
+        let policy = *self.policy(class);
+        let key = format!("{}:{}", client, class.as_str());
+        // A panic while holding the lock must not disable rate limiting
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut bucket = buckets.clients.get(&key).copied().unwrap_or_else(|| Bucket::full(&policy, now));
+        bucket.refill(&policy, now);
+        let mut global = self.global.map(|global_policy| {
+            let mut bucket = buckets.global.unwrap_or_else(|| Bucket::full(&global_policy, now));
+            bucket.refill(&global_policy, now);
+            (global_policy, bucket)
+        });
+
+        let retry_after_seconds = if bucket.tokens < 1.0 {
+            Some(policy.seconds_until(bucket.tokens, 1.0))
+        } else {
+            global
+                .filter(|(_, global_bucket)| global_bucket.tokens < 1.0)
+                .map(|(global_policy, global_bucket)| global_policy.seconds_until(global_bucket.tokens, 1.0))
+        };
+        let allowed = retry_after_seconds.is_none();
+
+        if allowed {
+            bucket.tokens -= 1.0;
+            if let Some((_, global_bucket)) = &mut global {
+                global_bucket.tokens -= 1.0;
+            }
+        }
+        buckets.insert(key, bucket);
+        buckets.global = global.map(|(_, global_bucket)| global_bucket);
+        let sweep_due = buckets
+            .swept_at
+            .is_none_or(|swept_at| now - swept_at >= Duration::seconds(SWEEP_INTERVAL_SECONDS));
+        if sweep_due {
+            self.drop_full_buckets(&mut buckets, now);
+        }
+
+        RateLimitDecision {
+            allowed,
+            limit: policy.burst,
+            remaining: bucket.tokens.max(0.0).floor() as u32,
+            reset_seconds: policy.seconds_until(bucket.tokens, f64::from(policy.burst)),
+            retry_after_seconds: retry_after_seconds.unwrap_or(0),
+        }
+    }
+
+    /// Forget buckets that have refilled completely
+    fn drop_full_buckets(&self, buckets: &mut Buckets, now: DateTime<Utc>) {
+        let is_full = |policy: &RatePolicy, bucket: &Bucket| {
+            let mut bucket = *bucket;
+            bucket.refill(policy, now);
+            bucket.tokens >= f64::from(policy.burst)
+        };
+        buckets.clients.retain(|key, bucket| {
+            let policy = if key.ends_with(":read") { &self.read } else { &self.write };
+            !is_full(policy, bucket)
+        });
+        let clients = &buckets.clients;
+        buckets.order.retain(|key| clients.contains_key(key));
+        buckets.global = match (&self.global, buckets.global) {
+            (Some(policy), Some(bucket)) if !is_full(policy, &bucket) => Some(bucket),
+            _ => None,
+        };
+        buckets.swept_at = Some(now);
+    }
+
+    /// Number of client buckets currently kept
+    pub fn tracked_buckets(&self) -> usize {
+        self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clients.len()
+    }
+
+    /// Write every bucket to storage, replacing what was saved before, so a
+    /// restart does not hand every client a full bucket
+    pub async fn save(&self, db: &Database) -> Result<usize> {
+        let buckets: Vec<(String, Bucket)> = {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            self.drop_full_buckets(&mut buckets, self.clock.now());
+            let global = buckets.global.map(|bucket| (GLOBAL_KEY.to_string(), bucket));
+            buckets.clients.iter().map(|(key, bucket)| (key.clone(), *bucket)).chain(global).collect()
+        };
+
+        db.replace_rate_limit_buckets(&buckets).await?;
+        Ok(buckets.len())
+    }
+
+    /// Restore the buckets written by [`Self::save`], removing them from storage
+    pub async fn load(&self, db: &Database) -> Result<usize> {
+        let saved = db.take_rate_limit_buckets().await?;
+
+        let restored = saved.len();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (key, bucket) in saved {
+            if key == GLOBAL_KEY {
+                buckets.global = Some(bucket);
+            } else {
+                buckets.insert(key, bucket);
+            }
+        }
+        Ok(restored)
+    }
+}
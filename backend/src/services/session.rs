@@ -1,54 +1,134 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use anyhow::Result;
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
+use crate::clock::{system_clock, SharedClock};
+use crate::config::SessionConfig;
+use crate::database::Database;
+
+/// Idle and absolute lifetimes applied to sessions
+#[derive(Clone, Copy, Debug)]
+pub struct SessionPolicy {
+    /// Sessions expire after this long without a request
+    pub idle_timeout: Duration,
+    /// Sessions expire this long after login, regardless of activity
+    pub absolute_timeout: Duration,
+    /// Idle and absolute lifetime of "remember me" sessions
+    pub remember_me_timeout: Duration,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::hours(2),
+            absolute_timeout: Duration::hours(24),
+            remember_me_timeout: Duration::days(30),
+        }
+    }
+}
+
+impl From<&SessionConfig> for SessionPolicy {
+    fn from(config: &SessionConfig) -> Self {
+        Self {
+            idle_timeout: Duration::minutes(config.idle_timeout_minutes),
+            absolute_timeout: Duration::hours(config.absolute_timeout_hours),
+            remember_me_timeout: Duration::days(config.remember_me_days),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Session {
     pub user_id: String,
+    pub csrf_token: String,
+    pub remember_me: bool,
     pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// When the session expires unless it is used again
     pub expires_at: DateTime<Utc>,
+    /// When the session expires regardless of activity
+    pub absolute_expires_at: DateTime<Utc>,
 }
 
 impl Session {
-    pub fn new(user_id: String) -> Self {
-        let now = Utc::now();
-        let expires_at = now + Duration::hours(24); // 24 hour session
+    /// A session logged in at `now`
+    pub fn new(user_id: String, remember_me: bool, policy: &SessionPolicy, now: DateTime<Utc>) -> Self {
+        let absolute_expires_at = now + if remember_me {
+            policy.remember_me_timeout
+        } else {
+            policy.absolute_timeout
+        };
 
-        Self {
+        let mut session = Self {
             user_id,
+            csrf_token: Uuid::new_v4().simple().to_string(),
+            remember_me,
             created_at: now,
-            expires_at,
-        }
+            last_seen_at: now,
+            expires_at: now,
+            absolute_expires_at,
+        };
+        session.touch(now, policy);
+        session
+    }
+
+    /// Record activity, sliding the idle expiry forward up to the absolute limit
+    pub fn touch(&mut self, now: DateTime<Utc>, policy: &SessionPolicy) {
+        let idle_timeout = if self.remember_me {
+            policy.remember_me_timeout
+        } else {
+            policy.idle_timeout
+        };
+        self.last_seen_at = now;
+        self.expires_at = (now + idle_timeout).min(self.absolute_expires_at);
     }
 
-    pub fn is_expired(&self) -> bool {
-        Utc::now() > self.expires_at
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now > self.expires_at
     }
 }
 
 #[derive(Clone)]
 pub struct SessionStore {
     sessions: Arc<RwLock<HashMap<String, Session>>>,
+    policy: SessionPolicy,
+    clock: SharedClock,
 }
 
 impl SessionStore {
     pub fn new() -> Self {
+        Self::with_policy(SessionPolicy::default())
+    }
+
+    pub fn with_policy(policy: SessionPolicy) -> Self {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            policy,
+            clock: system_clock(),
         }
     }
 
-    /// Create a new session for a user
-    pub fn create_session(&self, user_id: String) -> String {
+    /// Judge expiry by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn policy(&self) -> &SessionPolicy {
+        &self.policy
+    }
+
+    /// Create a new session for a user, returning its ID and the session itself
+    pub fn create_session(&self, user_id: String, remember_me: bool) -> (String, Session) {
         let session_id = Uuid::new_v4().to_string();
-        let session = Session::new(user_id);
+        let session = Session::new(user_id, remember_me, &self.policy, self.clock.now());
 
         if let Ok(mut sessions) = self.sessions.write() {
-            sessions.insert(session_id.clone(), session);
+            sessions.insert(session_id.clone(), session.clone());
         }
 
-        session_id
+        (session_id, session)
     }
 
     /// Get session by ID
@@ -69,26 +149,105 @@ impl SessionStore {
         }
     }
 
+    /// Remove every session belonging to a user, returning how many were removed
+    pub fn remove_user_sessions(&self, user_id: &str) -> usize {
+        if let Ok(mut sessions) = self.sessions.write() {
+            let before = sessions.len();
+            sessions.retain(|_, session| session.user_id != user_id);
+            before - sessions.len()
+        } else {
+            0
+        }
+    }
+
+    /// Remove every session, returning how many were removed
+    pub fn clear(&self) -> usize {
+        if let Ok(mut sessions) = self.sessions.write() {
+            let count = sessions.len();
+            sessions.clear();
+            count
+        } else {
+            0
+        }
+    }
+
     /// Clean up expired sessions
     pub fn cleanup_expired(&self) {
+        let now = self.clock.now();
         if let Ok(mut sessions) = self.sessions.write() {
-            sessions.retain(|_, session| !session.is_expired());
+            sessions.retain(|_, session| !session.is_expired(now));
         }
     }
 
-    /// Get user ID from session
-    pub fn get_user_id(&self, session_id: &str) -> Option<String> {
-        if let Some(session) = self.get_session(session_id) {
-            if !session.is_expired() {
-                Some(session.user_id)
-            } else {
-                // Clean up expired session
-                self.remove_session(session_id);
-                None
-            }
+    /// Number of sessions that have not expired
+    pub fn active_count(&self) -> usize {
+        let now = self.clock.now();
+        if let Ok(sessions) = self.sessions.read() {
+            sessions.values().filter(|session| !session.is_expired(now)).count()
         } else {
+            0
+        }
+    }
+
+    /// Get a session by ID if it has not expired
+    pub fn get_active_session(&self, session_id: &str) -> Option<Session> {
+        let session = self.get_session(session_id)?;
+        if session.is_expired(self.clock.now()) {
+            // Clean up expired session
+            self.remove_session(session_id);
             None
+        } else {
+            Some(session)
+        }
+    }
+
+    /// Get an active session and extend its idle expiry
+    pub fn touch_session(&self, session_id: &str) -> Option<Session> {
+        let now = self.clock.now();
+        let mut sessions = self.sessions.write().ok()?;
+        let session = sessions.get_mut(session_id)?;
+        if session.is_expired(now) {
+            sessions.remove(session_id);
+            return None;
         }
+        session.touch(now, &self.policy);
+        Some(session.clone())
+    }
+
+    /// Write every active session to storage, replacing what was saved before
+    pub async fn save(&self, db: &Database) -> Result<usize> {
+        let now = self.clock.now();
+        let sessions: Vec<(String, Session)> = match self.sessions.read() {
+            Ok(sessions) => sessions
+                .iter()
+                .filter(|(_, session)| !session.is_expired(now))
+                .map(|(id, session)| (id.clone(), session.clone()))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        db.replace_sessions(&sessions).await?;
+        Ok(sessions.len())
+    }
+
+    /// Restore the sessions written by [`Self::save`] that have not expired.
+    ///
+    /// The saved sessions are removed afterwards so that sessions ended while
+    /// the server runs cannot come back after a crash.
+    pub async fn load(&self, db: &Database) -> Result<usize> {
+        let saved = db.take_sessions(self.clock.now()).await?;
+
+        let restored = saved.len();
+        if let Ok(mut sessions) = self.sessions.write() {
+            sessions.extend(saved);
+        }
+
+        Ok(restored)
+    }
+
+    /// Get user ID from session
+    pub fn get_user_id(&self, session_id: &str) -> Option<String> {
+        self.get_active_session(session_id).map(|session| session.user_id)
     }
 }
 
@@ -96,4 +255,4 @@ impl Default for SessionStore {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
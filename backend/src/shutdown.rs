@@ -0,0 +1,125 @@
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+/// Resolves on SIGINT (Ctrl+C) or, on Unix, SIGTERM
+pub async fn termination_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Serve `app` until `signal` resolves, then stop accepting connections and
+/// give in-flight requests up to `drain_timeout` to finish.
+///
+/// `shutdown` is cancelled as soon as the signal arrives so background jobs
+/// sharing the token can stop alongside the server. Returns an error if the
+/// server failed, and `Ok(false)` if requests were still running at the
+/// deadline and had to be dropped.
+pub async fn serve_with_shutdown(
+    listener: TcpListener,
+    app: Router,
+    signal: impl Future<Output = ()>,
+    shutdown: CancellationToken,
+    drain_timeout: Duration,
+) -> Result<bool> {
+    // Connection info gives handlers the peer address for the audit log
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .into_future();
+    let server = tokio::spawn(async move { server.await.map_err(anyhow::Error::from) });
+
+    drain(server, signal, shutdown, drain_timeout, || {}).await
+}
+
+/// [`serve_with_shutdown`] over HTTPS, with HTTP/1.1 and HTTP/2
+pub async fn serve_tls_with_shutdown(
+    listener: TcpListener,
+    tls: RustlsConfig,
+    app: Router,
+    signal: impl Future<Output = ()>,
+    shutdown: CancellationToken,
+    drain_timeout: Duration,
+) -> Result<bool> {
+    let handle = Handle::new();
+    let server = axum_server::from_tcp_rustls(listener.into_std()?, tls)
+        .handle(handle.clone())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>());
+    let server = tokio::spawn(async move { server.await.map_err(anyhow::Error::from) });
+
+    // Stop accepting once shutdown starts; `drain` enforces the deadline
+    let stop = handle.clone();
+    let cancelled = shutdown.clone();
+    tokio::spawn(async move {
+        cancelled.cancelled().await;
+        stop.graceful_shutdown(None);
+    });
+
+    drain(server, signal, shutdown, drain_timeout, move || handle.shutdown()).await
+}
+
+/// Wait for `signal`, then for the server to finish its in-flight requests,
+/// calling `force_close` if it is still busy after `drain_timeout`
+async fn drain(
+    mut server: JoinHandle<Result<()>>,
+    signal: impl Future<Output = ()>,
+    shutdown: CancellationToken,
+    drain_timeout: Duration,
+    force_close: impl FnOnce(),
+) -> Result<bool> {
+    tokio::select! {
+        result = &mut server => {
+            // The server stopped on its own, which only happens on error
+            shutdown.cancel();
+            result??;
+            return Ok(true);
+        }
+        _ = signal => {}
+        _ = shutdown.cancelled() => {}
+    }
+
+    tracing::info!("Shutdown requested; draining in-flight requests for up to {:?}", drain_timeout);
+    shutdown.cancel();
+
+    match tokio::time::timeout(drain_timeout, &mut server).await {
+        Ok(result) => {
+            result.map_err(|e| anyhow!("Server task failed: {}", e))??;
+            Ok(true)
+        }
+        Err(_) => {
+            tracing::warn!("Requests still running after {:?}; closing them", drain_timeout);
+            force_close();
+            server.abort();
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use crate::models::audit_event::{AuditEvent, AuditQuery, NewAuditEvent, MAX_AUDIT_QUERY_LIMIT};
+use crate::models::quota::{QuotaOverride, QuotaUsage};
+use crate::models::user::UserSummary;
+use crate::models::{FlushMemo, ForeverMemo, User};
+use crate::services::rate_limit::Bucket;
+use crate::services::session::Session;
+use super::{
+    AuditRepository, CleanupRepository, MemoRepository, MigrationStatus, QuotaRepository, RateLimitRepository,
+    SessionRepository, SqlPool, Storage, UserRepository, USERNAME_TAKEN,
+};
+
+/// Rows of every table, in insertion order
+#[derive(Default)]
+struct Tables {
+    users: Vec<User>,
+    forever_memos: Vec<ForeverMemo>,
+    flush_memos: Vec<FlushMemo>,
+    user_quotas: HashMap<String, QuotaOverride>,
+    cleanup_logs: Vec<(u64, DateTime<Utc>)>,
+    sessions: HashMap<String, Session>,
+    rate_limit_buckets: Vec<(String, Bucket)>,
+    audit_events: Vec<AuditEvent>,
+}
+
+impl Tables {
+    fn user_exists(&self, user_id: &str) -> bool {
+        self.users.iter().any(|user| user.id == user_id)
+    }
+}
+
+/// Newest first; rows created in the same instant keep the latest insert first
+fn newest_first<T>(mut rows: Vec<T>, created_at: impl Fn(&T) -> DateTime<Utc>) -> Vec<T> {
+    rows.reverse();
+    rows.sort_by_key(|row| std::cmp::Reverse(created_at(row)));
+    rows
+}
+
+/// Storage kept in process memory, with the same semantics as the SQL
+/// backends: unique usernames, memos tied to an existing user, and deletes
+/// that cascade from users. Nothing survives the process, so it is meant
+/// for tests of service logic that don't need a database.
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tables(&self) -> std::sync::MutexGuard<'_, Tables> {
+        // A panic in another test thread must not poison every later call
+        self.tables.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl UserRepository for MemoryStorage {
+    async fn insert_user(&self, user_id: &str, username: &str, password_hash: &str) -> Result<User> {
+        let mut tables = self.tables();
+        if tables.users.iter().any(|user| user.username == username) {
+            return Err(anyhow!(USERNAME_TAKEN));
+        }
+
+        let user = User {
+            id: user_id.to_string(),
+            username: username.to_string(),
+            password_hash: password_hash.to_string(),
+            created_at: Utc::now(),
+            is_admin: false,
+            disabled_at: None,
+        };
+        tables.users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn find_user_by_id(&self, user_id: &str) -> Result<Option<User>> {
+        Ok(self.tables().users.iter().find(|user| user.id == user_id).cloned())
+    }
+
+    async fn find_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        Ok(self.tables().users.iter().find(|user| user.username == username).cloned())
+    }
+
+    async fn update_password_hash(&self, user_id: &str, password_hash: &str) -> Result<()> {
+        if let Some(user) = self.tables().users.iter_mut().find(|user| user.id == user_id) {
+            user.password_hash = password_hash.to_string();
+        }
+        Ok(())
+    }
+
+    async fn set_admin(&self, user_id: &str, is_admin: bool) -> Result<bool> {
+        let mut tables = self.tables();
+        let Some(user) = tables.users.iter_mut().find(|user| user.id == user_id) else {
+            return Ok(false);
+        };
+        user.is_admin = is_admin;
+        Ok(true)
+    }
+
+    async fn set_disabled_at(&self, user_id: &str, disabled_at: Option<DateTime<Utc>>) -> Result<bool> {
+        let mut tables = self.tables();
+        let Some(user) = tables.users.iter_mut().find(|user| user.id == user_id) else {
+            return Ok(false);
+        };
+        user.disabled_at = disabled_at;
+        Ok(true)
+    }
+
+    async fn delete_user(&self, user_id: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        tables.forever_memos.retain(|memo| memo.user_id != user_id);
+        tables.flush_memos.retain(|memo| memo.user_id != user_id);
+        tables.sessions.retain(|_, session| session.user_id != user_id);
+        tables.user_quotas.remove(user_id);
+
+        let before = tables.users.len();
+        tables.users.retain(|user| user.id != user_id);
+        Ok(tables.users.len() < before)
+    }
+
+    async fn list_users_with_memo_counts(&self, now: DateTime<Utc>) -> Result<Vec<UserSummary>> {
+        let tables = self.tables();
+        let mut users: Vec<UserSummary> = tables
+            .users
+            .iter()
+            .map(|user| UserSummary {
+                id: user.id.clone(),
+                username: user.username.clone(),
+                created_at: user.created_at,
+                is_admin: user.is_admin,
+                disabled_at: user.disabled_at,
+                forever_memo_count: tables.forever_memos.iter().filter(|memo| memo.user_id == user.id).count() as i64,
+                flush_memo_count: tables
+                    .flush_memos
+                    .iter()
+                    .filter(|memo| memo.user_id == user.id && memo.expires_at > now)
+                    .count() as i64,
+            })
+            .collect();
+        users.sort_by_key(|user| user.created_at);
+        Ok(users)
+    }
+}
+
+#[async_trait]
+impl MemoRepository for MemoryStorage {
+    async fn insert_forever_memo(&self, memo_id: &str, user_id: &str, content: &str) -> Result<ForeverMemo> {
+        let mut tables = self.tables();
+        if !tables.user_exists(user_id) {
+            return Err(anyhow!("Failed to create forever memo: unknown user {}", user_id));
+        }
+
+        let memo = ForeverMemo {
+            id: memo_id.to_string(),
+            user_id: user_id.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+        };
+        tables.forever_memos.push(memo.clone());
+        Ok(memo)
+    }
+
+    async fn list_forever_memos(&self, user_id: &str) -> Result<Vec<ForeverMemo>> {
+        let tables = self.tables();
+        let memos = tables.forever_memos.iter().filter(|memo| memo.user_id == user_id).cloned().collect();
+        Ok(newest_first(memos, |memo| memo.created_at))
+    }
+
+    async fn find_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<Option<ForeverMemo>> {
+        Ok(self
+            .tables()
+            .forever_memos
+            .iter()
+            .find(|memo| memo.id == memo_id && memo.user_id == user_id)
+            .cloned())
+    }
+
+    async fn update_forever_memo(&self, memo_id: &str, user_id: &str, content: &str) -> Result<Option<ForeverMemo>> {
+        let mut tables = self.tables();
+        let memo = tables
+            .forever_memos
+            .iter_mut()
+            .find(|memo| memo.id == memo_id && memo.user_id == user_id);
+        Ok(memo.map(|memo| {
+            memo.content = content.to_string();
+            memo.clone()
+        }))
+    }
+
+    async fn delete_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        let before = tables.forever_memos.len();
+        tables.forever_memos.retain(|memo| !(memo.id == memo_id && memo.user_id == user_id));
+        Ok(tables.forever_memos.len() < before)
+    }
+
+    async fn insert_flush_memo(&self, memo: &FlushMemo) -> Result<()> {
+        let mut tables = self.tables();
+        if !tables.user_exists(&memo.user_id) {
+            return Err(anyhow!("Failed to create flush memo: unknown user {}", memo.user_id));
+        }
+        if tables.flush_memos.iter().any(|existing| existing.id == memo.id) {
+            return Err(anyhow!("Failed to create flush memo: duplicate id {}", memo.id));
+        }
+
+        tables.flush_memos.push(memo.clone());
+        Ok(())
+    }
+
+    async fn list_flush_memos(&self, user_id: &str, now: DateTime<Utc>) -> Result<Vec<FlushMemo>> {
+        let tables = self.tables();
+        let memos = tables
+            .flush_memos
+            .iter()
+            .filter(|memo| memo.user_id == user_id && memo.expires_at > now)
+            .cloned()
+            .collect();
+        Ok(newest_first(memos, |memo| memo.created_at))
+    }
+
+    async fn find_flush_memo(&self, memo_id: &str, user_id: &str, now: DateTime<Utc>) -> Result<Option<FlushMemo>> {
+        Ok(self
+            .tables()
+            .flush_memos
+            .iter()
+            .find(|memo| memo.id == memo_id && memo.user_id == user_id && memo.expires_at > now)
+            .cloned())
+    }
+
+    async fn delete_flush_memo(&self, memo_id: &str, user_id: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        let before = tables.flush_memos.len();
+        tables.flush_memos.retain(|memo| !(memo.id == memo_id && memo.user_id == user_id));
+        Ok(tables.flush_memos.len() < before)
+    }
+}
+
+#[async_trait]
+impl QuotaRepository for MemoryStorage {
+    async fn memo_usage(&self, user_id: &str, now: DateTime<Utc>) -> Result<QuotaUsage> {
+        let tables = self.tables();
+        let forever = tables.forever_memos.iter().filter(|memo| memo.user_id == user_id);
+        let flush = tables
+            .flush_memos
+            .iter()
+            .filter(|memo| memo.user_id == user_id && memo.expires_at > now);
+
+        let forever_bytes: usize = forever.clone().map(|memo| memo.content.len()).sum();
+        let flush_bytes: usize = flush.clone().map(|memo| memo.content.len()).sum();
+        let flush_memos = flush.count() as u64;
+        Ok(QuotaUsage {
+            memos: forever.count() as u64 + flush_memos,
+            flush_memos,
+            bytes: (forever_bytes + flush_bytes) as u64,
+        })
+    }
+
+    async fn find_quota_override(&self, user_id: &str) -> Result<QuotaOverride> {
+        Ok(self.tables().user_quotas.get(user_id).copied().unwrap_or_default())
+    }
+
+    async fn set_quota_override(&self, user_id: &str, quota: &QuotaOverride) -> Result<bool> {
+        let mut tables = self.tables();
+        if !tables.user_exists(user_id) {
+            return Ok(false);
+        }
+
+        if quota.is_empty() {
+            tables.user_quotas.remove(user_id);
+        } else {
+            tables.user_quotas.insert(user_id.to_string(), *quota);
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl CleanupRepository for MemoryStorage {
+    async fn delete_expired_flush_memos(&self, now: DateTime<Utc>) -> Result<u64> {
+        let mut tables = self.tables();
+        let before = tables.flush_memos.len();
+        tables.flush_memos.retain(|memo| memo.expires_at > now);
+        Ok((before - tables.flush_memos.len()) as u64)
+    }
+
+    async fn delete_all_flush_memos(&self) -> Result<u64> {
+        let mut tables = self.tables();
+        let removed = tables.flush_memos.len();
+        tables.flush_memos.clear();
+        Ok(removed as u64)
+    }
+
+    async fn count_flush_memos(&self, now: DateTime<Utc>) -> Result<(i64, i64)> {
+        let tables = self.tables();
+        let expired = tables.flush_memos.iter().filter(|memo| memo.expires_at <= now).count();
+        Ok((tables.flush_memos.len() as i64, expired as i64))
+    }
+
+    async fn log_cleanup_run(&self, cleaned_count: u64, run_time: DateTime<Utc>) -> Result<()> {
+        self.tables().cleanup_logs.push((cleaned_count, run_time));
+        Ok(())
+    }
+
+    async fn cleanup_history(&self, limit: i64) -> Result<Vec<(u64, DateTime<Utc>)>> {
+        let tables = self.tables();
+        let mut runs = newest_first(tables.cleanup_logs.clone(), |(_, run_time)| *run_time);
+        runs.truncate(limit.max(0) as usize);
+        Ok(runs)
+    }
+
+    async fn last_cleanup(&self) -> Result<Option<DateTime<Utc>>> {
+        Ok(self.tables().cleanup_logs.iter().map(|(_, run_time)| *run_time).max())
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MemoryStorage {
+    async fn replace_sessions(&self, sessions: &[(String, Session)]) -> Result<()> {
+        self.tables().sessions = sessions.iter().cloned().collect();
+        Ok(())
+    }
+
+    async fn take_sessions(&self, now: DateTime<Utc>) -> Result<Vec<(String, Session)>> {
+        let sessions = std::mem::take(&mut self.tables().sessions);
+        Ok(sessions.into_iter().filter(|(_, session)| session.expires_at > now).collect())
+    }
+}
+
+#[async_trait]
+impl RateLimitRepository for MemoryStorage {
+    async fn replace_rate_limit_buckets(&self, buckets: &[(String, Bucket)]) -> Result<()> {
+        self.tables().rate_limit_buckets = buckets.to_vec();
+        Ok(())
+    }
+
+    async fn take_rate_limit_buckets(&self) -> Result<Vec<(String, Bucket)>> {
+        Ok(std::mem::take(&mut self.tables().rate_limit_buckets))
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MemoryStorage {
+    async fn insert_audit_event(&self, event: &NewAuditEvent, created_at: DateTime<Utc>) -> Result<()> {
+        let mut tables = self.tables();
+        let id = tables.audit_events.last().map_or(1, |last| last.id + 1);
+        tables.audit_events.push(AuditEvent {
+            id,
+            event_type: event.event_type.as_str().to_string(),
+            user_id: event.user_id.clone(),
+            actor_id: event.actor_id.clone(),
+            ip: event.ip.clone(),
+            user_agent: event.user_agent.clone(),
+            request_id: event.request_id.clone(),
+            details: event.details.clone().map(Json),
+            created_at,
+        });
+        Ok(())
+    }
+
+    async fn query_audit_events(&self, filter: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let tables = self.tables();
+        let matches = |value: &Option<String>, wanted: &Option<String>| {
+            wanted.as_ref().is_none_or(|wanted| value.as_ref() == Some(wanted))
+        };
+        let events = tables.audit_events.iter().filter(|event| {
+            matches(&event.user_id, &filter.user_id)
+                && matches(&event.actor_id, &filter.actor_id)
+                && filter.event_type.as_ref().is_none_or(|wanted| &event.event_type == wanted)
+                && filter.since.is_none_or(|since| event.created_at >= since)
+                && filter.until.is_none_or(|until| event.created_at < until)
+        });
+
+        let mut events = newest_first(events.cloned().collect(), |event| event.created_at);
+        events.truncate(filter.limit.unwrap_or(100).clamp(1, MAX_AUDIT_QUERY_LIMIT) as usize);
+        Ok(events)
+    }
+
+    async fn delete_audit_events_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let mut tables = self.tables();
+        let before = tables.audit_events.len();
+        tables.audit_events.retain(|event| event.created_at >= cutoff);
+        Ok((before - tables.audit_events.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+
+    fn sql_pool(&self) -> Option<SqlPool<'_>> {
+        None
+    }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// There is no schema to migrate
+    async fn migration_status(&self) -> Result<MigrationStatus> {
+        Ok(MigrationStatus { applied: 0, failed: 0, latest: 0 })
+    }
+
+    async fn close(&self) {}
+}
@@ -0,0 +1,244 @@
+//! Persistence behind repository traits, so the same services run on SQLite
+//! or PostgreSQL. The backend is chosen by the `DATABASE_URL` scheme.
+//! [`MemoryStorage`] implements the same traits without a database, for
+//! tests of service logic.
+
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+
+use std::sync::Arc;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, QueryBuilder, SqlitePool};
+use crate::config::DatabaseConfig;
+use crate::models::audit_event::{AuditEvent, AuditQuery, NewAuditEvent, MAX_AUDIT_QUERY_LIMIT};
+use crate::models::quota::{QuotaOverride, QuotaUsage};
+use crate::models::user::UserSummary;
+use crate::models::{FlushMemo, ForeverMemo, User};
+use crate::services::rate_limit::Bucket;
+use crate::services::session::Session;
+
+pub use memory::MemoryStorage;
+pub use postgres::PostgresStorage;
+pub use sqlite::SqliteStorage;
+
+/// Handle to the configured storage backend, shared by every service
+pub type Database = Arc<dyn Storage>;
+
+/// Error returned by `insert_user` when the username is in use, on every backend
+pub const USERNAME_TAKEN: &str = "Username already exists";
+
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Insert a user; the backend fills in `created_at`
+    async fn insert_user(&self, user_id: &str, username: &str, password_hash: &str) -> Result<User>;
+    async fn find_user_by_id(&self, user_id: &str) -> Result<Option<User>>;
+    async fn find_user_by_username(&self, username: &str) -> Result<Option<User>>;
+    async fn update_password_hash(&self, user_id: &str, password_hash: &str) -> Result<()>;
+    /// Returns false if the user does not exist
+    async fn set_admin(&self, user_id: &str, is_admin: bool) -> Result<bool>;
+    /// Returns false if the user does not exist
+    async fn set_disabled_at(&self, user_id: &str, disabled_at: Option<DateTime<Utc>>) -> Result<bool>;
+    /// Delete a user with their memos and saved sessions
+    async fn delete_user(&self, user_id: &str) -> Result<bool>;
+    /// Every user with their forever memo count and flush memos still alive at `now`
+    async fn list_users_with_memo_counts(&self, now: DateTime<Utc>) -> Result<Vec<UserSummary>>;
+}
+
+/// Memo lookups are always scoped to the owning user
+#[async_trait]
+pub trait MemoRepository: Send + Sync {
+    /// Insert a forever memo; the backend fills in `created_at`
+    async fn insert_forever_memo(&self, memo_id: &str, user_id: &str, content: &str) -> Result<ForeverMemo>;
+    /// Newest first
+    async fn list_forever_memos(&self, user_id: &str) -> Result<Vec<ForeverMemo>>;
+    async fn find_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<Option<ForeverMemo>>;
+    async fn update_forever_memo(&self, memo_id: &str, user_id: &str, content: &str) -> Result<Option<ForeverMemo>>;
+    async fn delete_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<bool>;
+
+    async fn insert_flush_memo(&self, memo: &FlushMemo) -> Result<()>;
+    /// Memos not yet expired at `now`, newest first
+    async fn list_flush_memos(&self, user_id: &str, now: DateTime<Utc>) -> Result<Vec<FlushMemo>>;
+    async fn find_flush_memo(&self, memo_id: &str, user_id: &str, now: DateTime<Utc>) -> Result<Option<FlushMemo>>;
+    async fn delete_flush_memo(&self, memo_id: &str, user_id: &str) -> Result<bool>;
+}
+
+/// Per-user quota overrides and the usage they are checked against
+#[async_trait]
+pub trait QuotaRepository: Send + Sync {
+    /// Memo counts and content bytes of a user, counting flush memos alive at `now`
+    async fn memo_usage(&self, user_id: &str, now: DateTime<Utc>) -> Result<QuotaUsage>;
+    /// An empty override when the admins have set none
+    async fn find_quota_override(&self, user_id: &str) -> Result<QuotaOverride>;
+    /// Replace a user's override, removing it when empty. Returns false if
+    /// the user does not exist.
+    async fn set_quota_override(&self, user_id: &str, quota: &QuotaOverride) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait CleanupRepository: Send + Sync {
+    async fn delete_expired_flush_memos(&self, now: DateTime<Utc>) -> Result<u64>;
+    async fn delete_all_flush_memos(&self) -> Result<u64>;
+    /// Total flush memos and how many of them have expired at `now`
+    async fn count_flush_memos(&self, now: DateTime<Utc>) -> Result<(i64, i64)>;
+    async fn log_cleanup_run(&self, cleaned_count: u64, run_time: DateTime<Utc>) -> Result<()>;
+    /// The last `limit` runs, newest first
+    async fn cleanup_history(&self, limit: i64) -> Result<Vec<(u64, DateTime<Utc>)>>;
+    async fn last_cleanup(&self) -> Result<Option<DateTime<Utc>>>;
+}
+
+/// Sessions saved across restarts
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    /// Replace every saved session
+    async fn replace_sessions(&self, sessions: &[(String, Session)]) -> Result<()>;
+    /// Remove every saved session, returning those still valid at `now`
+    async fn take_sessions(&self, now: DateTime<Utc>) -> Result<Vec<(String, Session)>>;
+}
+
+/// Rate limit buckets saved across restarts
+#[async_trait]
+pub trait RateLimitRepository: Send + Sync {
+    /// Replace every saved bucket
+    async fn replace_rate_limit_buckets(&self, buckets: &[(String, Bucket)]) -> Result<()>;
+    /// Remove every saved bucket, returning them
+    async fn take_rate_limit_buckets(&self) -> Result<Vec<(String, Bucket)>>;
+}
+
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    async fn insert_audit_event(&self, event: &NewAuditEvent, created_at: DateTime<Utc>) -> Result<()>;
+    /// Events matching every given filter, newest first
+    async fn query_audit_events(&self, filter: &AuditQuery) -> Result<Vec<AuditEvent>>;
+    async fn delete_audit_events_before(&self, cutoff: DateTime<Utc>) -> Result<u64>;
+}
+
+/// Migrations applied to a database compared with those built in
+#[derive(Debug, Clone, Copy)]
+pub struct MigrationStatus {
+    pub applied: i64,
+    pub failed: i64,
+    pub latest: i64,
+}
+
+/// The connection pool behind a SQL backend
+pub enum SqlPool<'a> {
+    Sqlite(&'a SqlitePool),
+    Postgres(&'a PgPool),
+}
+
+impl SqlPool<'_> {
+    /// Open connections, idle connections and the configured maximum
+    pub fn connections(&self) -> (u32, usize, u32) {
+        match self {
+            SqlPool::Sqlite(pool) => (pool.size(), pool.num_idle(), pool.options().get_max_connections()),
+            SqlPool::Postgres(pool) => (pool.size(), pool.num_idle(), pool.options().get_max_connections()),
+        }
+    }
+}
+
+/// Everything the application persists
+#[async_trait]
+pub trait Storage:
+    UserRepository
+    + MemoRepository
+    + QuotaRepository
+    + CleanupRepository
+    + SessionRepository
+    + RateLimitRepository
+    + AuditRepository
+{
+    /// `sqlite` or `postgres`
+    fn backend(&self) -> &'static str;
+    fn sql_pool(&self) -> Option<SqlPool<'_>>;
+    /// Round trip to the database
+    async fn ping(&self) -> Result<()>;
+    async fn migration_status(&self) -> Result<MigrationStatus>;
+    /// Flush pending writes and close every connection
+    async fn close(&self);
+}
+
+/// Storage backends selectable through `DATABASE_URL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    pub fn from_url(database_url: &str) -> Result<Self> {
+        if database_url.starts_with("sqlite:") {
+            Ok(Backend::Sqlite)
+        } else if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+            Ok(Backend::Postgres)
+        } else {
+            Err(anyhow!("unsupported database URL scheme, expected sqlite: or postgres://"))
+        }
+    }
+}
+
+/// Connect to the configured database and apply its migrations
+pub async fn connect(config: &DatabaseConfig) -> Result<Database> {
+    let storage: Database = match Backend::from_url(&config.url)? {
+        Backend::Sqlite => Arc::new(SqliteStorage::connect(config).await?),
+        Backend::Postgres => Arc::new(PostgresStorage::connect(config).await?),
+    };
+    tracing::info!("Database initialized successfully ({})", storage.backend());
+    Ok(storage)
+}
+
+/// Map a failed user insert, turning unique violations into `USERNAME_TAKEN`
+fn insert_user_error(err: sqlx::Error) -> anyhow::Error {
+    match err.as_database_error() {
+        Some(db_err) if db_err.is_unique_violation() => anyhow!(USERNAME_TAKEN),
+        _ => anyhow!("Failed to create user: {}", err),
+    }
+}
+
+/// Quota limits are stored as signed integers; larger values are rejected by the API
+fn quota_column(limit: Option<u64>) -> Option<i64> {
+    limit.map(|max| i64::try_from(max).unwrap_or(i64::MAX))
+}
+
+/// Read back a stored quota limit
+fn quota_limit(column: Option<i64>) -> Option<u64> {
+    column.map(|max| max.max(0) as u64)
+}
+
+/// Append the audit query filters, ordering and limit; shared by the SQL backends
+fn push_audit_filters<'a, DB>(query: &mut QueryBuilder<'a, DB>, filter: &'a AuditQuery)
+where
+    DB: sqlx::Database,
+    &'a String: sqlx::Encode<'a, DB> + sqlx::Type<DB>,
+    DateTime<Utc>: sqlx::Encode<'a, DB> + sqlx::Type<DB>,
+    i64: sqlx::Encode<'a, DB> + sqlx::Type<DB>,
+{
+    query.push(" WHERE 1 = 1");
+    if let Some(user_id) = &filter.user_id {
+        query.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(actor_id) = &filter.actor_id {
+        query.push(" AND actor_id = ").push_bind(actor_id);
+    }
+    if let Some(event_type) = &filter.event_type {
+        query.push(" AND event_type = ").push_bind(event_type);
+    }
+    if let Some(since) = filter.since {
+        query.push(" AND created_at >= ").push_bind(since);
+    }
+    if let Some(until) = filter.until {
+        query.push(" AND created_at < ").push_bind(until);
+    }
+    let limit = filter.limit.unwrap_or(100).clamp(1, MAX_AUDIT_QUERY_LIMIT);
+    query.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit);
+}
+
+const AUDIT_SELECT: &str =
+    "SELECT id, event_type, user_id, actor_id, ip, user_agent, request_id, details, created_at FROM audit_events";
+
+/// Migration status query, valid on both SQL backends
+const MIGRATION_STATUS_SQL: &str = "SELECT MAX(CASE WHEN success THEN version END) AS applied,
+        SUM(CASE WHEN success THEN 0 ELSE 1 END) AS failed
+ FROM _sqlx_migrations";
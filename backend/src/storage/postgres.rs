@@ -0,0 +1,559 @@
+use std::str::FromStr;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    migrate::Migrator,
+    postgres::{PgConnectOptions, PgPoolOptions},
+    types::Json,
+    Connection, PgConnection, PgPool, Postgres, QueryBuilder, Row,
+};
+use crate::config::DatabaseConfig;
+use crate::models::audit_event::{AuditEvent, AuditQuery, NewAuditEvent};
+use crate::models::quota::{QuotaOverride, QuotaUsage};
+use crate::models::user::UserSummary;
+use crate::models::{FlushMemo, ForeverMemo, User};
+use crate::services::rate_limit::Bucket;
+use crate::services::session::Session;
+use super::{
+    push_audit_filters, quota_column, quota_limit, AuditRepository, CleanupRepository, MemoRepository, MigrationStatus,
+    QuotaRepository, RateLimitRepository, SessionRepository, SqlPool, Storage, UserRepository, AUDIT_SELECT,
+    MIGRATION_STATUS_SQL,
+};
+
+/// PostgreSQL migrations embedded in the binary
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+/// Schema version this binary migrates PostgreSQL databases to
+pub fn latest_migration_version() -> i64 {
+    MIGRATOR.iter().map(|migration| migration.version).max().unwrap_or(0)
+}
+
+const USER_COLUMNS: &str = "SELECT id, username, password_hash, created_at, is_admin, disabled_at FROM users";
+
+#[derive(Clone)]
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Connect to the server and migrate the database
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .acquire_timeout(config.acquire_timeout())
+            .connect_with(PgConnectOptions::from_str(&config.url)?)
+            .await?;
+        MIGRATOR.run(&pool).await?;
+        Ok(Self::new(pool))
+    }
+
+    /// A freshly migrated, uniquely named schema on the given server, so
+    /// tests can share one database without seeing each other's rows
+    pub async fn isolated(database_url: &str) -> Result<Self> {
+        let options = PgConnectOptions::from_str(database_url)?;
+        let schema = format!("test_{}", uuid::Uuid::new_v4().simple());
+
+        let mut conn = PgConnection::connect_with(&options).await?;
+        sqlx::query(&format!("CREATE SCHEMA {}", schema)).execute(&mut conn).await?;
+        conn.close().await?;
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(options.options([("search_path", schema.as_str())]))
+            .await?;
+        MIGRATOR.run(&pool).await?;
+        Ok(Self::new(pool))
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl UserRepository for PostgresStorage {
+    async fn insert_user(&self, user_id: &str, username: &str, password_hash: &str) -> Result<User> {
+        sqlx::query("INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(username)
+            .bind(password_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(super::insert_user_error)?;
+
+        let user = sqlx::query_as::<_, User>(&format!("{} WHERE id = $1", USER_COLUMNS))
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(user)
+    }
+
+    async fn find_user_by_id(&self, user_id: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as::<_, User>(&format!("{} WHERE id = $1", USER_COLUMNS))
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(user)
+    }
+
+    async fn find_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as::<_, User>(&format!("{} WHERE username = $1", USER_COLUMNS))
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(user)
+    }
+
+    async fn update_password_hash(&self, user_id: &str, password_hash: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+            .bind(password_hash)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_admin(&self, user_id: &str, is_admin: bool) -> Result<bool> {
+        let rows_affected = sqlx::query("UPDATE users SET is_admin = $1 WHERE id = $2")
+            .bind(is_admin)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected > 0)
+    }
+
+    async fn set_disabled_at(&self, user_id: &str, disabled_at: Option<DateTime<Utc>>) -> Result<bool> {
+        let rows_affected = sqlx::query("UPDATE users SET disabled_at = $1 WHERE id = $2")
+            .bind(disabled_at)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected > 0)
+    }
+
+    /// Memos and sessions go with the user through `ON DELETE CASCADE`
+    async fn delete_user(&self, user_id: &str) -> Result<bool> {
+        let rows_affected = sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected > 0)
+    }
+
+    async fn list_users_with_memo_counts(&self, now: DateTime<Utc>) -> Result<Vec<UserSummary>> {
+        let users = sqlx::query_as::<_, UserSummary>(
+            "SELECT u.id, u.username, u.created_at, u.is_admin, u.disabled_at,
+                    (SELECT COUNT(*) FROM forever_memos f WHERE f.user_id = u.id) AS forever_memo_count,
+                    (SELECT COUNT(*) FROM flush_memos m WHERE m.user_id = u.id AND m.expires_at > $1) AS flush_memo_count
+             FROM users u ORDER BY u.created_at ASC"
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(users)
+    }
+}
+
+#[async_trait]
+impl MemoRepository for PostgresStorage {
+    async fn insert_forever_memo(&self, memo_id: &str, user_id: &str, content: &str) -> Result<ForeverMemo> {
+        sqlx::query("INSERT INTO forever_memos (id, user_id, content) VALUES ($1, $2, $3)")
+            .bind(memo_id)
+            .bind(user_id)
+            .bind(content)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to create forever memo: {}", e))?;
+
+        let memo = sqlx::query_as::<_, ForeverMemo>(
+            "SELECT id, user_id, content, created_at FROM forever_memos WHERE id = $1"
+        )
+        .bind(memo_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(memo)
+    }
+
+    async fn list_forever_memos(&self, user_id: &str) -> Result<Vec<ForeverMemo>> {
+        let memos = sqlx::query_as::<_, ForeverMemo>(
+            "SELECT id, user_id, content, created_at FROM forever_memos
+             WHERE user_id = $1 ORDER BY created_at DESC"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(memos)
+    }
+
+    async fn find_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<Option<ForeverMemo>> {
+        let memo = sqlx::query_as::<_, ForeverMemo>(
+            "SELECT id, user_id, content, created_at FROM forever_memos WHERE id = $1 AND user_id = $2"
+        )
+        .bind(memo_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(memo)
+    }
+
+    async fn update_forever_memo(&self, memo_id: &str, user_id: &str, content: &str) -> Result<Option<ForeverMemo>> {
+        let memo = sqlx::query_as::<_, ForeverMemo>(
+            "UPDATE forever_memos SET content = $1 WHERE id = $2 AND user_id = $3
+             RETURNING id, user_id, content, created_at"
+        )
+        .bind(content)
+        .bind(memo_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(memo)
+    }
+
+    async fn delete_forever_memo(&self, memo_id: &str, user_id: &str) -> Result<bool> {
+        let rows_affected = sqlx::query("DELETE FROM forever_memos WHERE id = $1 AND user_id = $2")
+            .bind(memo_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected > 0)
+    }
+
+    async fn insert_flush_memo(&self, memo: &FlushMemo) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO flush_memos (id, user_id, content, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(&memo.id)
+        .bind(&memo.user_id)
+        .bind(&memo.content)
+        .bind(memo.created_at)
+        .bind(memo.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to create flush memo: {}", e))?;
+        Ok(())
+    }
+
+    async fn list_flush_memos(&self, user_id: &str, now: DateTime<Utc>) -> Result<Vec<FlushMemo>> {
+        let memos = sqlx::query_as::<_, FlushMemo>(
+            "SELECT id, user_id, content, created_at, expires_at FROM flush_memos
+             WHERE user_id = $1 AND expires_at > $2 ORDER BY created_at DESC"
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(memos)
+    }
+
+    async fn find_flush_memo(&self, memo_id: &str, user_id: &str, now: DateTime<Utc>) -> Result<Option<FlushMemo>> {
+        let memo = sqlx::query_as::<_, FlushMemo>(
+            "SELECT id, user_id, content, created_at, expires_at FROM flush_memos
+             WHERE id = $1 AND user_id = $2 AND expires_at > $3"
+        )
+        .bind(memo_id)
+        .bind(user_id)
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(memo)
+    }
+
+    async fn delete_flush_memo(&self, memo_id: &str, user_id: &str) -> Result<bool> {
+        let rows_affected = sqlx::query("DELETE FROM flush_memos WHERE id = $1 AND user_id = $2")
+            .bind(memo_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(rows_affected > 0)
+    }
+}
+
+#[async_trait]
+impl QuotaRepository for PostgresStorage {
+    async fn memo_usage(&self, user_id: &str, now: DateTime<Utc>) -> Result<QuotaUsage> {
+        let row = sqlx::query(
+            "SELECT (SELECT COUNT(*) FROM forever_memos WHERE user_id = $1) AS forever_memos,
+                    (SELECT COUNT(*) FROM flush_memos WHERE user_id = $1 AND expires_at > $2) AS flush_memos,
+                    (SELECT COALESCE(SUM(octet_length(content)), 0) FROM forever_memos WHERE user_id = $1)
+                    + (SELECT COALESCE(SUM(octet_length(content)), 0) FROM flush_memos WHERE user_id = $1 AND expires_at > $2)
+                    AS bytes"
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let forever_memos = row.get::<i64, _>("forever_memos") as u64;
+        let flush_memos = row.get::<i64, _>("flush_memos") as u64;
+        Ok(QuotaUsage {
+            memos: forever_memos + flush_memos,
+            flush_memos,
+            bytes: row.get::<i64, _>("bytes") as u64,
+        })
+    }
+
+    async fn find_quota_override(&self, user_id: &str) -> Result<QuotaOverride> {
+        let row = sqlx::query("SELECT max_memos, max_flush_memos, max_bytes FROM user_quotas WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row
+            .map(|row| QuotaOverride {
+                max_memos: quota_limit(row.get("max_memos")),
+                max_flush_memos: quota_limit(row.get("max_flush_memos")),
+                max_bytes: quota_limit(row.get("max_bytes")),
+            })
+            .unwrap_or_default())
+    }
+
+    async fn set_quota_override(&self, user_id: &str, quota: &QuotaOverride) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists = sqlx::query("SELECT 1 FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if !exists {
+            return Ok(false);
+        }
+
+        if quota.is_empty() {
+            sqlx::query("DELETE FROM user_quotas WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            sqlx::query(
+                "INSERT INTO user_quotas (user_id, max_memos, max_flush_memos, max_bytes) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (user_id) DO UPDATE SET max_memos = excluded.max_memos,
+                     max_flush_memos = excluded.max_flush_memos, max_bytes = excluded.max_bytes"
+            )
+            .bind(user_id)
+            .bind(quota_column(quota.max_memos))
+            .bind(quota_column(quota.max_flush_memos))
+            .bind(quota_column(quota.max_bytes))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl CleanupRepository for PostgresStorage {
+    async fn delete_expired_flush_memos(&self, now: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM flush_memos WHERE expires_at <= $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_all_flush_memos(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM flush_memos").execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn count_flush_memos(&self, now: DateTime<Utc>) -> Result<(i64, i64)> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS total, COALESCE(SUM(CASE WHEN expires_at <= $1 THEN 1 ELSE 0 END), 0) AS expired
+             FROM flush_memos"
+        )
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok((row.get("total"), row.get("expired")))
+    }
+
+    async fn log_cleanup_run(&self, cleaned_count: u64, run_time: DateTime<Utc>) -> Result<()> {
+        sqlx::query("INSERT INTO cleanup_logs (cleaned_count, run_time) VALUES ($1, $2)")
+            .bind(cleaned_count as i64)
+            .bind(run_time)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn cleanup_history(&self, limit: i64) -> Result<Vec<(u64, DateTime<Utc>)>> {
+        let rows: Vec<(i64, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT cleaned_count, run_time FROM cleanup_logs ORDER BY run_time DESC LIMIT $1"
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|(count, time)| (count as u64, time)).collect())
+    }
+
+    async fn last_cleanup(&self) -> Result<Option<DateTime<Utc>>> {
+        let last_run = sqlx::query_scalar("SELECT run_time FROM cleanup_logs ORDER BY run_time DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(last_run)
+    }
+}
+
+#[async_trait]
+impl SessionRepository for PostgresStorage {
+    async fn replace_sessions(&self, sessions: &[(String, Session)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM sessions").execute(&mut *tx).await?;
+        for (session_id, session) in sessions {
+            sqlx::query(
+                "INSERT INTO sessions (id, user_id, created_at, expires_at, csrf_token, remember_me, last_seen_at, absolute_expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+            )
+            .bind(session_id)
+            .bind(&session.user_id)
+            .bind(session.created_at)
+            .bind(session.expires_at)
+            .bind(&session.csrf_token)
+            .bind(session.remember_me)
+            .bind(session.last_seen_at)
+            .bind(session.absolute_expires_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn take_sessions(&self, now: DateTime<Utc>) -> Result<Vec<(String, Session)>> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            "SELECT id, user_id, created_at, expires_at, csrf_token, remember_me, last_seen_at, absolute_expires_at
+             FROM sessions WHERE expires_at > $1 AND csrf_token != '' AND absolute_expires_at IS NOT NULL"
+        )
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM sessions").execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let created_at: DateTime<Utc> = row.get("created_at");
+                let session = Session {
+                    user_id: row.get("user_id"),
+                    csrf_token: row.get("csrf_token"),
+                    remember_me: row.get("remember_me"),
+                    created_at,
+                    last_seen_at: row.get::<Option<DateTime<Utc>>, _>("last_seen_at").unwrap_or(created_at),
+                    expires_at: row.get("expires_at"),
+                    absolute_expires_at: row.get("absolute_expires_at"),
+                };
+                (row.get("id"), session)
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl RateLimitRepository for PostgresStorage {
+    async fn replace_rate_limit_buckets(&self, buckets: &[(String, Bucket)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM rate_limit_buckets").execute(&mut *tx).await?;
+        for (key, bucket) in buckets {
+            sqlx::query("INSERT INTO rate_limit_buckets (key, tokens, updated_at) VALUES ($1, $2, $3)")
+                .bind(key)
+                .bind(bucket.tokens)
+                .bind(bucket.updated_at)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn take_rate_limit_buckets(&self) -> Result<Vec<(String, Bucket)>> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query("SELECT key, tokens, updated_at FROM rate_limit_buckets")
+            .fetch_all(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM rate_limit_buckets").execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("key"), Bucket { tokens: row.get("tokens"), updated_at: row.get("updated_at") }))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AuditRepository for PostgresStorage {
+    async fn insert_audit_event(&self, event: &NewAuditEvent, created_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO audit_events (event_type, user_id, actor_id, ip, user_agent, request_id, details, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+        )
+        .bind(event.event_type.as_str())
+        .bind(&event.user_id)
+        .bind(&event.actor_id)
+        .bind(&event.ip)
+        .bind(&event.user_agent)
+        .bind(&event.request_id)
+        .bind(event.details.as_ref().map(Json))
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn query_audit_events(&self, filter: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new(AUDIT_SELECT);
+        push_audit_filters(&mut query, filter);
+        Ok(query.build_query_as::<AuditEvent>().fetch_all(&self.pool).await?)
+    }
+
+    async fn delete_audit_events_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM audit_events WHERE created_at < $1")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    fn backend(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn sql_pool(&self) -> Option<SqlPool<'_>> {
+        Some(SqlPool::Postgres(&self.pool))
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").fetch_one(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn migration_status(&self) -> Result<MigrationStatus> {
+        let row = sqlx::query(MIGRATION_STATUS_SQL).fetch_one(&self.pool).await?;
+        Ok(MigrationStatus {
+            applied: row.get::<Option<i64>, _>("applied").unwrap_or(0),
+            failed: row.get::<Option<i64>, _>("failed").unwrap_or(0),
+            latest: latest_migration_version(),
+        })
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+}
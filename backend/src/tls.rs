@@ -0,0 +1,126 @@
+//! HTTPS termination with rustls: the served certificate, reloaded when its
+//! files change, and the plain-HTTP listener redirecting to HTTPS.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::Request,
+    http::{header, uri::Authority, StatusCode},
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tower_http::trace::TraceLayer;
+use crate::{api::middleware::request_id, config::TlsConfig, logging::request_span};
+
+/// Use ring for rustls. Only the first call in a process has an effect.
+fn install_crypto_provider() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+}
+
+/// The certificate being served, with the files it came from
+#[derive(Clone)]
+pub struct TlsCertificate {
+    config: RustlsConfig,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl TlsCertificate {
+    /// Load the configured certificate and key. HTTP/2 is offered through
+    /// ALPN alongside HTTP/1.1.
+    pub async fn load(tls: &TlsConfig) -> Result<Self> {
+        let (Some(cert_path), Some(key_path)) = (&tls.cert_path, &tls.key_path) else {
+            return Err(anyhow!("tls.cert_path and tls.key_path are not set"));
+        };
+        install_crypto_provider();
+
+        let config = RustlsConfig::from_pem_file(cert_path, key_path)
+            .await
+            .with_context(|| format!("Failed to load TLS certificate {}", cert_path.display()))?;
+        Ok(Self { config, cert_path: cert_path.clone(), key_path: key_path.clone() })
+    }
+
+    /// Configuration handed to the server; reloading updates it in place
+    pub fn rustls_config(&self) -> RustlsConfig {
+        self.config.clone()
+    }
+
+    /// Read the files again. New connections get the new certificate;
+    /// open ones keep the old one. On error the old certificate stays.
+    pub async fn reload(&self) -> Result<()> {
+        self.config
+            .reload_from_pem_file(&self.cert_path, &self.key_path)
+            .await
+            .with_context(|| format!("Failed to reload TLS certificate {}", self.cert_path.display()))
+    }
+
+    /// Check the files every `interval` and reload them when either changed,
+    /// e.g. after a certificate renewal
+    pub fn spawn_reloader(self, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut stamps = self.file_stamps().await;
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                let current = self.file_stamps().await;
+                if current == stamps {
+                    continue;
+                }
+                // Remember the change even if it fails: a renewal that has
+                // written only one of the files is retried when the other changes
+                stamps = current;
+                match self.reload().await {
+                    Ok(()) => tracing::info!("Reloaded TLS certificate {}", self.cert_path.display()),
+                    Err(e) => tracing::error!("{:#}; still serving the previous one", e),
+                }
+            }
+        })
+    }
+
+    async fn file_stamps(&self) -> [Option<(SystemTime, u64)>; 2] {
+        [file_stamp(&self.cert_path).await, file_stamp(&self.key_path).await]
+    }
+}
+
+/// Modification time and size, enough to notice a file was replaced
+async fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Router for the plain-HTTP listener configured by `tls.redirect_http_port`:
+/// every request is sent to the same host and path on `https_port`
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new()
+        .fallback(move |request: Request| async move { redirect_to_https(&request, https_port) })
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(middleware::from_fn(request_id))
+}
+
+fn redirect_to_https(request: &Request, https_port: u16) -> Response {
+    let Some(authority) = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.parse::<Authority>().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "Host header required").into_response();
+    };
+
+    let host = match https_port {
+        443 => authority.host().to_string(),
+        port => format!("{}:{}", authority.host(), port),
+    };
+    let path = request.uri().path_and_query().map_or("/", |path| path.as_str());
+    Redirect::permanent(&format!("https://{}{}", host, path)).into_response()
+}
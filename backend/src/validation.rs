@@ -0,0 +1,106 @@
+//! Rules for user input, shared by the API's request validation and the
+//! models' own checks. Lengths count characters rather than bytes, so
+//! Japanese text gets the same limits as ASCII.
+
+use std::fmt;
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::services::password::MAX_PASSWORD_BYTES;
+
+pub const USERNAME_MIN_CHARS: usize = 3;
+pub const USERNAME_MAX_CHARS: usize = 50;
+pub const PASSWORD_MIN_CHARS: usize = 8;
+
+/// One problem with one field of a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    /// Stable identifier for clients: `required`, `too_short`, `too_long` or `out_of_range`
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Every problem found in a request; displays as the messages joined together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationErrors {
+    pub fields: Vec<FieldError>,
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<&str> = self.fields.iter().map(|error| error.message.as_str()).collect();
+        f.write_str(&messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Collects field errors, so a request is rejected with all of its problems at once
+#[derive(Debug, Default)]
+pub struct Validation {
+    errors: Vec<FieldError>,
+}
+
+impl Validation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(mut self, field: &str, code: &'static str, message: impl Into<String>) -> Self {
+        self.errors.push(FieldError { field: field.to_string(), code, message: message.into() });
+        self
+    }
+
+    /// `value` has something besides whitespace
+    pub fn not_blank(self, field: &str, label: &str, value: &str) -> Self {
+        if value.trim().is_empty() {
+            self.error(field, "required", format!("{} cannot be empty", label))
+        } else {
+            self
+        }
+    }
+
+    /// `value` is `min..=max` characters long
+    pub fn char_length(self, field: &str, label: &str, value: &str, min: usize, max: usize) -> Self {
+        let chars = value.chars().count();
+        if chars < min {
+            self.error(field, "too_short", format!("{} must be at least {} characters", label, min))
+        } else if chars > max {
+            self.error(field, "too_long", format!("{} must be at most {} characters", label, max))
+        } else {
+            self
+        }
+    }
+
+    pub fn username(self, field: &str, username: &str) -> Self {
+        self.char_length(field, "Username", username, USERNAME_MIN_CHARS, USERNAME_MAX_CHARS)
+    }
+
+    /// A password being set. The upper bound is in bytes because it guards
+    /// the hasher's input.
+    pub fn new_password(self, field: &str, password: &str) -> Self {
+        if password.len() > MAX_PASSWORD_BYTES {
+            return self.error(
+                field,
+                "too_long",
+                format!("Password must be at most {} bytes long", MAX_PASSWORD_BYTES),
+            );
+        }
+        self.char_length(field, "Password", password, PASSWORD_MIN_CHARS, usize::MAX)
+    }
+
+    pub fn memo_content(self, field: &str, content: &str, max_chars: usize) -> Self {
+        if content.trim().is_empty() {
+            return self.not_blank(field, "Content", content);
+        }
+        self.char_length(field, "Content", content, 1, max_chars)
+    }
+
+    pub fn finish(self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors { fields: self.errors })
+        }
+    }
+}
@@ -115,12 +115,14 @@ if [ "$LOGIN_RESPONSE" != "200" ]; then
     exit 1
 fi
 print_status "User login working"
+CSRF_TOKEN=$(jq -r .csrf_token /tmp/login_response.json)
 
 # Test memo creation
 echo "Testing memo creation..."
 MEMO_RESPONSE=$(curl -s -w "%{http_code}" -o /tmp/memo_response.json \
     -X POST http://127.0.0.1:3001/api/memos/forever \
     -H "Content-Type: application/json" \
+    -H "X-CSRF-Token: $CSRF_TOKEN" \
     -j /tmp/cookies.txt \
     -d '{"content":"Integration test memo"}')
 
@@ -0,0 +1,129 @@
+use memory_memo::services::{AdminService, AuthService, MemoService};
+use memory_memo::models::User;
+use memory_memo::database::create_test_database;
+use anyhow::Result;
+
+#[tokio::test]
+async fn test_admin_bootstrap_creates_admin() -> Result<()> {
+    let pool = create_test_database().await?;
+    let admin_service = AdminService::new(pool.clone());
+
+    let admin = admin_service.bootstrap_admin("admin", Some("adminpass123")).await?;
+    assert!(admin.is_admin);
+
+    // The bootstrapped admin can log in with the given password
+    let auth_service = AuthService::new(pool.clone());
+    let user = auth_service.login("admin", "adminpass123").await?;
+    assert!(user.is_admin);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_bootstrap_promotes_existing_user() -> Result<()> {
+    let pool = create_test_database().await?;
+    let admin_service = AdminService::new(pool.clone());
+
+    let user = User::create(&pool, "existing", "original123").await?;
+    assert!(!user.is_admin);
+
+    // Existing users are promoted only with their own password
+    let admin = admin_service.bootstrap_admin("existing", Some("original123")).await?;
+    assert!(admin.is_admin);
+    assert_eq!(admin.id, user.id);
+    assert!(User::find_by_id(&pool, &user.id).await?.unwrap().verify_password("original123"));
+
+    // Once admin, later starts need no password
+    assert!(admin_service.bootstrap_admin("existing", None).await?.is_admin);
+
+    // Creating a new admin requires a password
+    assert!(admin_service.bootstrap_admin("newadmin", None).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_bootstrap_refuses_preregistered_username() -> Result<()> {
+    let pool = create_test_database().await?;
+    let admin_service = AdminService::new(pool.clone());
+
+    // Someone registered the configured username before the operator started the server
+    let squatter = User::create(&pool, "admin", "squatter123").await?;
+
+    let err = admin_service.bootstrap_admin("admin", Some("adminpass123")).await.unwrap_err();
+    assert!(err.to_string().contains("already exists and is not an admin"), "{err}");
+    assert!(admin_service.bootstrap_admin("admin", None).await.is_err());
+    assert!(!User::find_by_id(&pool, &squatter.id).await?.unwrap().is_admin);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_disable_blocks_login() -> Result<()> {
+    let pool = create_test_database().await?;
+    let admin_service = AdminService::new(pool.clone());
+    let auth_service = AuthService::new(pool.clone());
+
+    let user = auth_service.register("testuser", "password123").await?;
+    assert!(admin_service.set_disabled(&user.id, true).await?);
+
+    let err = auth_service.login("testuser", "password123").await.unwrap_err();
+    assert!(err.to_string().contains("Account is disabled"));
+
+    // Wrong passwords still report invalid credentials
+    let err = auth_service.login("testuser", "wrongpassword").await.unwrap_err();
+    assert!(err.to_string().contains("Invalid username or password"));
+
+    assert!(admin_service.set_disabled(&user.id, false).await?);
+    auth_service.login("testuser", "password123").await?;
+
+    // Unknown users are reported, not errors
+    assert!(!admin_service.set_disabled("missing", true).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_reset_password() -> Result<()> {
+    let pool = create_test_database().await?;
+    let admin_service = AdminService::new(pool.clone());
+    let auth_service = AuthService::new(pool.clone());
+
+    let user = auth_service.register("testuser", "password123").await?;
+    assert!(admin_service.reset_password(&user.id, "newpassword456").await?);
+
+    assert!(auth_service.login("testuser", "password123").await.is_err());
+    auth_service.login("testuser", "newpassword456").await?;
+
+    assert!(admin_service.reset_password(&user.id, "short").await.is_err());
+    assert!(!admin_service.reset_password("missing", "newpassword456").await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_list_users_with_memo_counts() -> Result<()> {
+    let pool = create_test_database().await?;
+    let admin_service = AdminService::new(pool.clone());
+    let memo_service = MemoService::new(pool.clone());
+
+    let alice = User::create(&pool, "alice", "password123").await?;
+    let bob = User::create(&pool, "bob", "password123").await?;
+    memo_service.create_forever_memo(&alice.id, "Forever 1").await?;
+    memo_service.create_forever_memo(&alice.id, "Forever 2").await?;
+    memo_service.create_flush_memo(&alice.id, "Flush 1").await?;
+    memo_service.create_flush_memo(&bob.id, "Flush 2").await?;
+
+    let users = admin_service.list_users().await?;
+    assert_eq!(users.len(), 2);
+
+    let alice_summary = users.iter().find(|u| u.id == alice.id).unwrap();
+    assert_eq!(alice_summary.forever_memo_count, 2);
+    assert_eq!(alice_summary.flush_memo_count, 1);
+
+    let bob_summary = users.iter().find(|u| u.id == bob.id).unwrap();
+    assert_eq!(bob_summary.forever_memo_count, 0);
+    assert_eq!(bob_summary.flush_memo_count, 1);
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+async fn create_test_server(config: Config) -> Result<TestServer> {
+    let pool = create_test_database().await?;
+    let app = create_app(pool, config).await?;
+    let mut server = TestServer::new(app)?;
+    server.do_save_cookies();
+    Ok(server)
+}
+
+/// Register and log in through `prefix`, sending the CSRF token from then on
+async fn log_in(server: &mut TestServer, prefix: &str) -> Result<()> {
+    let credentials = json!({"username": "testuser", "password": "password123"});
+    server.post(&format!("{}/auth/register", prefix)).json(&credentials).await;
+    let response = server.post(&format!("{}/auth/login", prefix)).json(&credentials).await;
+    response.assert_status_ok();
+
+    let csrf_token = response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    server.add_header(HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(&csrf_token)?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_v1_routes_are_not_deprecated() -> Result<()> {
+    let mut server = create_test_server(Config::default()).await?;
+    log_in(&mut server, "/api/v1").await?;
+
+    let response = server.post("/api/v1/memos/forever").json(&json!({"content": "Versioned"})).await;
+    response.assert_status(StatusCode::CREATED);
+    assert!(response.maybe_header("deprecation").is_none());
+    assert!(response.maybe_header("sunset").is_none());
+
+    let response = server.get("/api/v1/memos").await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["forever_memos"][0]["content"], "Versioned");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unversioned_aliases_are_deprecated() -> Result<()> {
+    let mut server = create_test_server(Config::default()).await?;
+    log_in(&mut server, "/api").await?;
+
+    // Same handlers and session as v1
+    server.post("/api/memos/flush").json(&json!({"content": "Old path"})).await.assert_status(StatusCode::CREATED);
+    server.get("/api/v1/memos").await.assert_status_ok();
+
+    let response = server.get("/api/memos").await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["flush_memos"][0]["content"], "Old path");
+    assert_eq!(response.header("deprecation"), "@1792281600");
+    assert_eq!(response.header("sunset"), "Sun, 18 Apr 2027 00:00:00 GMT");
+    assert_eq!(response.header("link"), r#"</api/v1/memos>; rel="successor-version""#);
+
+    // Rejections carry the notice too
+    server.clear_cookies();
+    let response = server.get("/api/auth/me").await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+    assert_eq!(response.header("link"), r#"</api/v1/auth/me>; rel="successor-version""#);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_successor_link_includes_base_path() -> Result<()> {
+    let mut config = Config::default();
+    config.server.base_path = "/memory_memo".to_string();
+    config.api.unversioned_sunset = "2027-01-31".parse()?;
+    let server = create_test_server(config).await?;
+
+    let response = server.get("/memory_memo/api/auth/me").await;
+    assert_eq!(response.header("sunset"), "Sun, 31 Jan 2027 00:00:00 GMT");
+    assert_eq!(response.header("link"), r#"</memory_memo/api/v1/auth/me>; rel="successor-version""#);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unversioned_aliases_can_be_turned_off() -> Result<()> {
+    let mut config = Config::default();
+    config.api.unversioned_aliases = false;
+    let mut server = create_test_server(config).await?;
+
+    log_in(&mut server, "/api/v1").await?;
+    server.get("/api/v1/memos").await.assert_status_ok();
+    server.get("/api/memos").await.assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_version_is_not_found() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    server.get("/api/v2/memos").await.assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
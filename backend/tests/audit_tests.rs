@@ -0,0 +1,242 @@
+use axum_test::TestServer;
+use axum::http::{header, HeaderName, HeaderValue, StatusCode};
+use chrono::{Duration, Utc};
+use memory_memo::{create_app, AppState};
+use memory_memo::config::Config;
+use memory_memo::database::{create_test_database, Database};
+use memory_memo::models::{AuditEvent, AuditEventType, NewAuditEvent, User};
+use memory_memo::services::AuditService;
+use serde_json::{json, Value};
+use tower_cookies::cookie::Cookie;
+use anyhow::Result;
+
+async fn create_test_server() -> Result<(TestServer, Database)> {
+    let pool = create_test_database().await?;
+    let mut config = Config::default();
+    config.server.trust_forwarded_for = true;
+    let app = create_app(pool.clone(), config).await?;
+    Ok((TestServer::new(app)?, pool))
+}
+
+/// Register and log in a user, returning the session cookie and CSRF token
+async fn login(server: &TestServer, username: &str, password: &str) -> Result<(Cookie<'static>, String)> {
+    server
+        .post("/api/auth/register")
+        .json(&json!({"username": username, "password": password}))
+        .await;
+
+    let response = server
+        .post("/api/auth/login")
+        .add_header(HeaderName::from_static("x-forwarded-for"), HeaderValue::from_static("6.6.6.6, 10.0.0.1"))
+        .add_header(header::USER_AGENT, HeaderValue::from_static("audit-test/1.0"))
+        .json(&json!({"username": username, "password": password}))
+        .await;
+
+    let session = response.cookies().get("session_id").unwrap().clone().into_owned();
+    let csrf_token = response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    Ok((session, csrf_token))
+}
+
+fn csrf_header(token: &str) -> Result<(HeaderName, HeaderValue)> {
+    Ok((HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(token)?))
+}
+
+async fn activity(server: &TestServer, session: &Cookie<'static>) -> Vec<Value> {
+    let response = server.get("/api/auth/activity").add_cookie(session.clone()).await;
+    response.assert_status_ok();
+    response.json::<Value>()["events"].as_array().unwrap().clone()
+}
+
+fn event_types(events: &[Value]) -> Vec<&str> {
+    events.iter().map(|event| event["event_type"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_activity_records_account_and_memo_events() -> Result<()> {
+    let (server, _pool) = create_test_server().await?;
+
+    server
+        .post("/api/auth/register")
+        .json(&json!({"username": "alice", "password": "password123"}))
+        .await;
+    server
+        .post("/api/auth/login")
+        .json(&json!({"username": "alice", "password": "not-her-password"}))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    let (session, csrf) = login(&server, "alice", "password123").await?;
+    let (name, value) = csrf_header(&csrf)?;
+
+    let memo: Value = server
+        .post("/api/memos/forever")
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .json(&json!({"content": "Audited memo"}))
+        .await
+        .json();
+    let memo_id = memo["id"].as_str().unwrap();
+    server
+        .put(&format!("/api/memos/forever/{}", memo_id))
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .json(&json!({"content": "Edited"}))
+        .await;
+    server
+        .delete(&format!("/api/memos/forever/{}", memo_id))
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .await;
+
+    // Contract: newest first, covering the whole account history
+    let events = activity(&server, &session).await;
+    assert_eq!(
+        event_types(&events),
+        ["memo_deleted", "memo_updated", "memo_created", "login_succeeded", "login_failed", "register"]
+    );
+    assert_eq!(events[0]["details"]["memo_id"], memo_id);
+    assert_eq!(events[0]["details"]["memo_type"], "forever");
+    assert_eq!(events[4]["details"]["reason"], "invalid_credentials");
+
+    // Contract: client details are captured, with the IP our proxy appended
+    // to X-Forwarded-For rather than the forged entry the client sent
+    assert_eq!(events[3]["ip"], "10.0.0.1");
+    assert_eq!(events[3]["user_agent"], "audit-test/1.0");
+    assert!(events[3]["request_id"].is_string());
+    assert!(events[3]["created_at"].is_string());
+
+    // Logging out is recorded too
+    server
+        .post("/api/auth/logout")
+        .add_cookie(session.clone())
+        .add_header(name, value)
+        .await
+        .assert_status_ok();
+    let (session, _) = login(&server, "alice", "password123").await?;
+    let events = activity(&server, &session).await;
+    assert_eq!(event_types(&events)[..2], ["login_succeeded", "logout"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_activity_is_private_to_the_account() -> Result<()> {
+    let (server, _pool) = create_test_server().await?;
+
+    let (alice, _) = login(&server, "alice", "password123").await?;
+    let (bob, _) = login(&server, "bob", "password123").await?;
+    // A failed login for an unknown name belongs to nobody
+    server
+        .post("/api/auth/login")
+        .json(&json!({"username": "mallory", "password": "password123"}))
+        .await;
+
+    for (session, username) in [(&alice, "alice"), (&bob, "bob")] {
+        let events = activity(&server, session).await;
+        assert_eq!(event_types(&events), ["login_succeeded", "register"], "{}", username);
+    }
+
+    server.get("/api/auth/activity").await.assert_status(StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_actions_are_audited_and_queryable() -> Result<()> {
+    let (server, pool) = create_test_server().await?;
+
+    let (user_session, _) = login(&server, "regular", "password123").await?;
+    let user = User::find_by_username(&pool, "regular").await?.unwrap();
+    server
+        .post("/api/auth/register")
+        .json(&json!({"username": "admin", "password": "adminpass123"}))
+        .await;
+    let admin = User::find_by_username(&pool, "admin").await?.unwrap();
+    User::set_admin(&pool, &admin.id, true).await?;
+    let (admin_session, admin_csrf) = login(&server, "admin", "adminpass123").await?;
+    let (name, value) = csrf_header(&admin_csrf)?;
+
+    server
+        .post(&format!("/api/admin/users/{}/reset-password", user.id))
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .json(&json!({"password": "newpassword123"}))
+        .await
+        .assert_status_ok();
+    server
+        .post(&format!("/api/admin/users/{}/disable", user.id))
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .await
+        .assert_status_ok();
+
+    // Contract: admins filter by user and event type
+    let response = server
+        .get("/api/admin/audit")
+        .add_cookie(admin_session.clone())
+        .add_query_param("user_id", &user.id)
+        .add_query_param("event_type", "sessions_revoked")
+        .await;
+    response.assert_status_ok();
+    let events = response.json::<Value>()["events"].as_array().unwrap().clone();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| event["actor_id"] == admin.id.as_str()));
+    assert_eq!(events[0]["details"]["reason"], "account_disabled");
+    assert_eq!(events[1]["details"]["reason"], "password_reset");
+
+    let response = server
+        .get("/api/admin/audit")
+        .add_cookie(admin_session.clone())
+        .add_query_param("actor_id", &admin.id)
+        .await;
+    assert_eq!(
+        event_types(response.json::<Value>()["events"].as_array().unwrap()),
+        ["sessions_revoked", "admin_user_disabled", "sessions_revoked", "password_changed"]
+    );
+
+    server
+        .get("/api/admin/audit")
+        .add_cookie(admin_session)
+        .add_query_param("event_type", "made_up")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Contract: disabling revoked the user's session
+    server
+        .get("/api/auth/activity")
+        .add_cookie(user_session)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Contract: the audit log is admin-only
+    let (other_session, _) = login(&server, "other", "password123").await?;
+    server
+        .get("/api/admin/audit")
+        .add_cookie(other_session)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scheduled_cleanup_enforces_audit_retention() -> Result<()> {
+    let pool = create_test_database().await?;
+    let mut config = Config::default();
+    config.audit.retention_days = 30;
+    let state = AppState::new(pool.clone(), config)?;
+
+    let audit = AuditService::new(pool.clone());
+    pool.insert_audit_event(
+        &NewAuditEvent::new(AuditEventType::LoginSucceeded).user("old-user"),
+        Utc::now() - Duration::days(31),
+    )
+    .await?;
+    audit.record(NewAuditEvent::new(AuditEventType::LoginSucceeded).user("recent-user")).await;
+
+    state.cleanup_service().run_scheduled().await?;
+
+    assert!(AuditEvent::list_for_user(&pool, "old-user", 10).await?.is_empty());
+    assert_eq!(AuditEvent::list_for_user(&pool, "recent-user", 10).await?.len(), 1);
+
+    Ok(())
+}
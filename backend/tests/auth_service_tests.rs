@@ -1,4 +1,5 @@
 use memory_memo::services::AuthService;
+use memory_memo::services::password::{Argon2Config, PasswordCheck, PasswordHasher, MAX_PASSWORD_BYTES};
 use memory_memo::models::User;
 use memory_memo::database::create_test_database;
 use anyhow::Result;
@@ -128,4 +129,78 @@ async fn test_auth_password_validation() -> Result<()> {
     assert!(result.is_ok());
     
     Ok(())
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_auth_password_too_long() -> Result<()> {
+    let pool = create_test_database().await?;
+    let auth_service = AuthService::new(pool.clone());
+
+    let too_long = "a".repeat(MAX_PASSWORD_BYTES + 1);
+    let result = auth_service.register("testuser", &too_long).await;
+    assert!(result.is_err());
+
+    let longest = "a".repeat(MAX_PASSWORD_BYTES);
+    let result = auth_service.register("testuser", &longest).await;
+    assert!(result.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auth_new_users_get_argon2id_hashes() -> Result<()> {
+    let pool = create_test_database().await?;
+    let auth_service = AuthService::new(pool.clone());
+
+    let user = auth_service.register("testuser", "password123").await?;
+    let stored = User::find_by_id(&pool, &user.id).await?.unwrap();
+    assert!(stored.password_hash.starts_with("$argon2id$"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auth_login_upgrades_bcrypt_hash() -> Result<()> {
+    let pool = create_test_database().await?;
+    let auth_service = AuthService::new(pool.clone());
+
+    // Simulate an account created before the Argon2 migration
+    let user = auth_service.register("legacyuser", "password123").await?;
+    let legacy_hash = bcrypt::hash("password123", 4)?;
+    User::update_password_hash(&pool, &user.id, &legacy_hash).await?;
+
+    // Wrong password must not trigger an upgrade
+    assert!(auth_service.login("legacyuser", "wrong_password").await.is_err());
+    let stored = User::find_by_id(&pool, &user.id).await?.unwrap();
+    assert_eq!(stored.password_hash, legacy_hash);
+
+    // Successful login verifies the bcrypt hash and replaces it
+    auth_service.login("legacyuser", "password123").await?;
+    let stored = User::find_by_id(&pool, &user.id).await?.unwrap();
+    assert!(stored.password_hash.starts_with("$argon2id$"));
+
+    // The upgraded hash keeps working
+    auth_service.login("legacyuser", "password123").await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auth_login_rehashes_outdated_argon2_params() -> Result<()> {
+    let pool = create_test_database().await?;
+    let weak = PasswordHasher::new(Argon2Config { memory_kib: 8, iterations: 1, parallelism: 1 })?;
+    let weak_service = AuthService::with_hasher(pool.clone(), weak);
+    let user = weak_service.register("testuser", "password123").await?;
+    let weak_hash = User::find_by_id(&pool, &user.id).await?.unwrap().password_hash;
+
+    let auth_service = AuthService::new(pool.clone());
+    auth_service.login("testuser", "password123").await?;
+
+    let stored = User::find_by_id(&pool, &user.id).await?.unwrap();
+    assert_ne!(stored.password_hash, weak_hash);
+    assert_eq!(
+        PasswordHasher::default().verify("password123", &stored.password_hash),
+        PasswordCheck::Valid
+    );
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::path::PathBuf;
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use memory_memo::create_app;
+use memory_memo::config::{Config, DatabaseConfig};
+use memory_memo::database::{init_database, Database};
+use memory_memo::models::User;
+use memory_memo::models::audit_event::AuditQuery;
+use memory_memo::services::BackupService;
+use memory_memo::services::backup::verify_snapshot;
+use memory_memo::storage::SqlPool;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tower_cookies::cookie::Cookie;
+use anyhow::Result;
+
+/// VACUUM INTO needs a file-backed database
+struct TestDatabase {
+    pool: Database,
+    path: PathBuf,
+    backup_dir: PathBuf,
+}
+
+impl TestDatabase {
+    async fn new() -> Result<Self> {
+        let id = uuid::Uuid::new_v4();
+        let path = std::env::temp_dir().join(format!("memory_memo_{}.db", id));
+        let backup_dir = std::env::temp_dir().join(format!("memory_memo_backups_{}", id));
+        let pool = init_database(&DatabaseConfig::with_url(format!("sqlite://{}", path.display()))).await?;
+        Ok(Self { pool, path, backup_dir })
+    }
+
+    fn backup_service(&self) -> BackupService {
+        let Some(SqlPool::Sqlite(pool)) = self.pool.sql_pool() else { unreachable!() };
+        BackupService::new(pool.clone(), self.backup_dir.clone()).with_verification(true)
+    }
+
+    async fn cleanup(self) -> Result<()> {
+        self.pool.close().await;
+        std::fs::remove_file(&self.path)?;
+        let _ = std::fs::remove_dir_all(&self.backup_dir);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_backups_are_verified_and_rotated() -> Result<()> {
+    let db = TestDatabase::new().await?;
+    User::create(&db.pool, "alice", "password123").await?;
+    let service = db.backup_service().with_rotation(2);
+
+    let first = service.create_backup().await?;
+    let second = service.create_backup().await?;
+    let third = service.create_backup().await?;
+    assert!(first.size_bytes > 0);
+
+    // Only the newest two are kept, listed newest first
+    let names: Vec<String> = service.list_backups().await?.into_iter().map(|b| b.name).collect();
+    assert_eq!(names, vec![third.name.clone(), second.name]);
+    assert!(service.backup_path(&first.name).is_err());
+
+    let check = verify_snapshot(&service.backup_path(&third.name)?).await?;
+    assert!(check.integrity_ok);
+    assert_eq!(check.schema_version, memory_memo::storage::sqlite::latest_migration_version());
+
+    // Names that could leave the backup directory are rejected
+    assert!(service.backup_path("../memory_memo.db").is_err());
+    assert!(service.backup_path("memory_memo_backup_../../etc.db").is_err());
+
+    db.cleanup().await
+}
+
+#[tokio::test]
+async fn test_restore_replaces_data_and_keeps_a_safety_backup() -> Result<()> {
+    let db = TestDatabase::new().await?;
+    User::create(&db.pool, "alice", "password123").await?;
+    let service = db.backup_service();
+    let snapshot = service.create_backup().await?;
+
+    // Change the live data after the snapshot
+    let alice = User::find_by_username(&db.pool, "alice").await?.unwrap();
+    User::delete(&db.pool, &alice.id).await?;
+    User::create(&db.pool, "bob", "password123").await?;
+
+    let report = service.restore(&service.backup_path(&snapshot.name)?).await?;
+    assert!(User::find_by_username(&db.pool, "alice").await?.is_some());
+    assert!(User::find_by_username(&db.pool, "bob").await?.is_none());
+
+    // The data replaced by the restore is still available
+    let safety_url = format!("sqlite://{}", service.backup_path(&report.safety_backup.name)?.display());
+    let safety = init_database(&DatabaseConfig::with_url(safety_url)).await?;
+    assert!(User::find_by_username(&safety, "bob").await?.is_some());
+    safety.close().await;
+
+    db.cleanup().await
+}
+
+#[tokio::test]
+async fn test_restore_rejects_newer_or_damaged_snapshots() -> Result<()> {
+    let db = TestDatabase::new().await?;
+    User::create(&db.pool, "alice", "password123").await?;
+    let service = db.backup_service();
+    let snapshot = service.backup_path(&service.create_backup().await?.name)?;
+
+    // A snapshot written by a newer build
+    let newer = SqlitePool::connect(&format!("sqlite://{}", snapshot.display())).await?;
+    sqlx::query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (9999, 'future', 1, x'00', 0)",
+    )
+    .execute(&newer)
+    .await?;
+    newer.close().await;
+
+    let err = service.restore(&snapshot).await.unwrap_err();
+    assert!(err.to_string().contains("newer than this build"), "{}", err);
+
+    // Something that is not a database at all
+    let garbage = db.backup_dir.join("memory_memo_backup_20000101_000000.db");
+    std::fs::write(&garbage, b"definitely not sqlite")?;
+    assert!(service.restore(&garbage).await.is_err());
+
+    // Live data is untouched and no safety backups were taken
+    assert!(User::find_by_username(&db.pool, "alice").await?.is_some());
+    assert_eq!(service.list_backups().await?.len(), 2);
+
+    db.cleanup().await
+}
+
+async fn login_admin(server: &TestServer, pool: &Database) -> Result<(Cookie<'static>, (HeaderName, HeaderValue))> {
+    let admin = User::create(pool, "admin", "adminpass123").await?;
+    User::set_admin(pool, &admin.id, true).await?;
+
+    let response = server
+        .post("/api/auth/login")
+        .json(&json!({"username": "admin", "password": "adminpass123"}))
+        .await;
+    let session = response.cookies().get("session_id").unwrap().clone().into_owned();
+    let csrf = response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    Ok((session, (HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(&csrf)?)))
+}
+
+#[tokio::test]
+async fn test_admin_backup_endpoints() -> Result<()> {
+    let db = TestDatabase::new().await?;
+    let mut config = Config::default();
+    config.backup.dir = db.backup_dir.clone();
+    let server = TestServer::new(create_app(db.pool.clone(), config).await?)?;
+    let (session, (name, value)) = login_admin(&server, &db.pool).await?;
+
+    let response = server
+        .post("/api/admin/backups")
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let backup_name = response.json::<Value>()["name"].as_str().unwrap().to_owned();
+
+    let listed: Value = server.get("/api/admin/backups").add_cookie(session.clone()).await.json();
+    assert_eq!(listed["backups"][0]["name"], backup_name);
+
+    let check: Value = server
+        .post(&format!("/api/admin/backups/{}/verify", backup_name))
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .await
+        .json();
+    assert_eq!(check["integrity_ok"], true);
+
+    server
+        .post("/api/admin/backups/memory_memo_backup_19990101_000000.db/restore")
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Restoring logs everyone out, including the admin who asked for it
+    User::create(&db.pool, "bob", "password123").await?;
+    let response = server
+        .post(&format!("/api/admin/backups/{}/restore", backup_name))
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["revoked_sessions"], 1);
+    assert!(User::find_by_username(&db.pool, "bob").await?.is_none());
+    server
+        .get("/api/admin/backups")
+        .add_cookie(session)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // The restore is on record even though it replaced the audit log
+    let restored = AuditQuery {
+        event_type: Some("admin_backup_restored".to_string()),
+        ..AuditQuery::default()
+    };
+    assert_eq!(db.pool.query_audit_events(&restored).await?.len(), 1);
+
+    db.cleanup().await
+}
@@ -0,0 +1,103 @@
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+async fn create_test_server(base_path: &str) -> Result<TestServer> {
+    let pool = create_test_database().await?;
+    let mut config = Config::default();
+    config.server.base_path = base_path.to_string();
+    let app = create_app(pool, config).await?;
+    TestServer::new(app)
+}
+
+#[tokio::test]
+async fn test_routes_are_served_under_base_path() -> Result<()> {
+    let server = create_test_server("/memory_memo").await?;
+
+    server.get("/memory_memo/health").await.assert_status_ok();
+    server.get("/memory_memo/").await.assert_status_ok();
+    server.get("/memory_memo/app.js").await.assert_status_ok();
+
+    // Nothing is served outside the prefix
+    server.get("/health").await.assert_status(StatusCode::NOT_FOUND);
+    server
+        .post("/api/auth/register")
+        .json(&json!({"username": "testuser", "password": "password123"}))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bare_base_path_redirects_to_index() -> Result<()> {
+    let server = create_test_server("/memory_memo").await?;
+
+    let response = server.get("/memory_memo").await;
+    response.assert_status(StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.header("location"), "/memory_memo/");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_frontend_learns_base_path() -> Result<()> {
+    let server = create_test_server("/memory_memo").await?;
+
+    let response = server.get("/memory_memo/config.js").await;
+    response.assert_status_ok();
+    assert!(response.header("content-type").to_str()?.starts_with("application/javascript"));
+    assert!(response.text().contains(r#""basePath":"/memory_memo""#));
+
+    // At the root the prefix is empty
+    let server = create_test_server("").await?;
+    assert!(server.get("/config.js").await.text().contains(r#""basePath":"""#));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_cookies_are_scoped_to_base_path() -> Result<()> {
+    let server = create_test_server("/memory_memo").await?;
+
+    server
+        .post("/memory_memo/api/auth/register")
+        .json(&json!({"username": "testuser", "password": "password123"}))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let response = server
+        .post("/memory_memo/api/auth/login")
+        .json(&json!({"username": "testuser", "password": "password123"}))
+        .await;
+    response.assert_status_ok();
+
+    let session = response.cookie("session_id");
+    assert_eq!(session.path(), Some("/memory_memo"));
+    assert_eq!(response.cookie("csrf_token").path(), Some("/memory_memo"));
+
+    // The session works for mutating requests under the prefix
+    let csrf_token = response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    server
+        .post("/memory_memo/api/memos/forever")
+        .add_cookie(session.clone())
+        .add_header(HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(&csrf_token)?)
+        .json(&json!({"content": "Under a prefix"}))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    // Logging out expires the cookies on the same path
+    let response = server
+        .post("/memory_memo/api/auth/logout")
+        .add_cookie(session)
+        .add_header(HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(&csrf_token)?)
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.cookie("session_id").path(), Some("/memory_memo"));
+
+    Ok(())
+}
@@ -3,7 +3,6 @@ use memory_memo::models::{User, FlushMemo};
 use memory_memo::database::create_test_database;
 use anyhow::Result;
 use chrono::{Duration, Utc};
-use sqlx::Row;
 
 #[tokio::test]
 async fn test_cleanup_expired_flush_memos() -> Result<()> {
@@ -13,23 +12,16 @@ async fn test_cleanup_expired_flush_memos() -> Result<()> {
     let user = User::create(&pool, "testuser", "password123").await?;
     
     // Create some memos
-    let fresh_memo = FlushMemo::create(&pool, &user.id, "Fresh memo").await?;
-    let expired_memo = FlushMemo::create(&pool, &user.id, "Expired memo").await?;
-    
-    // Manually set one memo to be expired
-    let past_time = Utc::now() - Duration::hours(25);
-    sqlx::query("UPDATE flush_memos SET expires_at = ? WHERE id = ?")
-        .bind(past_time)
-        .bind(&expired_memo.id)
-        .execute(&pool)
-        .await?;
+    let _fresh_memo = FlushMemo::create(&pool, &user.id, "Fresh memo", Utc::now()).await?;
+    // One memo that expired an hour ago
+    let _expired_memo = FlushMemo::create_with_ttl(&pool, &user.id, "Expired memo", Duration::hours(-1), Utc::now()).await?;
     
     // Run cleanup
     let cleaned_count = cleanup_service.cleanup_expired_flush_memos().await?;
     assert_eq!(cleaned_count, 1);
     
     // Check that only fresh memo remains
-    let remaining_memos = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let remaining_memos = FlushMemo::list_by_user(&pool, &user.id, Utc::now()).await?;
     assert_eq!(remaining_memos.len(), 1);
     assert_eq!(remaining_memos[0].content, "Fresh memo");
     
@@ -44,15 +36,15 @@ async fn test_cleanup_no_expired_memos() -> Result<()> {
     let user = User::create(&pool, "testuser", "password123").await?;
     
     // Create only fresh memos
-    FlushMemo::create(&pool, &user.id, "Fresh memo 1").await?;
-    FlushMemo::create(&pool, &user.id, "Fresh memo 2").await?;
+    FlushMemo::create(&pool, &user.id, "Fresh memo 1", Utc::now()).await?;
+    FlushMemo::create(&pool, &user.id, "Fresh memo 2", Utc::now()).await?;
     
     // Run cleanup - should not delete anything
     let cleaned_count = cleanup_service.cleanup_expired_flush_memos().await?;
     assert_eq!(cleaned_count, 0);
     
     // Check that all memos remain
-    let remaining_memos = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let remaining_memos = FlushMemo::list_by_user(&pool, &user.id, Utc::now()).await?;
     assert_eq!(remaining_memos.len(), 2);
     
     Ok(())
@@ -67,31 +59,23 @@ async fn test_cleanup_multiple_users() -> Result<()> {
     let user2 = User::create(&pool, "user2", "password2").await?;
     
     // Create memos for both users
-    let user1_expired = FlushMemo::create(&pool, &user1.id, "User1 expired").await?;
-    let user1_fresh = FlushMemo::create(&pool, &user1.id, "User1 fresh").await?;
-    let user2_expired = FlushMemo::create(&pool, &user2.id, "User2 expired").await?;
-    let user2_fresh = FlushMemo::create(&pool, &user2.id, "User2 fresh").await?;
-    
-    // Set expired times
-    let past_time = Utc::now() - Duration::hours(25);
-    for expired_id in [&user1_expired.id, &user2_expired.id] {
-        sqlx::query("UPDATE flush_memos SET expires_at = ? WHERE id = ?")
-            .bind(past_time)
-            .bind(expired_id)
-            .execute(&pool)
-            .await?;
-    }
+    // Expired memos were created with a lifetime that has already passed
+    let expired = Duration::hours(-1);
+    let _user1_expired = FlushMemo::create_with_ttl(&pool, &user1.id, "User1 expired", expired, Utc::now()).await?;
+    let _user1_fresh = FlushMemo::create(&pool, &user1.id, "User1 fresh", Utc::now()).await?;
+    let _user2_expired = FlushMemo::create_with_ttl(&pool, &user2.id, "User2 expired", expired, Utc::now()).await?;
+    let _user2_fresh = FlushMemo::create(&pool, &user2.id, "User2 fresh", Utc::now()).await?;
     
     // Run cleanup
     let cleaned_count = cleanup_service.cleanup_expired_flush_memos().await?;
     assert_eq!(cleaned_count, 2);
     
     // Check that only fresh memos remain for both users
-    let user1_remaining = FlushMemo::list_by_user(&pool, &user1.id).await?;
+    let user1_remaining = FlushMemo::list_by_user(&pool, &user1.id, Utc::now()).await?;
     assert_eq!(user1_remaining.len(), 1);
     assert_eq!(user1_remaining[0].content, "User1 fresh");
     
-    let user2_remaining = FlushMemo::list_by_user(&pool, &user2.id).await?;
+    let user2_remaining = FlushMemo::list_by_user(&pool, &user2.id, Utc::now()).await?;
     assert_eq!(user2_remaining.len(), 1);
     assert_eq!(user2_remaining[0].content, "User2 fresh");
     
@@ -106,19 +90,10 @@ async fn test_cleanup_statistics() -> Result<()> {
     let user = User::create(&pool, "testuser", "password123").await?;
     
     // Create several memos with different expiry times
-    let memo1 = FlushMemo::create(&pool, &user.id, "Will expire").await?;
-    let memo2 = FlushMemo::create(&pool, &user.id, "Will also expire").await?;
-    let _memo3 = FlushMemo::create(&pool, &user.id, "Will remain fresh").await?;
-    
-    // Set some to be expired
-    let past_time = Utc::now() - Duration::hours(25);
-    for expired_id in [&memo1.id, &memo2.id] {
-        sqlx::query("UPDATE flush_memos SET expires_at = ? WHERE id = ?")
-            .bind(past_time)
-            .bind(expired_id)
-            .execute(&pool)
-            .await?;
-    }
+    let expired = Duration::hours(-1);
+    let _memo1 = FlushMemo::create_with_ttl(&pool, &user.id, "Will expire", expired, Utc::now()).await?;
+    let _memo2 = FlushMemo::create_with_ttl(&pool, &user.id, "Will also expire", expired, Utc::now()).await?;
+    let _memo3 = FlushMemo::create(&pool, &user.id, "Will remain fresh", Utc::now()).await?;
     
     // Get cleanup statistics
     let stats_before = cleanup_service.get_cleanup_statistics().await?;
@@ -145,25 +120,15 @@ async fn test_cleanup_dry_run() -> Result<()> {
     let user = User::create(&pool, "testuser", "password123").await?;
     
     // Create an expired memo
-    let expired_memo = FlushMemo::create(&pool, &user.id, "Will be checked but not deleted").await?;
-    let past_time = Utc::now() - Duration::hours(25);
-    sqlx::query("UPDATE flush_memos SET expires_at = ? WHERE id = ?")
-        .bind(past_time)
-        .bind(&expired_memo.id)
-        .execute(&pool)
-        .await?;
+    FlushMemo::create_with_ttl(&pool, &user.id, "Will be checked but not deleted", Duration::hours(-1), Utc::now()).await?;
     
     // Run dry run - should report what would be deleted but not actually delete
     let would_be_cleaned = cleanup_service.cleanup_expired_flush_memos_dry_run().await?;
     assert_eq!(would_be_cleaned, 1);
     
     // Verify memo is still there
-    let all_memos_count = sqlx::query("SELECT COUNT(*) as count FROM flush_memos WHERE user_id = ?")
-        .bind(&user.id)
-        .fetch_one(&pool)
-        .await?;
-    let count: i64 = all_memos_count.get("count");
-    assert_eq!(count, 1);
+    let stats = cleanup_service.get_cleanup_statistics().await?;
+    assert_eq!(stats.total_count, 1);
     
     Ok(())
 }
@@ -181,4 +146,22 @@ async fn test_cleanup_with_scheduling_info() -> Result<()> {
     cleanup_service.log_cleanup_run(5, Utc::now()).await?;
     
     Ok(())
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_periodic_cleanup_stops_on_shutdown() -> Result<()> {
+    let pool = create_test_database().await?;
+    let shutdown = tokio_util::sync::CancellationToken::new();
+
+    let job = CleanupService::new(pool.clone())
+        .spawn_periodic(std::time::Duration::from_secs(3600), shutdown.clone());
+
+    // The first run happens immediately
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let history = CleanupService::new(pool.clone()).get_cleanup_history(10).await?;
+    assert_eq!(history.len(), 1);
+
+    shutdown.cancel();
+    tokio::time::timeout(std::time::Duration::from_secs(1), job).await??;
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use clap::Parser;
+use memory_memo::cli::{run_command, BackupCommand, Cli, CleanupCommand, Command, UserCommand};
+use memory_memo::config::{Config, DatabaseConfig};
+use memory_memo::database::{create_test_database, Database};
+use memory_memo::models::{AuditEvent, AuditEventType, ForeverMemo, NewAuditEvent, User};
+use memory_memo::services::MemoService;
+use chrono::{Duration, Utc};
+use anyhow::Result;
+use serde_json::Value;
+
+async fn run(pool: &Database, args: &[&str]) -> Result<String> {
+    let cli = Cli::try_parse_from(std::iter::once("memory_memo").chain(args.iter().copied()))?;
+    let mut out = Vec::new();
+    run_command(cli.command.unwrap(), pool.clone(), &Config::default(), &mut out).await?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[test]
+fn test_cli_parses_subcommands() -> Result<()> {
+    // No subcommand means serve
+    assert!(Cli::try_parse_from(["memory_memo"])?.command.is_none());
+
+    let cli = Cli::try_parse_from(["memory_memo", "user", "create", "alice", "--password", "password123", "--admin"])?;
+    assert!(matches!(
+        cli.command,
+        Some(Command::User { command: UserCommand::Create { admin: true, .. } })
+    ));
+
+    let cli = Cli::try_parse_from(["memory_memo", "cleanup", "dry-run"])?;
+    assert!(matches!(cli.command, Some(Command::Cleanup { command: CleanupCommand::DryRun })));
+
+    // Export requires a user
+    assert!(Cli::try_parse_from(["memory_memo", "export"]).is_err());
+
+    let cli = Cli::try_parse_from(["memory_memo", "backup", "restore", "snapshot.db"])?;
+    assert!(matches!(cli.command, Some(Command::Backup { output: None, command: Some(BackupCommand::Restore { .. }) })));
+    assert!(Cli::try_parse_from(["memory_memo", "backup", "--output", "x.db", "list"]).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cli_user_lifecycle() -> Result<()> {
+    let pool = create_test_database().await?;
+
+    run(&pool, &["user", "create", "alice", "--password", "password123", "--admin"]).await?;
+    let alice = User::find_by_username(&pool, "alice").await?.unwrap();
+    assert!(alice.is_admin);
+
+    MemoService::new(pool.clone()).create_forever_memo(&alice.id, "Keep me").await?;
+    let listing = run(&pool, &["user", "list"]).await?;
+    assert!(listing.contains("alice"));
+
+    run(&pool, &["user", "reset-password", "alice", "--password", "newpassword456"]).await?;
+    let alice = User::find_by_username(&pool, "alice").await?.unwrap();
+    assert!(alice.verify_password("newpassword456"));
+
+    run(&pool, &["user", "delete", "alice"]).await?;
+    assert!(User::find_by_username(&pool, "alice").await?.is_none());
+    assert!(ForeverMemo::list_by_user(&pool, &alice.id).await?.is_empty());
+
+    // Unknown users are reported as errors
+    assert!(run(&pool, &["user", "delete", "alice"]).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cli_cleanup_commands() -> Result<()> {
+    let pool = create_test_database().await?;
+
+    assert!(run(&pool, &["cleanup", "dry-run"]).await?.contains("0 expired"));
+    assert!(run(&pool, &["cleanup", "run"]).await?.contains("Removed 0"));
+
+    let stats = run(&pool, &["cleanup", "stats"]).await?;
+    assert!(stats.contains("Flush memos: 0 total"));
+    assert!(!stats.contains("Last cleanup: never"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cli_cleanup_uses_configured_audit_retention() -> Result<()> {
+    let pool = create_test_database().await?;
+    for (user_id, age) in [("old-user", 31), ("recent-user", 29)] {
+        pool.insert_audit_event(
+            &NewAuditEvent::new(AuditEventType::LoginSucceeded).user(user_id),
+            Utc::now() - Duration::days(age),
+        )
+        .await?;
+    }
+
+    let mut config = Config::default();
+    config.audit.retention_days = 30;
+    let cli = Cli::try_parse_from(["memory_memo", "cleanup", "run"])?;
+    let mut out = Vec::new();
+    run_command(cli.command.unwrap(), pool.clone(), &config, &mut out).await?;
+    assert!(String::from_utf8(out)?.contains("Removed 1 audit events older than 30 days"));
+
+    assert!(AuditEvent::list_for_user(&pool, "old-user", 10).await?.is_empty());
+    assert_eq!(AuditEvent::list_for_user(&pool, "recent-user", 10).await?.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cli_export_user() -> Result<()> {
+    let pool = create_test_database().await?;
+    let user = User::create(&pool, "bob", "password123").await?;
+    let memo_service = MemoService::new(pool.clone());
+    memo_service.create_forever_memo(&user.id, "Forever").await?;
+    memo_service.create_flush_memo(&user.id, "Flush").await?;
+
+    let output = run(&pool, &["export", "--user", "bob"]).await?;
+    let export: Value = serde_json::from_str(&output)?;
+    assert_eq!(export["user"]["username"], "bob");
+    assert!(export["user"].get("password_hash").is_none());
+    assert_eq!(export["forever_memos"].as_array().unwrap().len(), 1);
+    assert_eq!(export["flush_memos"].as_array().unwrap().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cli_backup_and_migrate() -> Result<()> {
+    // VACUUM INTO needs a file-backed database
+    let source = std::env::temp_dir().join(format!("memory_memo_{}.db", uuid::Uuid::new_v4()));
+    let pool = memory_memo::database::init_database(&DatabaseConfig::with_url(format!("sqlite://{}", source.display()))).await?;
+    User::create(&pool, "carol", "password123").await?;
+
+    let path = std::env::temp_dir().join(format!("memory_memo_backup_{}.db", uuid::Uuid::new_v4()));
+    let path_str = path.to_str().unwrap();
+    run(&pool, &["backup", "--output", path_str]).await?;
+
+    // The snapshot is a complete database
+    let backup = memory_memo::database::init_database(&DatabaseConfig::with_url(format!("sqlite://{}", path_str))).await?;
+    assert!(User::find_by_username(&backup, "carol").await?.is_some());
+    backup.close().await;
+
+    // Existing files are never overwritten
+    assert!(run(&pool, &["backup", "--output", path_str]).await.is_err());
+    assert!(run(&pool, &["backup", "verify", path_str]).await?.contains("is intact"));
+    std::fs::remove_file(&path)?;
+
+    assert!(run(&pool, &["migrate"]).await?.contains("migration version"));
+    pool.close().await;
+    std::fs::remove_file(&source)?;
+    Ok(())
+}
@@ -0,0 +1,302 @@
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::{create_test_database, Database};
+use memory_memo::models::User;
+use serde_json::{json, Value};
+use tower_cookies::cookie::Cookie;
+use anyhow::Result;
+
+async fn create_test_server() -> Result<(TestServer, Database)> {
+    let pool = create_test_database().await?;
+    let app = create_app(pool.clone(), Config::default()).await?;
+    Ok((TestServer::new(app)?, pool))
+}
+
+/// Register and log in a user, returning the session cookie and CSRF token
+async fn login(server: &TestServer, username: &str, password: &str) -> Result<(Cookie<'static>, String)> {
+    server
+        .post("/api/auth/register")
+        .json(&json!({"username": username, "password": password}))
+        .await;
+
+    let response = server
+        .post("/api/auth/login")
+        .json(&json!({"username": username, "password": password}))
+        .await;
+
+    let session = response.cookies().get("session_id").unwrap().clone().into_owned();
+    let csrf_token = response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    Ok((session, csrf_token))
+}
+
+fn csrf_header(token: &str) -> Result<(HeaderName, HeaderValue)> {
+    Ok((HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(token)?))
+}
+
+async fn create_admin(server: &TestServer, pool: &Database) -> Result<(Cookie<'static>, String)> {
+    server
+        .post("/api/auth/register")
+        .json(&json!({"username": "admin", "password": "adminpass123"}))
+        .await;
+    let admin = User::find_by_username(pool, "admin").await?.unwrap();
+    User::set_admin(pool, &admin.id, true).await?;
+    login(server, "admin", "adminpass123").await
+}
+
+#[tokio::test]
+async fn test_admin_routes_require_admin_role() -> Result<()> {
+    let (server, _pool) = create_test_server().await?;
+
+    // Contract: Anonymous requests are rejected with 401
+    server.get("/api/admin/users").await.assert_status(StatusCode::UNAUTHORIZED);
+
+    // Contract: Regular users are rejected with 403
+    let (session, _) = login(&server, "regular", "password123").await?;
+    server
+        .get("/api/admin/users")
+        .add_cookie(session)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_list_users_contract() -> Result<()> {
+    let (server, pool) = create_test_server().await?;
+    let (admin_session, _) = create_admin(&server, &pool).await?;
+    login(&server, "regular", "password123").await?;
+
+    let response = server
+        .get("/api/admin/users")
+        .add_cookie(admin_session)
+        .await;
+
+    // Contract: Should return all users with memo counts and no password hashes
+    response.assert_status_ok();
+    let body: Value = response.json();
+    let users = body["users"].as_array().unwrap();
+    assert_eq!(users.len(), 2);
+    for user in users {
+        assert!(user.get("forever_memo_count").is_some());
+        assert!(user.get("flush_memo_count").is_some());
+        assert!(user.get("password_hash").is_none());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_disable_user_revokes_sessions() -> Result<()> {
+    let (server, pool) = create_test_server().await?;
+    let (admin_session, admin_csrf) = create_admin(&server, &pool).await?;
+    let (user_session, _) = login(&server, "regular", "password123").await?;
+    let user = User::find_by_username(&pool, "regular").await?.unwrap();
+    let (name, value) = csrf_header(&admin_csrf)?;
+
+    let response = server
+        .post(&format!("/api/admin/users/{}/disable", user.id))
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .await;
+    response.assert_status_ok();
+
+    // Contract: Existing sessions stop working and new logins are refused
+    server
+        .get("/api/auth/me")
+        .add_cookie(user_session)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .post("/api/auth/login")
+        .json(&json!({"username": "regular", "password": "password123"}))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    // Contract: Re-enabling restores access
+    server
+        .post(&format!("/api/admin/users/{}/enable", user.id))
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .await
+        .assert_status_ok();
+    server
+        .post("/api/auth/login")
+        .json(&json!({"username": "regular", "password": "password123"}))
+        .await
+        .assert_status_ok();
+
+    // Contract: Admins cannot lock themselves out
+    let admin = User::find_by_username(&pool, "admin").await?.unwrap();
+    server
+        .post(&format!("/api/admin/users/{}/disable", admin.id))
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Contract: Unknown users return 404
+    server
+        .post("/api/admin/users/missing/disable")
+        .add_cookie(admin_session)
+        .add_header(name, value)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_reset_password_contract() -> Result<()> {
+    let (server, pool) = create_test_server().await?;
+    let (admin_session, admin_csrf) = create_admin(&server, &pool).await?;
+    login(&server, "regular", "password123").await?;
+    let user = User::find_by_username(&pool, "regular").await?.unwrap();
+    let (name, value) = csrf_header(&admin_csrf)?;
+
+    let response = server
+        .post(&format!("/api/admin/users/{}/reset-password", user.id))
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .json(&json!({"password": "resetpass456"}))
+        .await;
+    response.assert_status_ok();
+
+    server
+        .post("/api/auth/login")
+        .json(&json!({"username": "regular", "password": "resetpass456"}))
+        .await
+        .assert_status_ok();
+
+    // Contract: Short passwords are rejected
+    server
+        .post(&format!("/api/admin/users/{}/reset-password", user.id))
+        .add_cookie(admin_session)
+        .add_header(name, value)
+        .json(&json!({"password": "short"}))
+        .await
+        .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_cleanup_endpoints_contract() -> Result<()> {
+    let (server, pool) = create_test_server().await?;
+    let (admin_session, admin_csrf) = create_admin(&server, &pool).await?;
+    let (name, value) = csrf_header(&admin_csrf)?;
+
+    let response = server
+        .post("/api/admin/cleanup/run")
+        .add_cookie(admin_session.clone())
+        .add_header(name, value)
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["cleaned_count"], 0);
+
+    let response = server
+        .get("/api/admin/cleanup/stats")
+        .add_cookie(admin_session.clone())
+        .await;
+    response.assert_status_ok();
+    let stats: Value = response.json();
+    assert!(stats.get("total_count").is_some());
+    assert!(stats.get("last_cleanup").is_some());
+
+    let response = server
+        .get("/api/admin/cleanup/history")
+        .add_query_param("limit", 5)
+        .add_cookie(admin_session)
+        .await;
+    response.assert_status_ok();
+    let history: Value = response.json();
+    assert_eq!(history["runs"].as_array().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_time_offset_contract() -> Result<()> {
+    // Contract: Without debug.time_offset the endpoint does not exist
+    let (server, pool) = create_test_server().await?;
+    let (admin_session, _) = create_admin(&server, &pool).await?;
+    server
+        .get("/api/admin/debug/clock")
+        .add_cookie(admin_session)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let pool = create_test_database().await?;
+    let mut config = Config::default();
+    config.debug.time_offset = true;
+    let server = TestServer::new(create_app(pool.clone(), config).await?)?;
+    let (admin_session, admin_csrf) = create_admin(&server, &pool).await?;
+    let (user_session, user_csrf) = login(&server, "regular", "password123").await?;
+
+    let (name, value) = csrf_header(&user_csrf)?;
+    server
+        .post("/api/memos/flush")
+        .add_cookie(user_session.clone())
+        .add_header(name, value)
+        .json(&json!({"content": "Gone tomorrow"}))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let response = server
+        .get("/api/admin/debug/clock")
+        .add_cookie(admin_session.clone())
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["offset_seconds"], 0);
+
+    // Contract: Shifting the clock past the TTL expires flush memos
+    let (name, value) = csrf_header(&admin_csrf)?;
+    let response = server
+        .put("/api/admin/debug/clock")
+        .add_cookie(admin_session.clone())
+        .add_header(name, value)
+        .json(&json!({"offset_seconds": 25 * 60 * 60}))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["offset_seconds"], 25 * 60 * 60);
+
+    // Contract: Offsets chrono cannot represent are a validation error
+    let (name, value) = csrf_header(&admin_csrf)?;
+    let response = server
+        .put("/api/admin/debug/clock")
+        .add_cookie(admin_session.clone())
+        .add_header(name, value)
+        .json(&json!({"offset_seconds": i64::MAX}))
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.json::<Value>()["fields"][0]["code"], "out_of_range");
+
+    // Contract: So are offsets chrono can hold but that would push the clock
+    // past the dates it can represent, and anything beyond ten years
+    for offset_seconds in [10_i64.pow(13), -(10_i64.pow(13)), 3651 * 24 * 60 * 60] {
+        let (name, value) = csrf_header(&admin_csrf)?;
+        let response = server
+            .put("/api/admin/debug/clock")
+            .add_cookie(admin_session.clone())
+            .add_header(name, value)
+            .json(&json!({"offset_seconds": offset_seconds}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.json::<Value>()["fields"][0]["code"], "out_of_range");
+    }
+
+    // Sessions keep real time, so the user is still logged in
+    let response = server.get("/api/memos").add_cookie(user_session).await;
+    response.assert_status_ok();
+    assert!(response.json::<Value>()["flush_memos"].as_array().unwrap().is_empty());
+
+    let response = server
+        .get("/api/admin/cleanup/stats")
+        .add_cookie(admin_session)
+        .await;
+    assert_eq!(response.json::<Value>()["expired_count"], 1);
+
+    Ok(())
+}
@@ -1,14 +1,16 @@
 use axum_test::TestServer;
-use axum::http::StatusCode;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
 use memory_memo::create_app;
+use memory_memo::config::Config;
 use memory_memo::database::create_test_database;
 use serde_json::{json, Value};
+use tower_cookies::cookie::{Cookie, SameSite};
 use anyhow::Result;
 
 async fn create_test_server() -> Result<TestServer> {
     let pool = create_test_database().await?;
-    let app = create_app(pool).await?;
-    Ok(TestServer::new(app)?)
+    let app = create_app(pool, Config::default()).await?;
+    TestServer::new(app)
 }
 
 #[tokio::test]
@@ -100,7 +102,7 @@ async fn test_auth_register_contract_validation() -> Result<()> {
             "password": "password123"
         }))
         .await;
-    response.assert_status(StatusCode::BAD_REQUEST);
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
     
     // Test short password (less than 8 chars)
     let response = server
@@ -110,7 +112,7 @@ async fn test_auth_register_contract_validation() -> Result<()> {
             "password": "1234567"
         }))
         .await;
-    response.assert_status(StatusCode::BAD_REQUEST);
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
     
     Ok(())
 }
@@ -152,6 +154,62 @@ async fn test_auth_login_contract_success() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_auth_login_contract_cookie_attributes() -> Result<()> {
+    let server = create_test_server().await?;
+
+    server
+        .post("/api/auth/register")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await;
+
+    let response = server
+        .post("/api/auth/login")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await;
+
+    let cookies = response.cookies();
+
+    // Contract: Session cookie is HttpOnly, SameSite and a browser-session cookie
+    let session_cookie = cookies.get("session_id").unwrap();
+    assert_eq!(session_cookie.http_only(), Some(true));
+    assert_eq!(session_cookie.same_site(), Some(SameSite::Lax));
+    assert!(session_cookie.max_age().is_none());
+    assert!(session_cookie.expires().is_none());
+
+    // Contract: Session cookie value is sealed, not the raw session ID
+    assert!(uuid::Uuid::parse_str(session_cookie.value()).is_err());
+
+    // Contract: CSRF cookie is readable by scripts and matches the response body
+    let body: Value = response.json();
+    let csrf_cookie = cookies.get("csrf_token").unwrap();
+    assert_ne!(csrf_cookie.http_only(), Some(true));
+    assert_eq!(csrf_cookie.value(), body["csrf_token"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auth_forged_session_cookie_rejected() -> Result<()> {
+    let server = create_test_server().await?;
+
+    let response = server
+        .get("/api/auth/me")
+        .add_cookie(Cookie::new("session_id", uuid::Uuid::new_v4().to_string()))
+        .await;
+
+    // Contract: Unsigned cookie values are not accepted as sessions
+    response.assert_status(StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_auth_login_contract_invalid_credentials() -> Result<()> {
     let server = create_test_server().await?;
@@ -220,10 +278,16 @@ async fn test_auth_logout_contract_success() -> Result<()> {
         .unwrap()
         .clone();
     
+    let csrf_token = login_response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+
     // Test logout
     let response = server
         .post("/api/auth/logout")
         .add_cookie(session_cookie)
+        .add_header(
+            HeaderName::from_static("x-csrf-token"),
+            HeaderValue::from_str(&csrf_token)?,
+        )
         .await;
     
     // Contract: Should return 200 OK
@@ -268,4 +332,97 @@ async fn test_auth_endpoints_content_type() -> Result<()> {
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_auth_login_contract_remember_me() -> Result<()> {
+    let server = create_test_server().await?;
+
+    server
+        .post("/api/auth/register")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await;
+
+    let response = server
+        .post("/api/auth/login")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await;
+    // Contract: without remember_me the cookies end with the browser session
+    for name in ["session_id", "csrf_token"] {
+        let cookie = response.cookies().get(name).unwrap().clone();
+        assert!(cookie.max_age().is_none() && cookie.expires().is_none(), "{name}");
+    }
+
+    let response = server
+        .post("/api/auth/login")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123",
+            "remember_me": true
+        }))
+        .await;
+
+    // Contract: remember_me issues persistent cookies lasting the remember-me period
+    response.assert_status(StatusCode::OK);
+    for name in ["session_id", "csrf_token"] {
+        let max_age = response.cookies().get(name).unwrap().max_age().unwrap();
+        assert_eq!(max_age.whole_days(), 30, "{name}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auth_login_rotates_existing_session() -> Result<()> {
+    let server = create_test_server().await?;
+
+    server
+        .post("/api/auth/register")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await;
+
+    let first_login = server
+        .post("/api/auth/login")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await;
+    let first_session = first_login.cookies().get("session_id").unwrap().clone();
+
+    // Logging in again while presenting the old session replaces it
+    let second_login = server
+        .post("/api/auth/login")
+        .add_cookie(first_session.clone())
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await;
+    let second_session = second_login.cookies().get("session_id").unwrap().clone();
+    assert_ne!(first_session.value(), second_session.value());
+
+    // Contract: the previous session no longer authenticates
+    server
+        .get("/api/auth/me")
+        .add_cookie(first_session)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    server
+        .get("/api/auth/me")
+        .add_cookie(second_session)
+        .await
+        .assert_status_ok();
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use axum_test::TestServer;
-use axum::http::StatusCode;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
 use memory_memo::create_app;
+use memory_memo::config::Config;
 use memory_memo::database::create_test_database;
 use serde_json::{json, Value};
 use tower_cookies::cookie::Cookie;
@@ -8,11 +9,13 @@ use anyhow::Result;
 
 async fn create_test_server() -> Result<TestServer> {
     let pool = create_test_database().await?;
-    let app = create_app(pool).await?;
-    Ok(TestServer::new(app)?)
+    let app = create_app(pool, Config::default()).await?;
+    TestServer::new(app)
 }
 
-async fn create_authenticated_user(server: &TestServer) -> Result<Cookie<'static>> {
+/// Log in a test user, returning the session cookie and sending the CSRF
+/// token on every subsequent request made through `server`
+async fn create_authenticated_user(server: &mut TestServer) -> Result<Cookie<'static>> {
     // Register user
     server
         .post("/api/auth/register")
@@ -37,6 +40,12 @@ async fn create_authenticated_user(server: &TestServer) -> Result<Cookie<'static
         .find(|c| c.name() == "session_id")
         .unwrap();
 
+    let csrf_token = login_response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    server.add_header(
+        HeaderName::from_static("x-csrf-token"),
+        HeaderValue::from_str(&csrf_token)?,
+    );
+
     // Convert to cookie::Cookie with owned value
     let cookie_value = session_cookie.value().to_owned();
     let cookie = Cookie::new("session_id", cookie_value);
@@ -45,8 +54,8 @@ async fn create_authenticated_user(server: &TestServer) -> Result<Cookie<'static
 
 #[tokio::test]
 async fn test_memo_create_forever_memo_success() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     let response = server
         .post("/api/memos/forever")
@@ -72,8 +81,8 @@ async fn test_memo_create_forever_memo_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_create_flush_memo_success() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     let response = server
         .post("/api/memos/flush")
@@ -128,8 +137,8 @@ async fn test_memo_create_unauthorized() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_create_validation() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     // Test empty content
     let response = server
@@ -140,7 +149,7 @@ async fn test_memo_create_validation() -> Result<()> {
         }))
         .await;
 
-    response.assert_status(StatusCode::BAD_REQUEST);
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
 
     // Test missing content field
     let response = server
@@ -156,8 +165,8 @@ async fn test_memo_create_validation() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_list_success() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     // Create some memos first
     server
@@ -218,8 +227,8 @@ async fn test_memo_list_unauthorized() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_update_forever_memo_success() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     // Create a memo first
     let create_response = server
@@ -255,8 +264,8 @@ async fn test_memo_update_forever_memo_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_update_unauthorized() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     // Create a memo first
     let create_response = server
@@ -286,8 +295,8 @@ async fn test_memo_update_unauthorized() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_update_not_found() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     let response = server
         .put("/api/memos/forever/nonexistent-id")
@@ -305,8 +314,8 @@ async fn test_memo_update_not_found() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_delete_success() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     // Create a memo first
     let create_response = server
@@ -334,8 +343,8 @@ async fn test_memo_delete_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_delete_unauthorized() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     // Create a memo first
     let create_response = server
@@ -362,8 +371,8 @@ async fn test_memo_delete_unauthorized() -> Result<()> {
 
 #[tokio::test]
 async fn test_memo_endpoints_content_type() -> Result<()> {
-    let server = create_test_server().await?;
-    let session = create_authenticated_user(&server).await?;
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
 
     // Test that all memo endpoints return JSON
     let endpoints_and_payloads = [
@@ -391,4 +400,52 @@ async fn test_memo_endpoints_content_type() -> Result<()> {
     assert!(response.headers().get("content-type").unwrap().to_str().unwrap().contains("application/json"));
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_memo_mutations_require_csrf_token() -> Result<()> {
+    let mut server = create_test_server().await?;
+    let session = create_authenticated_user(&mut server).await?;
+
+    let create_response = server
+        .post("/api/memos/forever")
+        .add_cookie(session.clone())
+        .json(&json!({"content": "Protected memo"}))
+        .await;
+    let memo: Value = create_response.json();
+    let memo_id = memo["id"].as_str().unwrap();
+
+    // Keep the session but stop sending the CSRF header
+    server.clear_headers();
+
+    // Contract: Missing CSRF token is rejected with 403
+    let response = server
+        .post("/api/memos/flush")
+        .add_cookie(session.clone())
+        .json(&json!({"content": "Cross-site memo"}))
+        .await;
+    response.assert_status(StatusCode::FORBIDDEN);
+
+    // Contract: Wrong CSRF token is rejected with 403
+    let response = server
+        .delete(&format!("/api/memos/forever/{}", memo_id))
+        .add_cookie(session.clone())
+        .add_header(
+            HeaderName::from_static("x-csrf-token"),
+            HeaderValue::from_static("not-the-token"),
+        )
+        .await;
+    response.assert_status(StatusCode::FORBIDDEN);
+
+    // Contract: Safe methods do not need the token
+    let response = server
+        .get("/api/memos")
+        .add_cookie(session.clone())
+        .await;
+    response.assert_status_ok();
+    let memos: Value = response.json();
+    assert_eq!(memos["forever_memos"].as_array().unwrap().len(), 1);
+    assert_eq!(memos["flush_memos"].as_array().unwrap().len(), 0);
+
+    Ok(())
+}
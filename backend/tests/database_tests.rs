@@ -0,0 +1,65 @@
+use std::sync::Arc;
+use memory_memo::config::DatabaseConfig;
+use memory_memo::database::{close_database, init_database, Database};
+use memory_memo::models::{FlushMemo, ForeverMemo, User};
+use memory_memo::storage::{Backend, SqlPool, SqliteStorage};
+use anyhow::Result;
+use chrono::Utc;
+
+#[tokio::test]
+async fn test_file_database_is_tuned() -> Result<()> {
+    // The parent directory does not exist yet
+    let dir = std::env::temp_dir().join(format!("memory_memo_{}", uuid::Uuid::new_v4()));
+    let mut config = DatabaseConfig::with_url(format!("sqlite://{}", dir.join("data/memo.db").display()));
+    config.max_connections = 3;
+    let db = init_database(&config).await?;
+    assert_eq!(db.backend(), "sqlite");
+    let Some(SqlPool::Sqlite(pool)) = db.sql_pool() else { unreachable!() };
+
+    let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(pool).await?;
+    assert_eq!(journal_mode, "wal");
+    let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(pool).await?;
+    assert_eq!(foreign_keys, 1);
+    let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(pool).await?;
+    assert_eq!(busy_timeout, 5000);
+    // 1 = NORMAL
+    let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(pool).await?;
+    assert_eq!(synchronous, 1);
+    assert_eq!(pool.options().get_max_connections(), 3);
+
+    close_database(&db).await;
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deleting_a_user_cascades_to_memos() -> Result<()> {
+    let storage = SqliteStorage::in_memory().await?;
+    let sqlite = storage.pool().clone();
+    let pool: Database = Arc::new(storage);
+    let user = User::create(&pool, "alice", "password123").await?;
+    ForeverMemo::create(&pool, &user.id, "Kept forever").await?;
+    FlushMemo::create(&pool, &user.id, "Gone soon", Utc::now()).await?;
+
+    sqlx::query("DELETE FROM users WHERE id = ?").bind(&user.id).execute(&sqlite).await?;
+
+    let memos: i64 = sqlx::query_scalar(
+        "SELECT (SELECT COUNT(*) FROM forever_memos) + (SELECT COUNT(*) FROM flush_memos)",
+    )
+    .fetch_one(&sqlite)
+    .await?;
+    assert_eq!(memos, 0);
+
+    // Memos cannot point at a user that does not exist
+    assert!(ForeverMemo::create(&pool, "no-such-user", "Orphan").await.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_backend_is_chosen_by_url_scheme() {
+    assert_eq!(Backend::from_url("sqlite://memory_memo.db").unwrap(), Backend::Sqlite);
+    assert_eq!(Backend::from_url("sqlite::memory:").unwrap(), Backend::Sqlite);
+    assert_eq!(Backend::from_url("postgres://memo@localhost/memory_memo").unwrap(), Backend::Postgres);
+    assert_eq!(Backend::from_url("postgresql://memo@localhost/memory_memo").unwrap(), Backend::Postgres);
+    assert!(Backend::from_url("mysql://localhost/memory_memo").is_err());
+}
@@ -1,14 +1,14 @@
 use axum_test::TestServer;
-use axum::http::StatusCode;
-use memory_memo::{create_app, database::create_test_database};
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use memory_memo::{config::Config, create_app, database::create_test_database};
 use serde_json::{json, Value};
 
 #[tokio::test]
 async fn test_complete_user_workflow() {
     // Setup test server
     let pool = create_test_database().await.unwrap();
-    let app = create_app(pool).await.unwrap();
-    let server = TestServer::new(app).unwrap();
+    let app = create_app(pool, Config::default()).await.unwrap();
+    let mut server = TestServer::new(app).unwrap();
 
     // Test 1: User Registration
     let register_response = server
@@ -38,6 +38,11 @@ async fn test_complete_user_workflow() {
         .find(|c| c.name() == "session_id")
         .unwrap()
         .clone();
+    let csrf_token = login_response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    server.add_header(
+        HeaderName::from_static("x-csrf-token"),
+        HeaderValue::from_str(&csrf_token).unwrap(),
+    );
     println!("✅ User login successful");
 
     // Test 3: Create Forever Memo
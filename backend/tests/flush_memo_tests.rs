@@ -1,8 +1,10 @@
+use std::sync::Arc;
+use memory_memo::clock::{Clock, ManualClock, OffsetClock};
 use memory_memo::models::{User, FlushMemo};
 use memory_memo::database::create_test_database;
+use memory_memo::services::MemoService;
 use anyhow::Result;
 use chrono::{Duration, Utc};
-use sqlx::Row;
 
 #[tokio::test]
 async fn test_flush_memo_creation() -> Result<()> {
@@ -12,7 +14,7 @@ async fn test_flush_memo_creation() -> Result<()> {
     let user = User::create(&pool, "testuser", "password123").await?;
     
     // Test flush memo creation
-    let memo = FlushMemo::create(&pool, &user.id, "This is a test flush memo").await?;
+    let memo = FlushMemo::create(&pool, &user.id, "This is a test flush memo", Utc::now()).await?;
     assert_eq!(memo.user_id, user.id);
     assert_eq!(memo.content, "This is a test flush memo");
     assert!(!memo.id.is_empty());
@@ -40,16 +42,16 @@ async fn test_flush_memo_list_by_user() -> Result<()> {
     let user2 = User::create(&pool, "user2", "password2").await?;
     
     // Create memos for both users
-    FlushMemo::create(&pool, &user1.id, "User1 flush memo 1").await?;
-    FlushMemo::create(&pool, &user2.id, "User2 flush memo 1").await?;
-    FlushMemo::create(&pool, &user1.id, "User1 flush memo 2").await?;
+    FlushMemo::create(&pool, &user1.id, "User1 flush memo 1", Utc::now()).await?;
+    FlushMemo::create(&pool, &user2.id, "User2 flush memo 1", Utc::now()).await?;
+    FlushMemo::create(&pool, &user1.id, "User1 flush memo 2", Utc::now()).await?;
     
     // Get memos for user1
-    let user1_memos = FlushMemo::list_by_user(&pool, &user1.id).await?;
+    let user1_memos = FlushMemo::list_by_user(&pool, &user1.id, Utc::now()).await?;
     assert_eq!(user1_memos.len(), 2);
     
     // Get memos for user2
-    let user2_memos = FlushMemo::list_by_user(&pool, &user2.id).await?;
+    let user2_memos = FlushMemo::list_by_user(&pool, &user2.id, Utc::now()).await?;
     assert_eq!(user2_memos.len(), 1);
     
     // Verify content
@@ -66,14 +68,14 @@ async fn test_flush_memo_ordering() -> Result<()> {
     
     let user = User::create(&pool, "testuser", "password123").await?;
     
-    // Create memos in sequence
-    let _memo1 = FlushMemo::create(&pool, &user.id, "First flush memo").await?;
-    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await; // Ensure different timestamps
-    let _memo2 = FlushMemo::create(&pool, &user.id, "Second flush memo").await?;
-    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-    let _memo3 = FlushMemo::create(&pool, &user.id, "Third flush memo").await?;
+    // Create memos in sequence, a second apart on the clock
+    let clock = ManualClock::starting_now();
+    for content in ["First flush memo", "Second flush memo", "Third flush memo"] {
+        FlushMemo::create(&pool, &user.id, content, clock.now()).await?;
+        clock.advance(Duration::seconds(1));
+    }
     
-    let memos = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let memos = FlushMemo::list_by_user(&pool, &user.id, clock.now()).await?;
     
     // Should be ordered by newest first (created_at DESC)
     assert_eq!(memos.len(), 3);
@@ -95,17 +97,17 @@ async fn test_flush_memo_delete() -> Result<()> {
     let pool = create_test_database().await?;
     
     let user = User::create(&pool, "testuser", "password123").await?;
-    let memo = FlushMemo::create(&pool, &user.id, "Flush memo to delete").await?;
+    let memo = FlushMemo::create(&pool, &user.id, "Flush memo to delete", Utc::now()).await?;
     
     // Verify memo exists
-    let memos_before = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let memos_before = FlushMemo::list_by_user(&pool, &user.id, Utc::now()).await?;
     assert_eq!(memos_before.len(), 1);
     
     // Delete memo
     FlushMemo::delete(&pool, &memo.id, &user.id).await?;
     
     // Verify memo is deleted
-    let memos_after = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let memos_after = FlushMemo::list_by_user(&pool, &user.id, Utc::now()).await?;
     assert_eq!(memos_after.len(), 0);
     
     Ok(())
@@ -120,14 +122,14 @@ async fn test_flush_memo_delete_security() -> Result<()> {
     let user2 = User::create(&pool, "user2", "password2").await?;
     
     // User1 creates a memo
-    let memo = FlushMemo::create(&pool, &user1.id, "User1's flush memo").await?;
+    let memo = FlushMemo::create(&pool, &user1.id, "User1's flush memo", Utc::now()).await?;
     
     // User2 tries to delete User1's memo - should fail
     let result = FlushMemo::delete(&pool, &memo.id, &user2.id).await;
     assert!(result.is_err());
     
     // Verify memo still exists
-    let memos = FlushMemo::list_by_user(&pool, &user1.id).await?;
+    let memos = FlushMemo::list_by_user(&pool, &user1.id, Utc::now()).await?;
     assert_eq!(memos.len(), 1);
     
     Ok(())
@@ -139,38 +141,26 @@ async fn test_flush_memo_cleanup_expired() -> Result<()> {
     
     let user = User::create(&pool, "testuser", "password123").await?;
     
-    // Create a flush memo
-    let memo = FlushMemo::create(&pool, &user.id, "Memo to expire").await?;
-    
-    // Manually set expiry to past (simulate expired memo)
-    let past_time = Utc::now() - Duration::hours(1);
-    sqlx::query("UPDATE flush_memos SET expires_at = ? WHERE id = ?")
-        .bind(past_time)
-        .bind(&memo.id)
-        .execute(&pool)
-        .await?;
+    // Create a flush memo that expired an hour ago
+    FlushMemo::create_with_ttl(&pool, &user.id, "Memo to expire", Duration::hours(-1), Utc::now()).await?;
     
     // Create another memo that hasn't expired
-    FlushMemo::create(&pool, &user.id, "Fresh memo").await?;
+    FlushMemo::create(&pool, &user.id, "Fresh memo", Utc::now()).await?;
     
     // Before cleanup - should have 2 memos total (but list_by_user only returns non-expired)
-    let all_memos_count = sqlx::query("SELECT COUNT(*) as count FROM flush_memos WHERE user_id = ?")
-        .bind(&user.id)
-        .fetch_one(&pool)
-        .await?;
-    let count: i64 = all_memos_count.get("count");
+    let (count, _) = pool.count_flush_memos(Utc::now()).await?;
     assert_eq!(count, 2, "Should have 2 total memos before cleanup");
     
     // list_by_user should only show 1 (the non-expired one)
-    let memos_before = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let memos_before = FlushMemo::list_by_user(&pool, &user.id, Utc::now()).await?;
     assert_eq!(memos_before.len(), 1, "list_by_user should only show non-expired memos");
     
     // Run cleanup
-    let cleaned_count = FlushMemo::cleanup_expired(&pool).await?;
+    let cleaned_count = FlushMemo::cleanup_expired(&pool, Utc::now()).await?;
     assert_eq!(cleaned_count, 1);
     
     // After cleanup - should have 1 memo (the fresh one)
-    let memos_after = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let memos_after = FlushMemo::list_by_user(&pool, &user.id, Utc::now()).await?;
     assert_eq!(memos_after.len(), 1);
     assert_eq!(memos_after[0].content, "Fresh memo");
     
@@ -184,19 +174,12 @@ async fn test_flush_memo_list_excludes_expired() -> Result<()> {
     let user = User::create(&pool, "testuser", "password123").await?;
     
     // Create two flush memos
-    let _memo1 = FlushMemo::create(&pool, &user.id, "Active memo").await?;
-    let memo2 = FlushMemo::create(&pool, &user.id, "Expired memo").await?;
-    
-    // Manually set one memo to be expired
-    let past_time = Utc::now() - Duration::hours(1);
-    sqlx::query("UPDATE flush_memos SET expires_at = ? WHERE id = ?")
-        .bind(past_time)
-        .bind(&memo2.id)
-        .execute(&pool)
-        .await?;
+    let _memo1 = FlushMemo::create(&pool, &user.id, "Active memo", Utc::now()).await?;
+    // One that expired an hour ago
+    let _memo2 = FlushMemo::create_with_ttl(&pool, &user.id, "Expired memo", Duration::hours(-1), Utc::now()).await?;
     
     // List should only return non-expired memos
-    let memos = FlushMemo::list_by_user(&pool, &user.id).await?;
+    let memos = FlushMemo::list_by_user(&pool, &user.id, Utc::now()).await?;
     assert_eq!(memos.len(), 1);
     assert_eq!(memos[0].content, "Active memo");
     
@@ -210,8 +193,44 @@ async fn test_flush_memo_empty_content() -> Result<()> {
     let user = User::create(&pool, "testuser", "password123").await?;
     
     // Try to create memo with empty content - should fail
-    let result = FlushMemo::create(&pool, &user.id, "").await;
+    let result = FlushMemo::create(&pool, &user.id, "", Utc::now()).await;
     assert!(result.is_err());
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_flush_memo_expires_on_the_clock() -> Result<()> {
+    let pool = create_test_database().await?;
+    let clock = Arc::new(ManualClock::starting_now());
+    let memo_service = MemoService::new(pool.clone()).with_clock(clock.clone());
+    let user = User::create(&pool, "testuser", "password123").await?;
+
+    let memo = memo_service.create_flush_memo(&user.id, "Short-lived").await?;
+    assert_eq!(memo.created_at, clock.now());
+    assert_eq!(memo.time_until_expiry(clock.now()), Some(Duration::hours(24)));
+
+    // Still there a second before the TTL runs out
+    clock.advance(Duration::hours(24) - Duration::seconds(1));
+    assert!(!memo.is_expired(clock.now()));
+    assert_eq!(memo_service.list_user_memos(&user.id).await?.flush_memos.len(), 1);
+    assert!(memo_service.get_flush_memo(&memo.id, &user.id).await?.is_some());
+
+    // Gone from listings once it expires, even before cleanup deletes it
+    clock.advance(Duration::seconds(2));
+    assert!(memo.is_expired(clock.now()));
+    assert_eq!(memo.time_until_expiry(clock.now()), None);
+    assert!(memo_service.list_user_memos(&user.id).await?.flush_memos.is_empty());
+    assert!(memo_service.get_flush_memo(&memo.id, &user.id).await?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_offset_clock_saturates_instead_of_overflowing() {
+    let clock = OffsetClock::new(Arc::new(ManualClock::starting_now()));
+    clock.set_offset(Duration::seconds(10_i64.pow(13)));
+    assert_eq!(clock.now(), chrono::DateTime::<Utc>::MAX_UTC);
+    clock.set_offset(Duration::seconds(-(10_i64.pow(13))));
+    assert_eq!(clock.now(), chrono::DateTime::<Utc>::MIN_UTC);
+}
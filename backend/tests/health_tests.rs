@@ -0,0 +1,98 @@
+use axum_test::TestServer;
+use axum::http::StatusCode;
+use chrono::Duration;
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use memory_memo::services::{CleanupService, HealthService};
+use memory_memo::services::health::CheckStatus;
+use memory_memo::storage::SqlPool;
+use serde_json::Value;
+use anyhow::Result;
+
+#[tokio::test]
+async fn test_health_endpoints() -> Result<()> {
+    let pool = create_test_database().await?;
+    let server = TestServer::new(create_app(pool, Config::default()).await?)?;
+
+    // The legacy endpoint still answers in plain text
+    let response = server.get("/health").await;
+    response.assert_status_ok();
+    assert_eq!(response.text(), "OK");
+
+    let response = server.get("/health/live").await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["status"], "ok");
+
+    let response = server.get("/health/ready").await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["status"], "ok");
+    for check in ["database", "migrations", "disk", "cleanup"] {
+        assert!(body[check]["status"].is_string(), "missing {} check", check);
+        assert!(body[check]["latency_ms"].is_number());
+    }
+    assert_eq!(body["database"]["status"], "ok");
+    assert_eq!(body["migrations"]["status"], "ok");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_readiness_checks() -> Result<()> {
+    let pool = create_test_database().await?;
+
+    // In-memory database has no disk to check; the job has not run yet
+    let health = HealthService::new(pool.clone(), "sqlite::memory:", 0, Some(Duration::hours(3)));
+    let report = health.readiness().await;
+    assert!(report.is_ready());
+    assert_eq!(report.disk.status, CheckStatus::Skipped);
+    assert_eq!(report.cleanup.status, CheckStatus::Warn);
+
+    CleanupService::new(pool.clone()).cleanup_expired_flush_memos().await?;
+    let report = health.readiness().await;
+    assert_eq!(report.cleanup.status, CheckStatus::Ok);
+
+    let disabled = HealthService::new(pool.clone(), "sqlite::memory:", 0, None);
+    assert_eq!(disabled.readiness().await.cleanup.status, CheckStatus::Skipped);
+
+    // No disk has this much space free
+    let url = format!("sqlite://{}", std::env::temp_dir().join("memory_memo.db").display());
+    let full = HealthService::new(pool.clone(), &url, u64::MAX, None);
+    let report = full.readiness().await;
+    assert_eq!(report.disk.status, CheckStatus::Fail);
+    assert!(!report.is_ready());
+
+    // A migration that has not been applied makes the instance unready
+    let forget_latest = "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)";
+    match pool.sql_pool().unwrap() {
+        SqlPool::Sqlite(pool) => sqlx::query(forget_latest).execute(pool).await.map(drop)?,
+        SqlPool::Postgres(pool) => sqlx::query(forget_latest).execute(pool).await.map(drop)?,
+    }
+    let report = health.readiness().await;
+    assert_eq!(report.migrations.status, CheckStatus::Fail);
+    assert!(!report.is_ready());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_readiness_returns_503_when_database_is_closed() -> Result<()> {
+    let pool = create_test_database().await?;
+    let server = TestServer::new(create_app(pool.clone(), Config::default()).await?)?;
+
+    pool.close().await;
+
+    let response = server.get("/health/ready").await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = response.json();
+    assert_eq!(body["status"], "fail");
+    assert_eq!(body["database"]["status"], "fail");
+    // The storage error is logged, not handed to whoever asks
+    assert_eq!(body["database"]["detail"], "check failed");
+
+    // Liveness does not depend on the database
+    server.get("/health/live").await.assert_status_ok();
+
+    Ok(())
+}
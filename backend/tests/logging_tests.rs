@@ -0,0 +1,146 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use memory_memo::create_app;
+use memory_memo::config::{Config, LogFormat};
+use memory_memo::database::create_test_database;
+use memory_memo::logging::fmt_layer;
+use serde_json::{json, Value};
+use tracing_subscriber::layer::SubscriberExt;
+use anyhow::Result;
+
+const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Log sink shared between the subscriber and the test
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+async fn create_test_server() -> Result<TestServer> {
+    let pool = create_test_database().await?;
+    TestServer::new(create_app(pool, Config::default()).await?)
+}
+
+#[tokio::test]
+async fn test_request_id_is_generated_and_echoed() -> Result<()> {
+    let server = create_test_server().await?;
+
+    let response = server.get("/health").await;
+    let id = response.headers()[&REQUEST_ID].to_str()?;
+    assert!(uuid::Uuid::parse_str(id).is_ok());
+
+    // An ID set by a proxy is kept
+    let response = server
+        .get("/health")
+        .add_header(REQUEST_ID, HeaderValue::from_static("edge-1234.abc_DEF"))
+        .await;
+    assert_eq!(response.headers()[&REQUEST_ID], "edge-1234.abc_DEF");
+
+    // Anything that does not look like an ID is replaced
+    let response = server
+        .get("/health")
+        .add_header(REQUEST_ID, HeaderValue::from_static("bad id; drop table"))
+        .await;
+    let id = response.headers()[&REQUEST_ID].to_str()?;
+    assert!(uuid::Uuid::parse_str(id).is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_json_logs_carry_request_context_without_secrets() -> Result<()> {
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::registry().with(fmt_layer(LogFormat::Json, captured.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let server = create_test_server().await?;
+    let credentials = json!({"username": "loguser", "password": "hunter2-secret-password"});
+    server.post("/api/auth/register").json(&credentials).await;
+    let login = server.post("/api/auth/login").json(&credentials).await;
+    let user_id = login.json::<Value>()["user_id"].as_str().unwrap().to_owned();
+    let csrf = login.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+
+    server
+        .post("/api/memos/forever")
+        .add_cookie(login.cookie("session_id"))
+        .add_header(HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(&csrf)?)
+        .add_header(REQUEST_ID, HeaderValue::from_static("memo-request-1"))
+        .json(&json!({"content": "my private diary entry"}))
+        .await;
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone())?;
+    assert!(!output.contains("hunter2-secret-password"));
+    assert!(!output.contains("my private diary entry"));
+
+    // Events inside MemoService carry the request and user of the enclosing spans
+    let lines: Vec<Value> = output
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let service_event = lines
+        .iter()
+        .find(|line| {
+            let spans = line["spans"].as_array();
+            spans.is_some_and(|spans| spans.iter().any(|span| span["name"] == "create_forever_memo"))
+        })
+        .expect("an event inside create_forever_memo");
+    let request_span = service_event["spans"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|span| span["name"] == "request")
+        .unwrap();
+    assert_eq!(request_span["request_id"], "memo-request-1");
+    assert_eq!(request_span["user_id"], user_id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_csp_reports_are_logged_briefly() -> Result<()> {
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::registry().with(fmt_layer(LogFormat::Json, captured.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    // One request full of made-up violations with long URLs
+    let long_url = format!("https://attacker.example/{}", "a".repeat(500));
+    let report = json!({"type": "csp-violation", "body": {
+        "documentURL": long_url,
+        "effectiveDirective": "script-src-elem",
+        "blockedURL": long_url,
+    }});
+    let reports = Value::Array(vec![report; 12]);
+    let server = create_test_server().await?;
+    server.post("/api/csp-report").json(&reports).await.assert_status(StatusCode::NO_CONTENT);
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone())?;
+    let violations: Vec<&str> = output
+        .lines()
+        .filter(|line| line.contains("Content-Security-Policy violation"))
+        .collect();
+    assert_eq!(violations.len(), 5);
+    assert!(violations.iter().all(|line| line.contains("\"level\":\"INFO\"")));
+    assert!(violations.iter().all(|line| !line.contains(&"a".repeat(200))));
+    assert!(output.contains("7 more violations in the report not logged"));
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use memory_memo::services::MemoService;
-use memory_memo::models::{User, ForeverMemo, FlushMemo};
+use memory_memo::models::User;
 use memory_memo::database::create_test_database;
 use anyhow::Result;
 
@@ -176,4 +176,16 @@ async fn test_memo_ordering() -> Result<()> {
     assert_eq!(user_memos.forever_memos[1].content, "First forever memo");
     
     Ok(())
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_memo_flush_ttl_is_configurable() -> Result<()> {
+    let pool = create_test_database().await?;
+    let memo_service = MemoService::with_flush_ttl(pool.clone(), chrono::Duration::hours(2));
+
+    let user = User::create(&pool, "testuser", "password123").await?;
+    let memo = memo_service.create_flush_memo(&user.id, "Short-lived").await?;
+
+    assert_eq!(memo.expires_at - memo.created_at, chrono::Duration::hours(2));
+
+    Ok(())
+}
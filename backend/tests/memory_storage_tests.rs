@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use memory_memo::clock::{Clock, ManualClock};
+use memory_memo::database::Database;
+use memory_memo::models::quota::{QuotaLimits, QuotaOverride, QuotaUsage};
+use memory_memo::models::User;
+use memory_memo::services::rate_limit::{RatePolicy, RouteClass};
+use memory_memo::services::{AuthService, CleanupService, MemoService, RateLimiter};
+use memory_memo::storage::{MemoryStorage, USERNAME_TAKEN};
+use anyhow::Result;
+use chrono::Duration;
+
+fn memory_database() -> Database {
+    Arc::new(MemoryStorage::new())
+}
+
+#[tokio::test]
+async fn test_delete_memo_falls_back_to_flush_memos() -> Result<()> {
+    let db = memory_database();
+    let memo_service = MemoService::new(db.clone());
+    let user = User::create(&db, "testuser", "password123").await?;
+
+    let forever = memo_service.create_forever_memo(&user.id, "Forever").await?;
+    let flush = memo_service.create_flush_memo(&user.id, "Flush").await?;
+
+    // A flush memo ID is not found among forever memos, so the flush memo goes
+    memo_service.delete_memo(&flush.id, &user.id).await?;
+    let memos = memo_service.list_user_memos(&user.id).await?;
+    assert!(memos.flush_memos.is_empty());
+    assert_eq!(memos.forever_memos.len(), 1);
+
+    memo_service.delete_memo(&forever.id, &user.id).await?;
+    assert_eq!(memo_service.count_user_memos(&user.id).await?, (0, 0));
+
+    // Nothing left to delete in either table
+    assert!(memo_service.delete_memo(&forever.id, &user.id).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_memo_is_scoped_to_owner() -> Result<()> {
+    let db = memory_database();
+    let memo_service = MemoService::new(db.clone());
+    let owner = User::create(&db, "owner", "password123").await?;
+    let other = User::create(&db, "other", "password123").await?;
+
+    let memo = memo_service.create_flush_memo(&owner.id, "Mine").await?;
+    assert!(memo_service.delete_memo(&memo.id, &other.id).await.is_err());
+    assert_eq!(memo_service.count_user_memos(&owner.id).await?, (0, 1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memos_require_an_existing_user() -> Result<()> {
+    let memo_service = MemoService::new(memory_database());
+
+    assert!(memo_service.create_forever_memo("missing", "Orphan").await.is_err());
+    assert!(memo_service.create_flush_memo("missing", "Orphan").await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cleanup_counts_and_removes_expired_memos() -> Result<()> {
+    let db = memory_database();
+    let clock = Arc::new(ManualClock::starting_now());
+    let memo_service = MemoService::new(db.clone()).with_clock(clock.clone());
+    let cleanup_service = CleanupService::new(db.clone()).with_clock(clock.clone());
+    let user = User::create(&db, "testuser", "password123").await?;
+
+    memo_service.create_flush_memo(&user.id, "Expires first").await?;
+    clock.advance(Duration::hours(12));
+    memo_service.create_flush_memo(&user.id, "Fresh").await?;
+    clock.advance(Duration::hours(13));
+
+    assert_eq!(cleanup_service.cleanup_expired_flush_memos_dry_run().await?, 1);
+    assert_eq!(cleanup_service.cleanup_expired_flush_memos().await?, 1);
+
+    let stats = cleanup_service.get_cleanup_statistics().await?;
+    assert_eq!((stats.total_count, stats.expired_count, stats.active_count), (1, 0, 1));
+    assert_eq!(stats.last_cleanup, Some(clock.now()));
+    assert_eq!(cleanup_service.get_cleanup_history(10).await?.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_rejects_taken_username() -> Result<()> {
+    let auth_service = AuthService::new(memory_database());
+
+    auth_service.register("testuser", "password123").await?;
+    let err = auth_service.register("testuser", "password456").await.unwrap_err();
+    assert_eq!(err.to_string(), USERNAME_TAKEN);
+
+    assert!(auth_service.login("testuser", "password123").await.is_ok());
+    assert!(auth_service.login("testuser", "password456").await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deleting_user_removes_their_memos() -> Result<()> {
+    let db = memory_database();
+    let memo_service = MemoService::new(db.clone());
+    let user = User::create(&db, "testuser", "password123").await?;
+    let other = User::create(&db, "other", "password123").await?;
+
+    memo_service.create_forever_memo(&user.id, "Forever").await?;
+    memo_service.create_flush_memo(&user.id, "Flush").await?;
+    memo_service.create_forever_memo(&other.id, "Kept").await?;
+
+    assert!(User::delete(&db, &user.id).await?);
+    assert_eq!(memo_service.count_user_memos(&user.id).await?, (0, 0));
+    assert_eq!(memo_service.count_user_memos(&other.id).await?, (1, 0));
+
+    let summaries = User::list_with_memo_counts(&db).await?;
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].forever_memo_count, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_quota_usage_and_overrides() -> Result<()> {
+    let db = memory_database();
+    let clock = Arc::new(ManualClock::starting_now());
+    let memo_service = MemoService::with_flush_ttl(db.clone(), Duration::hours(1))
+        .with_clock(clock.clone())
+        .with_quota(QuotaLimits { max_memos: Some(10), max_flush_memos: None, max_bytes: None });
+    let user = User::create(&db, "testuser", "password123").await?;
+
+    memo_service.create_forever_memo(&user.id, "メモ").await?;
+    memo_service.create_flush_memo(&user.id, "flush").await?;
+    let usage = memo_service.quota_status(&user.id).await?.usage;
+    assert_eq!(usage, QuotaUsage { memos: 2, flush_memos: 1, bytes: 11 });
+
+    // Expired flush memos stop counting before cleanup removes them
+    clock.advance(Duration::hours(2));
+    let usage = memo_service.quota_status(&user.id).await?.usage;
+    assert_eq!(usage, QuotaUsage { memos: 1, flush_memos: 0, bytes: 6 });
+
+    let quota = QuotaOverride { max_memos: Some(1), ..QuotaOverride::default() };
+    assert!(db.set_quota_override(&user.id, &quota).await?);
+    assert!(memo_service.create_forever_memo(&user.id, "over").await.is_err());
+    assert!(!db.set_quota_override("missing", &quota).await?);
+
+    assert!(User::delete(&db, &user.id).await?);
+    assert_eq!(db.find_quota_override(&user.id).await?, QuotaOverride::default());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limit_buckets_round_trip() -> Result<()> {
+    let db = memory_database();
+    let policy = RatePolicy { burst: 1, per_minute: 1 };
+    let limiter = RateLimiter::new(policy, policy);
+
+    limiter.check("ip:10.0.0.1", RouteClass::Read);
+    assert_eq!(limiter.save(&db).await?, 1);
+
+    let restarted = RateLimiter::new(policy, policy);
+    assert_eq!(restarted.load(&db).await?, 1);
+    assert!(!restarted.check("ip:10.0.0.1", RouteClass::Read).allowed);
+    assert_eq!(RateLimiter::new(policy, policy).load(&db).await?, 0);
+
+    Ok(())
+}
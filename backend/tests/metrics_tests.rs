@@ -0,0 +1,152 @@
+use axum_test::TestServer;
+use axum::http::{header, HeaderName, HeaderValue, StatusCode};
+use memory_memo::{create_app, metrics_router, AppState};
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+const TOKEN: &str = "scrape-token-0123456789";
+
+fn metrics_config() -> Config {
+    let mut config = Config::default();
+    config.metrics.enabled = true;
+    config.metrics.token = Some(TOKEN.to_string());
+    config
+}
+
+fn bearer(token: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!("Bearer {}", token)).unwrap()
+}
+
+/// Value of an unlabelled or fully labelled sample, e.g. `memory_memo_sessions_active`
+fn sample(body: &str, series: &str) -> Option<f64> {
+    body.lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' ')?.parse().ok())
+}
+
+#[tokio::test]
+async fn test_metrics_disabled_by_default() -> Result<()> {
+    let pool = create_test_database().await?;
+    let server = TestServer::new(create_app(pool, Config::default()).await?)?;
+
+    server.get("/metrics").await.assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metrics_require_token() -> Result<()> {
+    let pool = create_test_database().await?;
+    let server = TestServer::new(create_app(pool, metrics_config()).await?)?;
+
+    server.get("/metrics").await.assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .get("/metrics")
+        .add_header(header::AUTHORIZATION, bearer("scrape-token-wrong-value"))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    let response = server.get("/metrics").add_header(header::AUTHORIZATION, bearer(TOKEN)).await;
+    response.assert_status_ok();
+    assert!(response.headers()["content-type"].to_str()?.starts_with("text/plain"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metrics_record_activity() -> Result<()> {
+    let pool = create_test_database().await?;
+    let server = TestServer::new(create_app(pool, metrics_config()).await?)?;
+
+    let credentials = json!({"username": "metricsuser", "password": "password123"});
+    server.post("/api/auth/register").json(&credentials).await;
+    server
+        .post("/api/auth/login")
+        .json(&json!({"username": "metricsuser", "password": "wrong-password"}))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    let login = server.post("/api/auth/login").json(&credentials).await;
+    let session = login.cookie("session_id");
+    let csrf = login.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    let csrf_header = HeaderName::from_static("x-csrf-token");
+
+    let memo: Value = server
+        .post("/api/memos/forever")
+        .add_cookie(session.clone())
+        .add_header(csrf_header.clone(), HeaderValue::from_str(&csrf)?)
+        .json(&json!({"content": "Counted memo"}))
+        .await
+        .json();
+    server
+        .post("/api/memos/flush")
+        .add_cookie(session.clone())
+        .add_header(csrf_header.clone(), HeaderValue::from_str(&csrf)?)
+        .json(&json!({"content": "Counted flush memo"}))
+        .await;
+    server
+        .delete(&format!("/api/memos/forever/{}", memo["id"].as_str().unwrap()))
+        .add_cookie(session.clone())
+        .add_header(csrf_header.clone(), HeaderValue::from_str(&csrf)?)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    let body = server.get("/metrics").add_header(header::AUTHORIZATION, bearer(TOKEN)).await.text();
+
+    assert_eq!(sample(&body, r#"memory_memo_auth_logins_total{outcome="success"}"#), Some(1.0));
+    assert_eq!(sample(&body, r#"memory_memo_auth_logins_total{outcome="failure"}"#), Some(1.0));
+    assert_eq!(sample(&body, r#"memory_memo_memo_operations_total{memo_type="forever",operation="create"}"#), Some(1.0));
+    assert_eq!(sample(&body, r#"memory_memo_memo_operations_total{memo_type="flush",operation="create"}"#), Some(1.0));
+    assert_eq!(sample(&body, r#"memory_memo_memo_operations_total{memo_type="forever",operation="delete"}"#), Some(1.0));
+    assert_eq!(sample(&body, "memory_memo_sessions_active"), Some(1.0));
+
+    // Requests are labelled by route template, not by the concrete path
+    assert_eq!(
+        sample(&body, r#"memory_memo_http_requests_total{method="DELETE",route="/api/memos/forever/:memo_id",status="204"}"#),
+        Some(1.0)
+    );
+    assert_eq!(
+        sample(&body, r#"memory_memo_http_requests_total{method="POST",route="/api/auth/login",status="401"}"#),
+        Some(1.0)
+    );
+    assert!(body.contains("memory_memo_http_request_duration_seconds_bucket"));
+    assert!(body.contains("memory_memo_db_pool_connections"));
+    assert!(body.contains("memory_memo_db_query_duration_seconds"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metrics_count_cleanup_runs() -> Result<()> {
+    let pool = create_test_database().await?;
+    let state = AppState::new(pool.clone(), metrics_config())?;
+
+    state.cleanup_service().cleanup_expired_flush_memos().await?;
+    state.cleanup_service().cleanup_expired_flush_memos().await?;
+
+    let body = state.metrics.render(state.session_store.active_count(), &pool)?;
+    assert_eq!(sample(&body, "memory_memo_cleanup_flush_memos_removed_count"), Some(2.0));
+    assert_eq!(sample(&body, "memory_memo_cleanup_flush_memos_removed_sum"), Some(0.0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metrics_on_separate_listener() -> Result<()> {
+    let pool = create_test_database().await?;
+    let mut config = Config::default();
+    config.metrics.enabled = true;
+    config.metrics.bind = Some("127.0.0.1:9100".to_string());
+    let state = AppState::new(pool, config)?;
+
+    // Not exposed on the main listener
+    let app = TestServer::new(memory_memo::router(state.clone())?)?;
+    app.get("/metrics").await.assert_status(StatusCode::NOT_FOUND);
+
+    // No token configured, so the bind address is the only restriction
+    let metrics = TestServer::new(metrics_router(state))?;
+    metrics.get("/metrics").await.assert_status_ok();
+
+    Ok(())
+}
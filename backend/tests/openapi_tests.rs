@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+use axum_test::TestServer;
+use axum::http::StatusCode;
+use memory_memo::api::openapi::openapi;
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use serde_json::Value;
+use anyhow::Result;
+
+/// Set to rewrite the committed document after an intended API change
+const UPDATE_ENV: &str = "UPDATE_OPENAPI_SNAPSHOT";
+
+fn snapshot_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../specs/001-web-2-1/contracts/openapi.json")
+}
+
+async fn create_test_server(base_path: &str) -> Result<TestServer> {
+    let pool = create_test_database().await?;
+    let mut config = Config::default();
+    config.server.base_path = base_path.to_string();
+    let app = create_app(pool, config).await?;
+    TestServer::new(app)
+}
+
+fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                refs.push(reference);
+            }
+            map.values().for_each(|value| collect_refs(value, refs));
+        }
+        Value::Array(items) => items.iter().for_each(|value| collect_refs(value, refs)),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_openapi_document_matches_snapshot() -> Result<()> {
+    let generated = format!("{}\n", openapi().to_pretty_json()?);
+    let path = snapshot_path();
+
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        std::fs::write(&path, generated)?;
+        return Ok(());
+    }
+
+    let committed = std::fs::read_to_string(&path)?;
+    assert!(
+        committed == generated,
+        "The OpenAPI document no longer matches {}. If the API change is intended, \
+         rerun the tests with {}=1 and commit the result.",
+        path.display(),
+        UPDATE_ENV
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_openapi_references_resolve() -> Result<()> {
+    let document = serde_json::to_value(openapi())?;
+    let mut refs = Vec::new();
+    collect_refs(&document, &mut refs);
+    assert!(!refs.is_empty());
+
+    for reference in refs {
+        let pointer = reference.trim_start_matches('#');
+        assert!(document.pointer(pointer).is_some(), "{} is not defined", reference);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_openapi_documents_every_api() -> Result<()> {
+    let document = serde_json::to_value(openapi())?;
+    let paths = document["paths"].as_object().expect("paths");
+
+    for path in [
+        "/api/v1/auth/login",
+        "/api/v1/memos",
+        "/api/v1/memos/forever/{memo_id}",
+        "/api/v1/admin/users",
+        "/api/v1/admin/backups/{name}/restore",
+        "/health/ready",
+    ] {
+        assert!(paths.contains_key(path), "{} is not documented", path);
+    }
+
+    // Mutating memo routes name the CSRF header
+    let create = &document["paths"]["/api/v1/memos/forever"]["post"];
+    assert_eq!(create["security"][0]["csrf"], Value::Array(vec![]));
+    assert_eq!(document["components"]["securitySchemes"]["csrf"]["name"], "X-CSRF-Token");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_openapi_document_is_served() -> Result<()> {
+    let server = create_test_server("").await?;
+
+    let response = server.get("/api/openapi.json").await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>(), serde_json::to_value(openapi())?);
+
+    let response = server.get("/api/docs/").await;
+    response.assert_status_ok();
+    assert!(response.text().contains("swagger-ui"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_openapi_is_served_under_base_path() -> Result<()> {
+    let server = create_test_server("/memory_memo").await?;
+
+    let document = server.get("/memory_memo/api/openapi.json").await.json::<Value>();
+    assert_eq!(document["servers"][0]["url"], "/memory_memo");
+
+    // Swagger UI loads the document from inside the prefix
+    let response = server.get("/memory_memo/api/docs").await;
+    response.assert_status(StatusCode::SEE_OTHER);
+    assert_eq!(response.header("location"), "/memory_memo/api/docs/");
+    let initializer = server.get("/memory_memo/api/docs/swagger-initializer.js").await.text();
+    assert!(initializer.contains("/memory_memo/api/openapi.json"));
+
+    server.get("/api/openapi.json").await.assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use std::sync::Arc;
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use chrono::Duration;
+use memory_memo::clock::ManualClock;
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::{create_test_database, Database};
+use memory_memo::models::quota::{QuotaExceeded, QuotaLimits, QuotaOverride, QuotaUsage};
+use memory_memo::models::User;
+use memory_memo::services::MemoService;
+use serde_json::{json, Value};
+use tower_cookies::cookie::Cookie;
+use anyhow::Result;
+
+fn limits(max_memos: Option<u64>, max_flush_memos: Option<u64>, max_bytes: Option<u64>) -> QuotaLimits {
+    QuotaLimits { max_memos, max_flush_memos, max_bytes }
+}
+
+fn exceeded(result: Result<impl std::fmt::Debug>) -> &'static str {
+    result.unwrap_err().downcast_ref::<QuotaExceeded>().expect("a quota error").limit
+}
+
+#[tokio::test]
+async fn test_memo_count_limits() -> Result<()> {
+    let db = create_test_database().await?;
+    let user = User::create(&db, "testuser", "password123").await?;
+    let memo_service = MemoService::new(db.clone()).with_quota(limits(Some(3), Some(1), None));
+
+    memo_service.create_flush_memo(&user.id, "flush").await?;
+    assert_eq!(exceeded(memo_service.create_flush_memo(&user.id, "another flush").await), "max_flush_memos");
+
+    memo_service.create_forever_memo(&user.id, "first").await?;
+    let last = memo_service.create_forever_memo(&user.id, "second").await?;
+    assert_eq!(exceeded(memo_service.create_forever_memo(&user.id, "third").await), "max_memos");
+
+    // Deleting frees a slot
+    memo_service.delete_forever_memo(&last.id, &user.id).await?;
+    memo_service.create_forever_memo(&user.id, "third").await?;
+
+    // Other users have their own quota
+    let other = User::create(&db, "otheruser", "password123").await?;
+    memo_service.create_flush_memo(&other.id, "flush").await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_byte_limit_counts_utf8_bytes() -> Result<()> {
+    let db = create_test_database().await?;
+    let user = User::create(&db, "testuser", "password123").await?;
+    let memo_service = MemoService::new(db.clone()).with_quota(limits(None, None, Some(12)));
+
+    // Three characters, nine bytes
+    let memo = memo_service.create_forever_memo(&user.id, "メモだ").await?;
+    assert_eq!(memo_service.quota_status(&user.id).await?.usage.bytes, 9);
+    assert_eq!(exceeded(memo_service.create_flush_memo(&user.id, "あと2").await), "max_bytes");
+    memo_service.create_flush_memo(&user.id, "abc").await?;
+
+    // Growing an update is checked, shrinking always works
+    let grown = memo_service.update_forever_memo(&memo.id, &user.id, "メモです").await;
+    assert_eq!(exceeded(grown), "max_bytes");
+    memo_service.update_forever_memo(&memo.id, &user.id, "メ").await?.expect("updated");
+    assert_eq!(memo_service.quota_status(&user.id).await?.usage.bytes, 6);
+
+    // Updating a missing memo is still a plain miss
+    assert!(memo_service.update_forever_memo("missing", &user.id, "x").await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_flush_memos_do_not_count() -> Result<()> {
+    let db = create_test_database().await?;
+    let user = User::create(&db, "testuser", "password123").await?;
+    let clock = Arc::new(ManualClock::starting_now());
+    let memo_service = MemoService::with_flush_ttl(db.clone(), Duration::hours(1))
+        .with_clock(clock.clone())
+        .with_quota(limits(None, Some(1), None));
+
+    memo_service.create_flush_memo(&user.id, "expires soon").await?;
+    assert!(memo_service.create_flush_memo(&user.id, "too many").await.is_err());
+
+    clock.advance(Duration::hours(2));
+    let status = memo_service.quota_status(&user.id).await?;
+    assert_eq!(status.usage, QuotaUsage::default());
+    memo_service.create_flush_memo(&user.id, "room again").await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_override_replaces_configured_limits() -> Result<()> {
+    let db = create_test_database().await?;
+    let user = User::create(&db, "testuser", "password123").await?;
+    let memo_service = MemoService::new(db.clone()).with_quota(limits(Some(1), Some(1), Some(100)));
+
+    // 0 lifts a limit, unset fields keep the default
+    let quota = QuotaOverride { max_memos: Some(0), max_flush_memos: None, max_bytes: Some(5) };
+    assert!(db.set_quota_override(&user.id, &quota).await?);
+    assert_eq!(db.find_quota_override(&user.id).await?, quota);
+    assert_eq!(memo_service.quota_status(&user.id).await?.limits, limits(None, Some(1), Some(5)));
+
+    memo_service.create_forever_memo(&user.id, "one").await?;
+    memo_service.create_forever_memo(&user.id, "t").await?;
+    assert_eq!(exceeded(memo_service.create_forever_memo(&user.id, "three").await), "max_bytes");
+
+    // An empty override removes it
+    assert!(db.set_quota_override(&user.id, &QuotaOverride::default()).await?);
+    assert_eq!(db.find_quota_override(&user.id).await?, QuotaOverride::default());
+    assert!(!db.set_quota_override("missing", &quota).await?);
+
+    // The override goes with the user
+    db.set_quota_override(&user.id, &quota).await?;
+    assert!(User::delete(&db, &user.id).await?);
+    assert_eq!(db.find_quota_override(&user.id).await?, QuotaOverride::default());
+
+    Ok(())
+}
+
+async fn create_test_server(config: Config) -> Result<(TestServer, Database)> {
+    let pool = create_test_database().await?;
+    let app = create_app(pool.clone(), config).await?;
+    Ok((TestServer::new(app)?, pool))
+}
+
+/// Register and log in a user, returning the session cookie and CSRF header
+async fn login(server: &TestServer, username: &str) -> Result<(Cookie<'static>, HeaderName, HeaderValue)> {
+    let credentials = json!({"username": username, "password": "password123"});
+    server.post("/api/v1/auth/register").json(&credentials).await;
+    let response = server.post("/api/v1/auth/login").json(&credentials).await;
+
+    let session = response.cookies().get("session_id").unwrap().clone().into_owned();
+    let csrf_token = response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    Ok((session, HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(&csrf_token)?))
+}
+
+#[tokio::test]
+async fn test_quota_is_enforced_and_reported() -> Result<()> {
+    let mut config = Config::default();
+    config.quota.max_memos = 2;
+    config.quota.max_flush_memos = 0;
+    config.quota.max_bytes = 0;
+    let (server, _pool) = create_test_server(config).await?;
+    let (session, name, value) = login(&server, "testuser").await?;
+
+    for content in ["one", "two"] {
+        server
+            .post("/api/v1/memos/flush")
+            .add_cookie(session.clone())
+            .add_header(name.clone(), value.clone())
+            .json(&json!({"content": content}))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    let response = server
+        .post("/api/v1/memos/forever")
+        .add_cookie(session.clone())
+        .add_header(name.clone(), value.clone())
+        .json(&json!({"content": "three"}))
+        .await;
+    response.assert_status(StatusCode::CONFLICT);
+    let body = response.json::<Value>();
+    assert_eq!(body["limit"], "max_memos");
+    assert_eq!(body["error"], "Memo limit reached (2 memos)");
+
+    let me = server.get("/api/v1/auth/me").add_cookie(session).await.json::<Value>();
+    assert_eq!(me["username"], "testuser");
+    assert_eq!(me["quota"]["limits"], json!({"max_memos": 2, "max_flush_memos": null, "max_bytes": null}));
+    assert_eq!(me["quota"]["usage"], json!({"memos": 2, "flush_memos": 2, "bytes": 6}));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_overrides_a_users_quota() -> Result<()> {
+    let mut config = Config::default();
+    config.quota.max_memos = 1;
+    let (server, pool) = create_test_server(config).await?;
+
+    let (user_session, user_csrf_name, user_csrf) = login(&server, "regular").await?;
+    login(&server, "admin").await?;
+    let admin = User::find_by_username(&pool, "admin").await?.unwrap();
+    User::set_admin(&pool, &admin.id, true).await?;
+    let (admin_session, name, value) = login(&server, "admin").await?;
+    let user = User::find_by_username(&pool, "regular").await?.unwrap();
+    let quota_path = format!("/api/v1/admin/users/{}/quota", user.id);
+
+    // Regular users cannot see or change quotas
+    server
+        .get(&quota_path)
+        .add_cookie(user_session.clone())
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    let response = server.get(&quota_path).add_cookie(admin_session.clone()).await;
+    response.assert_status_ok();
+    let body = response.json::<Value>();
+    assert_eq!(body["override"], json!({"max_memos": null, "max_flush_memos": null, "max_bytes": null}));
+    assert_eq!(body["limits"]["max_memos"], 1);
+
+    let response = server
+        .put(&quota_path)
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .json(&json!({"max_memos": 3}))
+        .await;
+    response.assert_status_ok();
+    let body = response.json::<Value>();
+    assert_eq!(body["override"]["max_memos"], 3);
+    assert_eq!(body["limits"]["max_memos"], 3);
+    assert_eq!(body["limits"]["max_bytes"], 10 * 1024 * 1024);
+    assert_eq!(body["usage"]["memos"], 0);
+
+    for content in ["one", "two", "three"] {
+        server
+            .post("/api/v1/memos/forever")
+            .add_cookie(user_session.clone())
+            .add_header(user_csrf_name.clone(), user_csrf.clone())
+            .json(&json!({"content": content}))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    // The change is audited
+    let events = server
+        .get("/api/v1/admin/audit")
+        .add_query_param("event_type", "admin_quota_changed")
+        .add_cookie(admin_session.clone())
+        .await
+        .json::<Value>();
+    assert_eq!(events["events"][0]["user_id"], user.id.as_str());
+    assert_eq!(events["events"][0]["details"]["max_memos"], 3);
+
+    let response = server
+        .put(&quota_path)
+        .add_cookie(admin_session.clone())
+        .add_header(name.clone(), value.clone())
+        .json(&json!({"max_bytes": u64::MAX}))
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.json::<Value>()["fields"][0]["code"], "out_of_range");
+
+    server
+        .put("/api/v1/admin/users/missing/quota")
+        .add_cookie(admin_session.clone())
+        .add_header(name, value)
+        .json(&json!({}))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    server
+        .get("/api/v1/admin/users/missing/quota")
+        .add_cookie(admin_session)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[test]
+fn test_quota_check_only_refuses_growth() {
+    let quota = limits(Some(2), None, Some(10));
+    let full = QuotaUsage { memos: 5, flush_memos: 0, bytes: 50 };
+
+    // Over a lowered limit, changes that add nothing still pass
+    assert!(quota.check(full, QuotaUsage::default()).is_ok());
+    assert!(quota.check(full, QuotaUsage { memos: 1, ..QuotaUsage::default() }).is_err());
+    assert!(quota.check(QuotaUsage::default(), QuotaUsage { memos: 1, flush_memos: 1, bytes: 10 }).is_ok());
+    assert!(quota.check(QuotaUsage::default(), QuotaUsage { memos: 1, flush_memos: 1, bytes: 11 }).is_err());
+}
@@ -0,0 +1,250 @@
+use std::sync::Arc;
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use chrono::Duration;
+use memory_memo::clock::ManualClock;
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::{create_test_database, Database};
+use memory_memo::services::rate_limit::{RatePolicy, RouteClass, MAX_TRACKED_BUCKETS};
+use memory_memo::services::RateLimiter;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+fn policy(burst: u32, per_minute: u32) -> RatePolicy {
+    RatePolicy { burst, per_minute }
+}
+
+#[test]
+fn test_bucket_refills_over_time() {
+    let clock = Arc::new(ManualClock::starting_now());
+    let limiter = RateLimiter::new(policy(3, 60), policy(2, 6)).with_clock(clock.clone());
+
+    for remaining in [1, 0] {
+        let decision = limiter.check("ip:10.0.0.1", RouteClass::Write);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 2);
+        assert_eq!(decision.remaining, remaining);
+    }
+    let refused = limiter.check("ip:10.0.0.1", RouteClass::Write);
+    assert!(!refused.allowed);
+    assert_eq!(refused.retry_after_seconds, 10);
+    assert_eq!(refused.reset_seconds, 20);
+
+    // Reads, and other clients, have their own buckets
+    assert!(limiter.check("ip:10.0.0.1", RouteClass::Read).allowed);
+    assert!(limiter.check("ip:10.0.0.2", RouteClass::Write).allowed);
+
+    // Refused requests take nothing, so one token is back after 10 seconds
+    clock.advance(Duration::seconds(10));
+    assert!(limiter.check("ip:10.0.0.1", RouteClass::Write).allowed);
+    assert!(!limiter.check("ip:10.0.0.1", RouteClass::Write).allowed);
+
+    // Never more than the burst
+    clock.advance(Duration::hours(1));
+    assert_eq!(limiter.check("ip:10.0.0.1", RouteClass::Write).remaining, 1);
+}
+
+#[test]
+fn test_global_limit_covers_all_clients() {
+    let clock = Arc::new(ManualClock::starting_now());
+    let limiter = RateLimiter::new(policy(10, 60), policy(10, 60))
+        .with_global(Some(policy(2, 60)))
+        .with_clock(clock.clone());
+
+    assert!(limiter.check("ip:10.0.0.1", RouteClass::Read).allowed);
+    assert!(limiter.check("ip:10.0.0.2", RouteClass::Write).allowed);
+    let refused = limiter.check("ip:10.0.0.3", RouteClass::Read);
+    assert!(!refused.allowed);
+    assert_eq!(refused.retry_after_seconds, 1);
+    // The client's own bucket is untouched
+    assert_eq!(refused.remaining, 10);
+
+    clock.advance(Duration::seconds(1));
+    assert!(limiter.check("ip:10.0.0.3", RouteClass::Read).allowed);
+}
+
+#[test]
+fn test_tracked_buckets_stay_bounded() {
+    let clock = Arc::new(ManualClock::starting_now());
+    let limiter = RateLimiter::new(policy(1, 1), policy(1, 1)).with_clock(clock.clone());
+
+    // Every client spends its only token, so none of the buckets is full
+    for client in 0..MAX_TRACKED_BUCKETS + 500 {
+        assert!(limiter.check(&format!("ip:{}", client), RouteClass::Read).allowed);
+    }
+    assert_eq!(limiter.tracked_buckets(), MAX_TRACKED_BUCKETS);
+
+    // The oldest were forgotten and start over with a full bucket, the newest were not
+    assert!(limiter.check("ip:0", RouteClass::Read).allowed);
+    assert!(!limiter.check(&format!("ip:{}", MAX_TRACKED_BUCKETS + 499), RouteClass::Read).allowed);
+    assert_eq!(limiter.tracked_buckets(), MAX_TRACKED_BUCKETS);
+
+    // Once refilled they are swept away
+    clock.advance(Duration::minutes(2));
+    limiter.check("ip:new", RouteClass::Read);
+    assert_eq!(limiter.tracked_buckets(), 1);
+}
+
+#[tokio::test]
+async fn test_buckets_survive_save_and_load() -> Result<()> {
+    let db = create_test_database().await?;
+    let clock = Arc::new(ManualClock::starting_now());
+    let limiter = RateLimiter::new(policy(5, 60), policy(1, 1)).with_clock(clock.clone());
+
+    assert!(limiter.check("user:abc", RouteClass::Write).allowed);
+    limiter.check("user:abc", RouteClass::Read);
+    clock.advance(Duration::seconds(1));
+    // The read bucket is full again and not worth keeping
+    assert_eq!(limiter.save(&db).await?, 1);
+
+    let restarted = RateLimiter::new(policy(5, 60), policy(1, 1)).with_clock(clock.clone());
+    assert_eq!(restarted.load(&db).await?, 1);
+    assert!(!restarted.check("user:abc", RouteClass::Write).allowed);
+
+    // Loading takes the buckets out of storage
+    assert_eq!(RateLimiter::new(policy(5, 60), policy(1, 1)).load(&db).await?, 0);
+
+    Ok(())
+}
+
+async fn create_test_server(config: Config) -> Result<(TestServer, Database)> {
+    let pool = create_test_database().await?;
+    let app = create_app(pool.clone(), config).await?;
+    Ok((TestServer::new(app)?, pool))
+}
+
+fn forwarded_for(ip: &str) -> (HeaderName, HeaderValue) {
+    (HeaderName::from_static("x-forwarded-for"), HeaderValue::from_str(ip).unwrap())
+}
+
+#[tokio::test]
+async fn test_api_answers_429_with_headers() -> Result<()> {
+    let mut config = Config::default();
+    config.server.trust_forwarded_for = true;
+    config.rate_limit.write_burst = 2;
+    config.rate_limit.write_per_minute = 6;
+    let (server, _pool) = create_test_server(config).await?;
+    let (name, value) = forwarded_for("203.0.113.7");
+    let credentials = json!({"username": "testuser", "password": "wrong-password"});
+
+    let response = server
+        .post("/api/v1/auth/login")
+        .add_header(name.clone(), value.clone())
+        .json(&credentials)
+        .await;
+    assert_eq!(response.header("ratelimit-limit"), "2");
+    assert_eq!(response.header("ratelimit-remaining"), "1");
+    assert_eq!(response.header("ratelimit-reset"), "10");
+
+    server
+        .post("/api/v1/auth/login")
+        .add_header(name.clone(), value.clone())
+        .json(&credentials)
+        .await;
+    let response = server
+        .post("/api/v1/auth/login")
+        .add_header(name.clone(), value.clone())
+        .json(&credentials)
+        .await;
+    response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.header("retry-after"), "10");
+    assert_eq!(response.header("ratelimit-remaining"), "0");
+    assert_eq!(response.json::<Value>()["error"], "Too many requests");
+
+    // Reads and other addresses still go through
+    server
+        .get("/api/v1/memos")
+        .add_header(name.clone(), value.clone())
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    let (other_name, other_value) = forwarded_for("203.0.113.8");
+    server
+        .post("/api/v1/auth/login")
+        .add_header(other_name, other_value)
+        .json(&credentials)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // The deprecated paths share the limit, and probes are not limited at all
+    server
+        .post("/api/auth/login")
+        .add_header(name.clone(), value.clone())
+        .json(&credentials)
+        .await
+        .assert_status(StatusCode::TOO_MANY_REQUESTS);
+    let health = server.get("/health").add_header(name, value).await;
+    health.assert_status_ok();
+    assert!(health.maybe_header("ratelimit-limit").is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forged_forwarded_for_entries_share_a_bucket() -> Result<()> {
+    let mut config = Config::default();
+    config.server.trust_forwarded_for = true;
+    config.rate_limit.write_burst = 2;
+    let (server, _pool) = create_test_server(config).await?;
+    let credentials = json!({"username": "testuser", "password": "wrong-password"});
+
+    // A new made-up address each time, in front of the one our proxy appended
+    let mut statuses = Vec::new();
+    for forged in ["6.6.6.1", "6.6.6.2", "6.6.6.3"] {
+        let (name, value) = forwarded_for(&format!("{}, 10.0.0.1", forged));
+        let response = server.post("/api/v1/auth/login").add_header(name, value).json(&credentials).await;
+        statuses.push(response.status_code());
+    }
+    assert_eq!(
+        statuses,
+        [StatusCode::UNAUTHORIZED, StatusCode::UNAUTHORIZED, StatusCode::TOO_MANY_REQUESTS]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_signed_in_users_have_their_own_buckets() -> Result<()> {
+    let mut config = Config::default();
+    config.rate_limit.read_burst = 2;
+    let (server, _pool) = create_test_server(config).await?;
+
+    let credentials = json!({"username": "testuser", "password": "password123"});
+    server.post("/api/v1/auth/register").json(&credentials).await;
+    let response = server.post("/api/v1/auth/login").json(&credentials).await;
+    let session = response.cookies().get("session_id").unwrap().clone().into_owned();
+
+    // Anonymous reads from the same address use up the IP's bucket...
+    for _ in 0..2 {
+        server.get("/api/v1/memos").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+    server.get("/api/v1/memos").await.assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+    // ...but not the user's
+    for _ in 0..2 {
+        server.get("/api/v1/memos").add_cookie(session.clone()).await.assert_status_ok();
+    }
+    server
+        .get("/api/v1/memos")
+        .add_cookie(session)
+        .await
+        .assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limiting_can_be_disabled() -> Result<()> {
+    let mut config = Config::default();
+    config.rate_limit.enabled = false;
+    config.rate_limit.read_burst = 1;
+    let (server, _pool) = create_test_server(config).await?;
+
+    for _ in 0..3 {
+        let response = server.get("/api/v1/memos").await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert!(response.maybe_header("ratelimit-limit").is_none());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use axum_test::TestServer;
+use axum::http::{header, StatusCode};
+use memory_memo::assets;
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use serde_json::json;
+use anyhow::Result;
+
+async fn create_test_server(config: Config) -> Result<TestServer> {
+    let pool = create_test_database().await?;
+    let app = create_app(pool, config).await?;
+    TestServer::new(app)
+}
+
+fn header_str(response: &axum_test::TestResponse, name: header::HeaderName) -> String {
+    response.header(name).to_str().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn test_frontend_gets_strict_policy() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    let response = server.get("/").await;
+    response.assert_status_ok();
+    let policy = header_str(&response, header::CONTENT_SECURITY_POLICY);
+    assert!(policy.contains("script-src 'self';"), "{policy}");
+    assert!(policy.contains("style-src 'self' https://fonts.googleapis.com;"), "{policy}");
+    assert!(policy.contains("font-src https://fonts.gstatic.com;"), "{policy}");
+    assert!(policy.contains("frame-ancestors 'none'"), "{policy}");
+    assert!(policy.contains("report-uri /api/csp-report"), "{policy}");
+    assert!(!policy.contains("unsafe"), "{policy}");
+    assert_eq!(response.header("reporting-endpoints"), "csp=\"/api/csp-report\"");
+    assert_eq!(response.header(header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+    assert_eq!(response.header(header::REFERRER_POLICY), "no-referrer");
+    assert_eq!(response.header(header::X_FRAME_OPTIONS), "DENY");
+    assert!(response.maybe_header(header::STRICT_TRANSPORT_SECURITY).is_none());
+
+    // API responses and errors get them too
+    let response = server.get("/api/v1/memos").await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+    assert_eq!(response.header(header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+    assert!(response.maybe_header(header::CONTENT_SECURITY_POLICY).is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_frontend_has_no_inline_code() {
+    // The strict policy blocks inline scripts, handlers and styles
+    for path in ["index.html", "app.js"] {
+        let body = String::from_utf8_lossy(assets::get(path).unwrap().body);
+        for inline in ["onclick=", "onsubmit=", "onload=", "style=\"", "<script>", "javascript:"] {
+            assert!(!body.contains(inline), "{path} contains {inline}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_swagger_ui_gets_its_own_policy() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    let response = server.get("/api/docs/").await;
+    response.assert_status_ok();
+    let policy = header_str(&response, header::CONTENT_SECURITY_POLICY);
+    assert!(policy.contains("script-src 'self';"), "{policy}");
+    assert!(policy.contains("style-src 'self' 'unsafe-inline';"), "{policy}");
+    assert!(policy.contains("img-src 'self' data:;"), "{policy}");
+
+    // Swagger UI only loads scripts from files, which script-src 'self' allows
+    let page = response.text();
+    assert!(!page.contains("<script>"));
+    server.get("/api/docs/swagger-initializer.js").await.assert_status_ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_headers_follow_the_config() -> Result<()> {
+    let mut config = Config::default();
+    config.server.base_path = "/memory_memo".to_string();
+    config.security_headers.csp_report_only = true;
+    config.security_headers.frame_ancestors = vec!["https://intranet.example.com".to_string()];
+    config.security_headers.referrer_policy = "same-origin".to_string();
+    config.security_headers.hsts = Some(true);
+    config.security_headers.hsts_max_age_seconds = 600;
+    let server = create_test_server(config).await?;
+
+    let response = server.get("/memory_memo/").await;
+    response.assert_status_ok();
+    assert!(response.maybe_header(header::CONTENT_SECURITY_POLICY).is_none());
+    let policy = header_str(&response, header::CONTENT_SECURITY_POLICY_REPORT_ONLY);
+    assert!(policy.contains("frame-ancestors https://intranet.example.com;"), "{policy}");
+    assert!(policy.contains("report-uri /memory_memo/api/csp-report"), "{policy}");
+    assert!(response.maybe_header(header::X_FRAME_OPTIONS).is_none());
+    assert_eq!(response.header(header::REFERRER_POLICY), "same-origin");
+    assert_eq!(response.header(header::STRICT_TRANSPORT_SECURITY), "max-age=600");
+
+    server
+        .post("/memory_memo/api/csp-report")
+        .json(&json!({"csp-report": {}}))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // A custom policy is sent as is
+    let mut config = Config::default();
+    config.security_headers.content_security_policy = Some("default-src 'self'".to_string());
+    let server = create_test_server(config).await?;
+    assert_eq!(server.get("/").await.header(header::CONTENT_SECURITY_POLICY), "default-src 'self'");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_csp_reports_are_accepted() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    // report-uri sends one report
+    let report = json!({"csp-report": {
+        "document-uri": "http://localhost:3000/",
+        "violated-directive": "script-src-elem",
+        "blocked-uri": "inline",
+    }});
+    server
+        .post("/api/csp-report")
+        .bytes(report.to_string().into())
+        .content_type("application/csp-report")
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // report-to sends a list
+    let reports = json!([{"type": "csp-violation", "body": {
+        "documentURL": "http://localhost:3000/",
+        "effectiveDirective": "style-src-elem",
+        "blockedURL": "https://cdn.example.com/style.css",
+    }}]);
+    server
+        .post("/api/csp-report")
+        .bytes(reports.to_string().into())
+        .content_type("application/reports+json")
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    server
+        .post("/api/csp-report")
+        .text("not json")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    server
+        .post("/api/csp-report")
+        .text("x".repeat(64 * 1024))
+        .await
+        .assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_csp_reports_are_rate_limited() -> Result<()> {
+    let mut config = Config::default();
+    config.rate_limit.write_burst = 2;
+    let server = create_test_server(config).await?;
+
+    for _ in 0..2 {
+        let response = server.post("/api/csp-report").json(&json!({"csp-report": {}})).await;
+        response.assert_status(StatusCode::NO_CONTENT);
+        assert!(response.maybe_header("ratelimit-limit").is_some());
+    }
+    server
+        .post("/api/csp-report")
+        .json(&json!({"csp-report": {}}))
+        .await
+        .assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_security_headers_can_be_disabled() -> Result<()> {
+    let mut config = Config::default();
+    config.security_headers.enabled = false;
+    let server = create_test_server(config).await?;
+
+    let response = server.get("/").await;
+    assert!(response.maybe_header(header::CONTENT_SECURITY_POLICY).is_none());
+    assert!(response.maybe_header(header::X_CONTENT_TYPE_OPTIONS).is_none());
+    // Falls through to the static files
+    server
+        .post("/api/csp-report")
+        .json(&json!({"csp-report": {}}))
+        .await
+        .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use memory_memo::clock::{Clock, ManualClock};
+use memory_memo::services::session::{SessionPolicy, SessionStore};
+use chrono::Duration;
+
+fn short_policy() -> SessionPolicy {
+    SessionPolicy {
+        idle_timeout: Duration::milliseconds(200),
+        absolute_timeout: Duration::hours(1),
+        remember_me_timeout: Duration::days(30),
+    }
+}
+
+#[tokio::test]
+async fn test_session_idle_expiry_slides_on_activity() {
+    let clock = Arc::new(ManualClock::starting_now());
+    let store = SessionStore::with_policy(short_policy()).with_clock(clock.clone());
+    let (session_id, session) = store.create_session("user-1".to_string(), false);
+    let initial_expiry = session.expires_at;
+
+    // Activity before the idle timeout keeps the session alive and pushes expiry forward
+    clock.advance(Duration::milliseconds(120));
+    let touched = store.touch_session(&session_id).unwrap();
+    assert!(touched.expires_at > initial_expiry);
+
+    clock.advance(Duration::milliseconds(120));
+    assert!(store.touch_session(&session_id).is_some());
+
+    // Going idle for longer than the timeout ends the session
+    clock.advance(Duration::milliseconds(250));
+    assert!(store.touch_session(&session_id).is_none());
+    assert!(store.get_session(&session_id).is_none());
+}
+
+#[tokio::test]
+async fn test_session_idle_expiry_capped_by_absolute_timeout() {
+    let policy = SessionPolicy {
+        idle_timeout: Duration::hours(2),
+        absolute_timeout: Duration::hours(1),
+        remember_me_timeout: Duration::days(30),
+    };
+    let store = SessionStore::with_policy(policy);
+    let (session_id, session) = store.create_session("user-1".to_string(), false);
+
+    assert_eq!(session.expires_at, session.absolute_expires_at);
+    let touched = store.touch_session(&session_id).unwrap();
+    assert_eq!(touched.expires_at, session.absolute_expires_at);
+}
+
+#[tokio::test]
+async fn test_remember_me_session_is_long_lived() {
+    let clock = Arc::new(ManualClock::starting_now());
+    let store = SessionStore::with_policy(short_policy()).with_clock(clock.clone());
+    let (session_id, session) = store.create_session("user-1".to_string(), true);
+
+    assert!(session.remember_me);
+    assert_eq!(session.absolute_expires_at, clock.now() + Duration::days(30));
+
+    // Remember-me sessions are not subject to the short idle timeout
+    clock.advance(Duration::days(29));
+    assert!(store.touch_session(&session_id).is_some());
+
+    // but still end at their absolute expiry
+    clock.advance(Duration::days(2));
+    assert!(store.touch_session(&session_id).is_none());
+}
+
+#[tokio::test]
+async fn test_sessions_get_distinct_ids_and_csrf_tokens() {
+    let store = SessionStore::new();
+    let (first_id, first) = store.create_session("user-1".to_string(), false);
+    let (second_id, second) = store.create_session("user-1".to_string(), false);
+
+    assert_ne!(first_id, second_id);
+    assert_ne!(first.csrf_token, second.csrf_token);
+}
+
+#[tokio::test]
+async fn test_sessions_survive_save_and_load() -> anyhow::Result<()> {
+    let pool = memory_memo::database::create_test_database().await?;
+    let user = memory_memo::models::User::create(&pool, "testuser", "password123").await?;
+
+    let store = SessionStore::with_policy(short_policy());
+    let (kept_id, kept) = store.create_session(user.id.clone(), true);
+    let (expired_id, _) = store.create_session(user.id.clone(), false);
+
+    // Let the short idle timeout lapse for the regular session only
+    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    assert_eq!(store.save(&pool).await?, 1);
+
+    let restarted = SessionStore::with_policy(short_policy());
+    assert_eq!(restarted.load(&pool).await?, 1);
+
+    let restored = restarted.get_active_session(&kept_id).unwrap();
+    assert_eq!(restored.user_id, user.id);
+    assert_eq!(restored.csrf_token, kept.csrf_token);
+    assert!(restored.remember_me);
+    // PostgreSQL keeps timestamps to the microsecond
+    assert_eq!(
+        restored.absolute_expires_at.timestamp_micros(),
+        kept.absolute_expires_at.timestamp_micros()
+    );
+    assert!(restarted.get_session(&expired_id).is_none());
+
+    // Loading consumes the snapshot so stale sessions cannot be restored twice
+    let again = SessionStore::with_policy(short_policy());
+    assert_eq!(again.load(&pool).await?, 0);
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+use axum::{routing::get, Router};
+use memory_memo::shutdown::serve_with_shutdown;
+use anyhow::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+fn slow_app(delay: Duration) -> Router {
+    Router::new().route("/slow", get(move || async move {
+        tokio::time::sleep(delay).await;
+        "done"
+    }))
+}
+
+/// Send a bare HTTP/1.1 request and return the raw response
+async fn request(addr: std::net::SocketAddr, path: &str) -> Result<String> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).as_bytes())
+        .await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(response)
+}
+
+#[tokio::test]
+async fn test_in_flight_requests_drain_before_shutdown() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let shutdown = CancellationToken::new();
+    let (signal_tx, signal_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let server = tokio::spawn(serve_with_shutdown(
+        listener,
+        slow_app(Duration::from_millis(300)),
+        async { signal_rx.await.ok(); },
+        shutdown.clone(),
+        Duration::from_secs(5),
+    ));
+
+    let in_flight = tokio::spawn(request(addr, "/slow"));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    signal_tx.send(()).unwrap();
+
+    // The request started before the signal still completes
+    let response = in_flight.await??;
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.ends_with("done"));
+
+    assert!(server.await??);
+    assert!(shutdown.is_cancelled());
+
+    // New connections are refused once shut down
+    assert!(TcpStream::connect(addr).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_gives_up_after_timeout() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let shutdown = CancellationToken::new();
+
+    let server = tokio::spawn(serve_with_shutdown(
+        listener,
+        slow_app(Duration::from_secs(60)),
+        std::future::pending(),
+        shutdown.clone(),
+        Duration::from_millis(200),
+    ));
+
+    let _stuck = tokio::spawn(request(addr, "/slow"));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Cancelling the token directly also triggers shutdown
+    shutdown.cancel();
+    let drained = tokio::time::timeout(Duration::from_secs(2), server).await???;
+    assert!(!drained);
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use axum_test::TestServer;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use memory_memo::assets;
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use anyhow::Result;
+
+async fn create_test_server(config: Config) -> Result<TestServer> {
+    let pool = create_test_database().await?;
+    let app = create_app(pool, config).await?;
+    TestServer::new(app)
+}
+
+fn accept_encoding(value: &'static str) -> (HeaderName, HeaderValue) {
+    (HeaderName::from_static("accept-encoding"), HeaderValue::from_static(value))
+}
+
+#[test]
+fn test_frontend_is_embedded() {
+    let paths: Vec<_> = assets::paths().collect();
+    for expected in ["index.html", "app.js", "styles.css"] {
+        assert!(paths.contains(&expected), "{} is not embedded", expected);
+    }
+}
+
+#[test]
+fn test_hashed_asset_names() {
+    assert!(assets::is_hashed("app.3f9c2a1b.js"));
+    assert!(assets::is_hashed("css/styles.0123456789abcdef.css"));
+    assert!(!assets::is_hashed("app.js"));
+    assert!(!assets::is_hashed("app.min.js"));
+    assert!(!assets::is_hashed("3f9c2a1b.js"));
+}
+
+#[tokio::test]
+async fn test_hashed_assets_are_cached_for_good() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    // The embedded index.html loads the script and stylesheet by their hashed names
+    let index = server.get("/").await.text();
+    for original in ["app.js", "styles.css"] {
+        let (stem, extension) = original.split_once('.').unwrap();
+        let path = assets::paths()
+            .find(|path| assets::is_hashed(path) && path.starts_with(stem) && path.ends_with(extension))
+            .unwrap_or_else(|| panic!("{original} has no hashed name"));
+        assert!(index.contains(&format!("\"{}\"", path)), "index.html does not load {path}");
+        assert!(!index.contains(&format!("\"{}\"", original)), "index.html still loads {original}");
+        assert_eq!(assets::get(path).unwrap().body, assets::get(original).unwrap().body);
+
+        let response = server.get(&format!("/{}", path)).await;
+        response.assert_status_ok();
+        assert_eq!(response.header("cache-control"), "public, max-age=31536000, immutable", "{path}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_embedded_assets_are_served_with_content_types() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    for (path, content_type) in [
+        ("/", "text/html; charset=utf-8"),
+        ("/index.html", "text/html; charset=utf-8"),
+        ("/app.js", "text/javascript; charset=utf-8"),
+        ("/styles.css", "text/css; charset=utf-8"),
+    ] {
+        let response = server.get(path).await;
+        response.assert_status_ok();
+        assert_eq!(response.header("content-type"), content_type, "{}", path);
+        // Unhashed names must be revalidated, not cached blindly
+        assert_eq!(response.header("cache-control"), "no-cache", "{}", path);
+    }
+
+    server.get("/missing.js").await.assert_status(StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_embedded_assets_support_etag_revalidation() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    let response = server.get("/app.js").await;
+    let etag = response.header("etag");
+    assert!(etag.to_str()?.starts_with('"'));
+    assert_eq!(response.as_bytes().as_ref(), assets::get("app.js").unwrap().body);
+
+    let response = server
+        .get("/app.js")
+        .add_header(HeaderName::from_static("if-none-match"), etag.clone())
+        .await;
+    response.assert_status(StatusCode::NOT_MODIFIED);
+    assert!(response.as_bytes().is_empty());
+    assert_eq!(response.header("etag"), etag);
+
+    // A stale ETag gets the full body
+    server
+        .get("/app.js")
+        .add_header(HeaderName::from_static("if-none-match"), HeaderValue::from_static("\"stale\""))
+        .await
+        .assert_status_ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_embedded_assets_are_precompressed() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+    let asset = assets::get("app.js").unwrap();
+    let (name, value) = accept_encoding("gzip, deflate, br");
+
+    let response = server.get("/app.js").add_header(name, value).await;
+    response.assert_status_ok();
+    assert_eq!(response.header("content-encoding"), "br");
+    assert_eq!(response.header("vary"), "accept-encoding");
+    assert_eq!(response.as_bytes().as_ref(), asset.brotli.unwrap());
+    let brotli_etag = response.header("etag");
+
+    let (name, value) = accept_encoding("gzip;q=1.0, br;q=0");
+    let response = server.get("/app.js").add_header(name, value).await;
+    assert_eq!(response.header("content-encoding"), "gzip");
+    assert_eq!(response.as_bytes().as_ref(), asset.gzip.unwrap());
+    // Different encodings are different representations
+    assert_ne!(response.header("etag"), brotli_etag);
+
+    let response = server.get("/app.js").await;
+    assert!(response.maybe_header("content-encoding").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_static_dir_serves_from_disk() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("memory_memo_frontend_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("index.html"), "<p>from disk</p>")?;
+    std::fs::write(dir.join("extra.txt"), "not embedded")?;
+
+    let mut config = Config::default();
+    config.server.static_dir = Some(dir.clone());
+    let server = create_test_server(config).await?;
+
+    assert_eq!(server.get("/").await.text(), "<p>from disk</p>");
+    assert_eq!(server.get("/extra.txt").await.text(), "not embedded");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
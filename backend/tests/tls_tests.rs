@@ -0,0 +1,209 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::http::{header, HeaderValue, StatusCode, Version};
+use axum_test::TestServer;
+use http_body_util::Empty;
+use hyper::body::Bytes;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use memory_memo::config::{Config, TlsConfig};
+use memory_memo::create_app;
+use memory_memo::database::create_test_database;
+use memory_memo::shutdown::serve_tls_with_shutdown;
+use memory_memo::tls::{redirect_router, TlsCertificate};
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{client::TlsStream, TlsConnector};
+use tokio_util::sync::CancellationToken;
+use anyhow::Result;
+
+/// Certificate and key files in a scratch directory
+struct TestCertificate {
+    dir: PathBuf,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    /// The first certificate written, in DER
+    der: Vec<u8>,
+}
+
+impl TestCertificate {
+    fn new() -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!("memory_memo_tls_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir)?;
+        let mut certificate = Self {
+            cert_path: dir.join("cert.pem"),
+            key_path: dir.join("key.pem"),
+            dir,
+            der: Vec::new(),
+        };
+        certificate.der = certificate.renew()?;
+        Ok(certificate)
+    }
+
+    /// Write a new self-signed certificate for localhost, returning it in DER
+    fn renew(&self) -> Result<Vec<u8>> {
+        let generated = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        std::fs::write(&self.key_path, generated.key_pair.serialize_pem())?;
+        std::fs::write(&self.cert_path, generated.cert.pem())?;
+        Ok(generated.cert.der().to_vec())
+    }
+
+    fn config(&self) -> TlsConfig {
+        TlsConfig {
+            cert_path: Some(self.cert_path.clone()),
+            key_path: Some(self.key_path.clone()),
+            ..TlsConfig::default()
+        }
+    }
+}
+
+impl Drop for TestCertificate {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Open a TLS connection trusting only `trusted`, offering the given ALPN protocols
+async fn connect(addr: SocketAddr, trusted: &[u8], alpn: &[&[u8]]) -> Result<TlsStream<TcpStream>> {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let mut roots = RootCertStore::empty();
+    roots.add(CertificateDer::from(trusted.to_vec()))?;
+    let mut config = ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+
+    let stream = TcpStream::connect(addr).await?;
+    let server_name = ServerName::try_from("localhost")?;
+    Ok(TlsConnector::from(Arc::new(config)).connect(server_name, stream).await?)
+}
+
+/// The certificate the server presents to a new connection
+async fn served_certificate(addr: SocketAddr, trusted: &[u8]) -> Option<Vec<u8>> {
+    let stream = connect(addr, trusted, &[b"http/1.1"]).await.ok()?;
+    let certificates = stream.get_ref().1.peer_certificates()?;
+    Some(certificates[0].to_vec())
+}
+
+#[tokio::test]
+async fn test_https_serves_http1_and_http2() -> Result<()> {
+    let certificate = TestCertificate::new()?;
+    let mut config = Config::default();
+    config.tls = certificate.config();
+    let app = create_app(create_test_database().await?, config).await?;
+    let tls = TlsCertificate::load(&certificate.config()).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let shutdown = CancellationToken::new();
+    let server = tokio::spawn(serve_tls_with_shutdown(
+        listener,
+        tls.rustls_config(),
+        app,
+        std::future::pending(),
+        shutdown.clone(),
+        Duration::from_secs(5),
+    ));
+    let trusted = certificate.der.clone();
+
+    // HTTP/1.1
+    let mut stream = connect(addr, &trusted, &[b"http/1.1"]).await?;
+    assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"http/1.1"[..]));
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    // Serving HTTPS turns HSTS on by default
+    assert!(response.contains("strict-transport-security: max-age=31536000"), "{response}");
+
+    // HTTP/2, negotiated through ALPN
+    let stream = connect(addr, &trusted, &[b"h2", b"http/1.1"]).await?;
+    assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+    let (mut sender, connection) =
+        hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream)).await?;
+    tokio::spawn(connection);
+    let request = hyper::Request::get("https://localhost/health/live").body(Empty::<Bytes>::new())?;
+    let response = sender.send_request(request).await?;
+    assert_eq!(response.version(), Version::HTTP_2);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    shutdown.cancel();
+    assert!(server.await??);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_renewed_certificate_is_picked_up() -> Result<()> {
+    let certificate = TestCertificate::new()?;
+    let tls = TlsCertificate::load(&certificate.config()).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let shutdown = CancellationToken::new();
+    tokio::spawn(serve_tls_with_shutdown(
+        listener,
+        tls.rustls_config(),
+        axum::Router::new(),
+        std::future::pending(),
+        shutdown.clone(),
+        Duration::from_secs(1),
+    ));
+    let reloader = tls.spawn_reloader(Duration::from_millis(50), shutdown.clone());
+    let original = certificate.der.clone();
+    assert_eq!(served_certificate(addr, &original).await, Some(original.clone()));
+
+    // A broken file is ignored and the old certificate stays
+    std::fs::write(&certificate.cert_path, "not a certificate")?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(served_certificate(addr, &original).await, Some(original.clone()));
+
+    let renewed = certificate.renew()?;
+    let mut served = None;
+    for _ in 0..40 {
+        served = served_certificate(addr, &renewed).await;
+        if served.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(served, Some(renewed));
+
+    shutdown.cancel();
+    reloader.await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unusable_certificate_is_rejected() -> Result<()> {
+    let certificate = TestCertificate::new()?;
+    std::fs::write(&certificate.key_path, "not a key")?;
+    let err = TlsCertificate::load(&certificate.config()).await.err().expect("an error");
+    assert!(err.to_string().contains("Failed to load TLS certificate"), "{err}");
+
+    assert!(TlsCertificate::load(&TlsConfig::default()).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_is_redirected_to_https() -> Result<()> {
+    let host = |value: &'static str| (header::HOST, HeaderValue::from_static(value));
+
+    let server = TestServer::new(redirect_router(8443))?;
+    let (name, value) = host("example.com:8080");
+    let response = server.get("/api/v1/memos").add_query_param("x", "1").add_header(name, value).await;
+    response.assert_status(StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.header(header::LOCATION), "https://example.com:8443/api/v1/memos?x=1");
+
+    // The default port is left out, and methods are kept
+    let server = TestServer::new(redirect_router(443))?;
+    let (name, value) = host("[::1]:80");
+    let response = server.post("/api/v1/auth/login").add_header(name, value).await;
+    response.assert_status(StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.header(header::LOCATION), "https://[::1]/api/v1/auth/login");
+
+    let (name, value) = host("bad host");
+    server.get("/").add_header(name, value).await.assert_status(StatusCode::BAD_REQUEST);
+    Ok(())
+}
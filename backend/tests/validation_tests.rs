@@ -0,0 +1,156 @@
+use axum_test::TestServer;
+use axum::http::{header, HeaderName, HeaderValue, StatusCode};
+use memory_memo::create_app;
+use memory_memo::config::Config;
+use memory_memo::database::create_test_database;
+use memory_memo::validation::Validation;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+async fn create_test_server(config: Config) -> Result<TestServer> {
+    let pool = create_test_database().await?;
+    let app = create_app(pool, config).await?;
+    let mut server = TestServer::new(app)?;
+    server.do_save_cookies();
+    Ok(server)
+}
+
+async fn log_in(server: &mut TestServer) -> Result<()> {
+    let credentials = json!({"username": "testuser", "password": "password123"});
+    server.post("/api/v1/auth/register").json(&credentials).await;
+    let response = server.post("/api/v1/auth/login").json(&credentials).await;
+    let csrf_token = response.json::<Value>()["csrf_token"].as_str().unwrap().to_owned();
+    server.add_header(HeaderName::from_static("x-csrf-token"), HeaderValue::from_str(&csrf_token)?);
+    Ok(())
+}
+
+fn field_codes(body: &Value) -> Vec<(String, String)> {
+    body["fields"]
+        .as_array()
+        .expect("fields")
+        .iter()
+        .map(|error| (error["field"].as_str().unwrap().to_string(), error["code"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_register_reports_every_field_error() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    let response = server
+        .post("/api/v1/auth/register")
+        .json(&json!({"username": "ab", "password": "short"}))
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response.json::<Value>();
+    assert_eq!(
+        field_codes(&body),
+        vec![
+            ("username".to_string(), "too_short".to_string()),
+            ("password".to_string(), "too_short".to_string()),
+        ]
+    );
+    assert_eq!(
+        body["error"],
+        "Username must be at least 3 characters; Password must be at least 8 characters"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lengths_count_characters_not_bytes() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    // 50 characters is allowed even though it is 150 bytes
+    let username = "あ".repeat(50);
+    server
+        .post("/api/v1/auth/register")
+        .json(&json!({"username": username, "password": "パスワード長いです"}))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let response = server
+        .post("/api/v1/auth/register")
+        .json(&json!({"username": "あ".repeat(51), "password": "password123"}))
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(field_codes(&response.json::<Value>()), vec![("username".to_string(), "too_long".to_string())]);
+
+    // Three characters, even though "ab" plus one kanji is five bytes
+    server
+        .post("/api/v1/auth/register")
+        .json(&json!({"username": "ab漢", "password": "password123"}))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memo_content_limit_is_configurable() -> Result<()> {
+    let mut config = Config::default();
+    config.memo.max_content_chars = 10;
+    let mut server = create_test_server(config).await?;
+    log_in(&mut server).await?;
+
+    server
+        .post("/api/v1/memos/forever")
+        .json(&json!({"content": "十文字ちょうどのメモ"}))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let response = server
+        .post("/api/v1/memos/flush")
+        .json(&json!({"content": "十一文字を超えるメモです"}))
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response.json::<Value>();
+    assert_eq!(field_codes(&body), vec![("content".to_string(), "too_long".to_string())]);
+    assert_eq!(body["error"], "Content must be at most 10 characters");
+
+    let response = server.post("/api/v1/memos/forever").json(&json!({"content": "   "})).await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(field_codes(&response.json::<Value>()), vec![("content".to_string(), "required".to_string())]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_malformed_json_gets_a_json_error() -> Result<()> {
+    let server = create_test_server(Config::default()).await?;
+
+    let response = server
+        .post("/api/v1/auth/login")
+        .content_type("application/json")
+        .bytes("{\"username\": ".into())
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    assert!(response.header(header::CONTENT_TYPE).to_str()?.starts_with("application/json"));
+    assert!(response.json::<Value>()["error"].as_str().unwrap().contains("parse"));
+
+    // Well-formed JSON of the wrong shape
+    let response = server.post("/api/v1/auth/login").json(&json!({"username": 42})).await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.json::<Value>()["error"].is_string());
+
+    let response = server.post("/api/v1/auth/login").text("username=testuser").await;
+    response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(response.json::<Value>()["error"].is_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_validation_rules() {
+    assert!(Validation::new().username("username", "abc").finish().is_ok());
+    assert!(Validation::new().new_password("password", "12345678").finish().is_ok());
+
+    let errors = Validation::new()
+        .new_password("password", &"x".repeat(1025))
+        .memo_content("content", "", 100)
+        .finish()
+        .unwrap_err();
+    let codes: Vec<_> = errors.fields.iter().map(|error| (error.field.as_str(), error.code)).collect();
+    assert_eq!(codes, vec![("password", "too_long"), ("content", "required")]);
+}
@@ -2,7 +2,9 @@ class MemoryMemoApp {
     constructor() {
         this.currentUser = null;
         this.memos = { forever_memos: [], flush_memos: [] };
-        this.apiBase = '/api';
+        // Served by the backend; carries the path prefix the app is mounted under
+        const config = window.MEMORY_MEMO_CONFIG || { basePath: '' };
+        this.apiBase = `${config.basePath}/api/v1`;
         
         this.init();
     }
@@ -25,6 +27,11 @@ class MemoryMemoApp {
         document.getElementById('logout-btn').addEventListener('click', () => this.handleLogout());
         document.getElementById('memo-form').addEventListener('submit', (e) => this.handleCreateMemo(e));
         
+        // Memo buttons; listeners instead of inline onclick, which the CSP blocks
+        for (const listId of ['forever-memos', 'flush-memos']) {
+            document.getElementById(listId).addEventListener('click', (e) => this.handleMemoAction(e));
+        }
+        
         // Modal events
         document.getElementById('close-modal').addEventListener('click', () => this.hideModal());
         document.getElementById('cancel-edit').addEventListener('click', () => this.hideModal());
@@ -38,6 +45,19 @@ class MemoryMemoApp {
         });
     }
     
+    handleMemoAction(e) {
+        const button = e.target.closest('button[data-action]');
+        if (!button) {
+            return;
+        }
+        const { action, id, type } = button.dataset;
+        if (action === 'edit') {
+            this.editMemo(id, type);
+        } else if (action === 'delete') {
+            this.deleteMemo(id, type);
+        }
+    }
+    
     switchTab(tab) {
         const loginTab = document.getElementById('login-tab');
         const registerTab = document.getElementById('register-tab');
@@ -81,6 +101,12 @@ class MemoryMemoApp {
         this.loadMemos();
     }
     
+    // Token the server expects in X-CSRF-Token on POST/PUT/DELETE requests
+    csrfHeaders() {
+        const match = document.cookie.match(/(?:^|;\s*)csrf_token=([^;]*)/);
+        return match ? { 'X-CSRF-Token': decodeURIComponent(match[1]) } : {};
+    }
+    
     showLoading() {
         document.getElementById('loading-overlay').classList.remove('hidden');
     }
@@ -106,6 +132,7 @@ class MemoryMemoApp {
         
         const username = document.getElementById('login-username').value.trim();
         const password = document.getElementById('login-password').value;
+        const remember_me = document.getElementById('login-remember-me').checked;
         
         if (!username || !password) {
             this.showError('ユーザー名とパスワードを入力してください。');
@@ -122,7 +149,7 @@ class MemoryMemoApp {
                     'Content-Type': 'application/json',
                 },
                 credentials: 'include',
-                body: JSON.stringify({ username, password })
+                body: JSON.stringify({ username, password, remember_me })
             });
             
             if (response.ok) {
@@ -202,6 +229,7 @@ class MemoryMemoApp {
             
             await fetch(`${this.apiBase}/auth/logout`, {
                 method: 'POST',
+                headers: this.csrfHeaders(),
                 credentials: 'include'
             });
             
@@ -262,8 +290,8 @@ class MemoryMemoApp {
                     <span>永続</span>
                 </div>
                 <div class="memo-actions">
-                    <button class="btn-edit" onclick="app.editMemo('${memo.id}', 'forever')">編集</button>
-                    <button class="btn-delete" onclick="app.deleteMemo('${memo.id}', 'forever')">削除</button>
+                    <button class="btn-edit" data-action="edit" data-id="${memo.id}" data-type="forever">編集</button>
+                    <button class="btn-delete" data-action="delete" data-id="${memo.id}" data-type="forever">削除</button>
                 </div>
             </div>
         `).join('');
@@ -285,7 +313,7 @@ class MemoryMemoApp {
                     <span class="memo-expires">期限: ${this.formatDate(memo.expires_at)}</span>
                 </div>
                 <div class="memo-actions">
-                    <button class="btn-delete" onclick="app.deleteMemo('${memo.id}', 'flush')">削除</button>
+                    <button class="btn-delete" data-action="delete" data-id="${memo.id}" data-type="flush">削除</button>
                 </div>
             </div>
         `).join('');
@@ -309,6 +337,7 @@ class MemoryMemoApp {
                 method: 'POST',
                 headers: {
                     'Content-Type': 'application/json',
+                    ...this.csrfHeaders(),
                 },
                 credentials: 'include',
                 body: JSON.stringify({ content })
@@ -360,6 +389,7 @@ class MemoryMemoApp {
                 method: 'PUT',
                 headers: {
                     'Content-Type': 'application/json',
+                    ...this.csrfHeaders(),
                 },
                 credentials: 'include',
                 body: JSON.stringify({ content })
@@ -389,6 +419,7 @@ class MemoryMemoApp {
             
             const response = await fetch(`${this.apiBase}/memos/${type}/${memoId}`, {
                 method: 'DELETE',
+                headers: this.csrfHeaders(),
                 credentials: 'include'
             });
             
@@ -33,9 +33,17 @@ print_info "Creating database backup..."
 if ! docker ps | grep -q $CONTAINER_NAME; then
     print_info "Container not running, looking for database file..."
     
-    # Try to backup local database
+    # Snapshot the local database online; copying the file while the server
+    # writes to it can produce a corrupt backup
     if [ -f "backend/memory_memo.db" ]; then
-        cp backend/memory_memo.db "$BACKUP_DIR/memory_memo_backup_$DATE.db"
+        if [ -x "backend/target/release/memory_memo" ]; then
+            (cd backend && ./target/release/memory_memo backup --output "../$BACKUP_DIR/memory_memo_backup_$DATE.db")
+        elif command -v sqlite3 > /dev/null; then
+            sqlite3 backend/memory_memo.db "VACUUM INTO '$BACKUP_DIR/memory_memo_backup_$DATE.db'"
+        else
+            echo "Build the server (cargo build --release) or install sqlite3 to take a backup"
+            exit 1
+        fi
         print_status "Local database backed up to $BACKUP_DIR/memory_memo_backup_$DATE.db"
     else
         echo "No database file found to backup"
@@ -43,7 +51,7 @@ if ! docker ps | grep -q $CONTAINER_NAME; then
     fi
 else
     # Backup from Docker container
-    docker exec $CONTAINER_NAME sqlite3 /app/data/memory_memo.db ".backup /tmp/backup.db"
+    docker exec $CONTAINER_NAME ./memory_memo backup --output /tmp/backup.db
     docker cp $CONTAINER_NAME:/tmp/backup.db "$BACKUP_DIR/memory_memo_backup_$DATE.db"
     docker exec $CONTAINER_NAME rm /tmp/backup.db
     
@@ -52,7 +52,7 @@ print_info "Restoring from: $BACKUP_FILE"
 
 # Confirmation
 print_warning "This will overwrite the current database!"
-print_warning "Make sure to backup current data if needed."
+print_warning "The current data is saved to the backup directory first."
 echo -n "Continue? (y/N): "
 read -r CONFIRM
 
@@ -61,41 +61,25 @@ if [ "$CONFIRM" != "y" ] && [ "$CONFIRM" != "Y" ]; then
     exit 0
 fi
 
-# Check if container is running
+# The server restores online: the snapshot is checked (integrity and schema
+# version), the current data is backed up, then replaced in one transaction
 if docker ps | grep -q $CONTAINER_NAME; then
-    print_info "Stopping application container..."
-    docker-compose down
-    
-    # Wait a moment
-    sleep 2
-    
     print_info "Restoring database in container..."
-    
-    # Start container with temporary override
-    docker-compose up -d
-    
-    # Wait for container to be ready
-    sleep 5
-    
-    # Copy backup to container and restore
+
     docker cp "$BACKUP_FILE" $CONTAINER_NAME:/tmp/restore.db
-    docker exec $CONTAINER_NAME sh -c "rm -f /app/data/memory_memo.db && cp /tmp/restore.db /app/data/memory_memo.db"
+    docker exec $CONTAINER_NAME ./memory_memo backup restore /tmp/restore.db
     docker exec $CONTAINER_NAME rm /tmp/restore.db
-    
-    # Restart container to reload database
-    docker-compose restart
-    
+
     print_status "Database restored in container"
-    
-else
-    # Restore to local file
-    if [ -f "backend/memory_memo.db" ]; then
-        print_info "Backing up current local database..."
-        mv backend/memory_memo.db "backend/memory_memo.db.backup.$(date +%Y%m%d_%H%M%S)"
-    fi
-    
-    cp "$BACKUP_FILE" backend/memory_memo.db
+
+elif [ -x "backend/target/release/memory_memo" ]; then
+    BACKUP_PATH=$(cd "$(dirname "$BACKUP_FILE")" && pwd)/$(basename "$BACKUP_FILE")
+    (cd backend && ./target/release/memory_memo backup restore "$BACKUP_PATH")
     print_status "Database restored locally"
+
+else
+    print_error "Build the server first (cargo build --release)"
+    exit 1
 fi
 
 print_info "Verifying restore..."